@@ -0,0 +1,298 @@
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::Path;
+
+use clap::ValueEnum;
+use migadu_client::import::{Conflict, ConflictStrategy, ImportPlan};
+use migadu_client::plan::{ApplyReport, Change, ChangeAction, DesiredState, Plan};
+use migadu_client::MigaduClient;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::confirm;
+use crate::diff;
+use crate::files;
+use crate::output::{cell, print_list, OutputFormat, Record};
+use crate::password::BulkPasswords;
+
+/// Command-line names for [`ConflictStrategy`].
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum OnConflict {
+    /// Import nothing if any resource already exists
+    #[default]
+    Fail,
+    /// Leave existing resources unchanged
+    Skip,
+    /// Update existing resources to match the snapshot (passwords are kept)
+    Overwrite,
+}
+
+impl From<OnConflict> for ConflictStrategy {
+    fn from(value: OnConflict) -> Self {
+        match value {
+            OnConflict::Fail => ConflictStrategy::Fail,
+            OnConflict::Skip => ConflictStrategy::Skip,
+            OnConflict::Overwrite => ConflictStrategy::Overwrite,
+        }
+    }
+}
+
+/// A field a change sets, as printed with `--output csv` or `tsv`.
+#[derive(Serialize)]
+struct ChangeRow {
+    action: &'static str,
+    resource: String,
+    domain: String,
+    id: String,
+    mailbox: Option<String>,
+    field: String,
+    current: Value,
+    new: Value,
+}
+
+impl Record for ChangeRow {
+    const COLUMNS: &'static [&'static str] = &[
+        "action", "resource", "domain", "id", "mailbox", "field", "current", "new",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.action.to_string(),
+            self.resource.clone(),
+            self.domain.clone(),
+            self.id.clone(),
+            self.mailbox.clone().unwrap_or_default(),
+            self.field.clone(),
+            cell(&self.current),
+            cell(&self.new),
+        ]
+    }
+}
+
+/// One row per field each change sets, or a single row for a change that sets none,
+/// such as a delete.
+fn change_rows<'a>(changes: impl IntoIterator<Item = &'a Change>) -> Vec<ChangeRow> {
+    let mut rows = Vec::new();
+    for change in changes {
+        let row = |field: String, current: Value, new: Value| ChangeRow {
+            action: match change.action {
+                ChangeAction::Create => "create",
+                ChangeAction::Update => "update",
+                ChangeAction::Delete => "delete",
+            },
+            resource: change.resource.to_string(),
+            domain: change.domain.clone(),
+            id: change.id.clone(),
+            mailbox: change.mailbox.clone(),
+            field,
+            current,
+            new,
+        };
+        if change.fields.is_empty() {
+            rows.push(row(String::new(), Value::Null, Value::Null));
+        }
+        for field in &change.fields {
+            rows.push(row(
+                field.field.clone(),
+                field.old.clone(),
+                field.new.clone(),
+            ));
+        }
+    }
+    rows
+}
+
+/// A row for a resource an import leaves alone because it already exists.
+///
+/// Conflicts do not record their domain; `id` is an address for all but rewrites.
+fn skipped_row(conflict: &Conflict) -> ChangeRow {
+    ChangeRow {
+        action: "skip",
+        resource: conflict.resource.to_string(),
+        domain: String::new(),
+        id: conflict.id.clone(),
+        mailbox: conflict.mailbox.clone(),
+        field: String::new(),
+        current: Value::Null,
+        new: Value::Null,
+    }
+}
+
+/// Reads a desired-state file, choosing the parser from its extension.
+pub fn load(path: &Path) -> Result<DesiredState, Box<dyn Error>> {
+    files::read(path, "desired-state file")
+}
+
+/// Prints a plan, with field-level changes in table format and one row per field in
+/// CSV and TSV.
+pub fn print_plan(format: OutputFormat, plan: &Plan) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(plan)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(plan)?),
+        OutputFormat::Csv | OutputFormat::Tsv => print_list(format, &change_rows(&plan.changes))?,
+        OutputFormat::Table => write_plan(&mut io::stdout().lock(), plan)?,
+    }
+    Ok(())
+}
+
+/// Writes a plan as text: one line per change followed by the fields it sets.
+pub fn write_plan(out: &mut impl Write, plan: &Plan) -> io::Result<()> {
+    for change in &plan.changes {
+        write_change(out, change)?;
+    }
+    if plan.is_empty() {
+        writeln!(out, "No changes: live state matches the desired state.")
+    } else {
+        writeln!(
+            out,
+            "\nPlan: {} to create, {} to update, {} to delete.",
+            plan.count(ChangeAction::Create),
+            plan.count(ChangeAction::Update),
+            plan.count(ChangeAction::Delete)
+        )
+    }
+}
+
+/// Prints an import plan, listing the existing resources it skips.
+///
+/// In CSV and TSV, skipped resources come first with the action `skip`.
+pub fn print_import(format: OutputFormat, import: &ImportPlan) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(import)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(import)?),
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let mut rows: Vec<_> = import.skipped.iter().map(skipped_row).collect();
+            rows.extend(change_rows(&import.plan.changes));
+            print_list(format, &rows)?
+        }
+        OutputFormat::Table => write_import(&mut io::stdout().lock(), import)?,
+    }
+    Ok(())
+}
+
+/// Writes an import plan as text, like [`write_plan`].
+pub fn write_import(out: &mut impl Write, import: &ImportPlan) -> io::Result<()> {
+    for conflict in &import.skipped {
+        write!(out, "= {} {}", conflict.resource, conflict.id)?;
+        if let Some(mailbox) = &conflict.mailbox {
+            write!(out, " (on {})", mailbox)?;
+        }
+        writeln!(out, " already exists, skipped")?;
+    }
+    write_plan(out, &import.plan)
+}
+
+/// Shows an import plan, confirms it unless `yes` is set and applies it.
+///
+/// Dry runs and empty plans are only printed. Generated passwords are saved before
+/// anything is created.
+pub async fn run_import(
+    client: &MigaduClient,
+    import: &ImportPlan,
+    passwords: &BulkPasswords,
+    format: OutputFormat,
+    dry_run: bool,
+    yes: bool,
+    question: &str,
+) -> Result<(), Box<dyn Error>> {
+    if dry_run || import.plan.is_empty() {
+        return print_import(format, import);
+    }
+    if !yes {
+        write_import(&mut io::stderr().lock(), import)?;
+        eprintln!();
+        confirm::prompt(question)?;
+    }
+    passwords.persist()?;
+    let report = client.apply(&import.plan).await;
+    print_report(format, &report)
+}
+
+/// Prints the outcome of applying a plan, failing if a change was rejected.
+///
+/// CSV and TSV list the applied changes like [`print_plan`]; the failure is reported
+/// through the returned error.
+pub fn print_report(format: OutputFormat, report: &ApplyReport) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(report)?),
+        OutputFormat::Csv | OutputFormat::Tsv => print_list(format, &change_rows(&report.applied))?,
+        OutputFormat::Table => {
+            let mut out = io::stdout().lock();
+            for change in &report.applied {
+                writeln!(out, "{}", change)?;
+            }
+            writeln!(out, "\nApplied {} changes.", report.applied.len())?;
+        }
+    }
+
+    match &report.failed {
+        Some(failed) => Err(format!(
+            "Failed to apply {}: {} ({} remaining changes skipped)",
+            failed.change, failed.error, report.skipped
+        )
+        .into()),
+        None => Ok(()),
+    }
+}
+
+fn write_change(out: &mut impl Write, change: &Change) -> io::Result<()> {
+    writeln!(out, "{}", change)?;
+    for field in &change.fields {
+        match change.action {
+            ChangeAction::Create => writeln!(out, "    {}: {}", field.field, field.new)?,
+            _ => diff::write_field(out, field)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use migadu_client::plan::{plan_domain, DomainSpec};
+    use migadu_client::state::DomainState;
+    use serde_json::json;
+
+    #[test]
+    fn test_change_rows_list_each_field() {
+        let live: DomainState = serde_json::from_value(json!({
+            "domain": "example.com",
+            "aliases": [{
+                "local_part": "old",
+                "domain_name": "example.com",
+                "address": "old@example.com",
+                "destinations": ["a@example.com"]
+            }]
+        }))
+        .unwrap();
+        let spec: DomainSpec = serde_json::from_value(json!({
+            "aliases": [{ "local_part": "info", "destinations": ["a@example.com", "b@example.com"] }]
+        }))
+        .unwrap();
+        let changes = plan_domain("example.com", &spec, &live).unwrap();
+
+        let rows: Vec<_> = change_rows(&changes).iter().map(Record::row).collect();
+        let create: Vec<_> = rows.iter().filter(|row| row[0] == "create").collect();
+        assert!(create
+            .iter()
+            .all(|row| row[1..4] == ["alias", "example.com", "info@example.com"]));
+        assert!(create
+            .iter()
+            .any(|row| row[5..] == ["destinations", "", "a@example.com,b@example.com"]));
+        assert_eq!(
+            rows.last().unwrap(),
+            &[
+                "delete",
+                "alias",
+                "example.com",
+                "old@example.com",
+                "",
+                "",
+                "",
+                ""
+            ]
+        );
+        assert!(rows.iter().all(|row| row.len() == ChangeRow::COLUMNS.len()));
+    }
+}
@@ -0,0 +1,237 @@
+use std::error::Error;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+
+use clap::{Subcommand, ValueEnum};
+use migadu_client::config::{Config, Profile};
+use migadu_client::credentials::{self, KEYRING_SERVICE};
+use migadu_client::MASK;
+use serde::Serialize;
+
+use crate::output::{print_list, OutputFormat, Record};
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// List all profiles
+    List,
+    /// Print a setting of the selected profile, masking a stored API key
+    Get {
+        /// Setting name
+        key: ConfigKey,
+    },
+    /// Change a setting of the selected profile, creating the profile if needed
+    ///
+    /// The API key itself cannot be set this way; use `store-key` or `api-key-command`.
+    Set {
+        /// Setting name
+        key: ConfigKey,
+        /// New value
+        value: String,
+    },
+    /// Remove a setting from the selected profile
+    Unset {
+        /// Setting name
+        key: ConfigKey,
+    },
+    /// Store an API key read from stdin in the keyring and use it for the selected profile
+    StoreKey {
+        /// Keyring account name [default: the profile's email]
+        #[arg(long)]
+        account: Option<String>,
+    },
+}
+
+/// Settings that can be read and written with `migadu config`.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ConfigKey {
+    Email,
+    ApiKey,
+    ApiKeyFile,
+    ApiKeyCommand,
+    ApiKeyKeyring,
+    Domain,
+    BaseUrl,
+    /// Profile used when `--profile` is not given (not tied to a profile)
+    DefaultProfile,
+}
+
+/// Summary of a profile as shown by `migadu config list`.
+#[derive(Serialize)]
+struct ProfileRow {
+    name: String,
+    default: bool,
+    email: Option<String>,
+    domain: Option<String>,
+    base_url: Option<String>,
+    api_key: &'static str,
+}
+
+impl Record for ProfileRow {
+    const COLUMNS: &'static [&'static str] =
+        &["name", "default", "email", "domain", "base_url", "api_key"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.default.to_string(),
+            self.email.clone().unwrap_or_default(),
+            self.domain.clone().unwrap_or_default(),
+            self.base_url.clone().unwrap_or_default(),
+            self.api_key.to_string(),
+        ]
+    }
+}
+
+/// Runs a `migadu config` subcommand against the file at `path`.
+pub fn run(
+    action: ConfigAction,
+    path: &Path,
+    profile: Option<&str>,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let mut config = Config::load(path)?;
+    let profile_name = profile
+        .unwrap_or_else(|| config.default_profile_name())
+        .to_string();
+
+    match action {
+        ConfigAction::List => {
+            let rows: Vec<_> = config
+                .profiles
+                .iter()
+                .map(|(name, p)| ProfileRow {
+                    name: name.clone(),
+                    default: name == config.default_profile_name(),
+                    email: p.email.clone(),
+                    domain: p.domain.clone(),
+                    base_url: p.base_url.clone(),
+                    api_key: p.api_key_source(),
+                })
+                .collect();
+            print_list(format, &rows)?;
+        }
+        ConfigAction::Get { key } => {
+            let value = match key {
+                ConfigKey::DefaultProfile => config.default_profile.clone(),
+                key => config
+                    .profiles
+                    .get(&profile_name)
+                    .and_then(|p| field(&mut p.clone(), key).take()),
+            };
+            match value {
+                Some(value) => println!("{}", shown(key, value)),
+                None => return Err(format!("{:?} is not set", key).into()),
+            }
+        }
+        ConfigAction::Set { key, value } => {
+            check_settable(key)?;
+            match key {
+                ConfigKey::DefaultProfile => config.default_profile = Some(value),
+                key => {
+                    let profile = config.profiles.entry(profile_name).or_default();
+                    *field(profile, key) = Some(value);
+                }
+            }
+            config.save(path)?;
+        }
+        ConfigAction::Unset { key } => {
+            match key {
+                ConfigKey::DefaultProfile => config.default_profile = None,
+                key => {
+                    if let Some(profile) = config.profiles.get_mut(&profile_name) {
+                        *field(profile, key) = None;
+                    }
+                }
+            }
+            config.save(path)?;
+        }
+        ConfigAction::StoreKey { account } => {
+            let profile = config.profiles.entry(profile_name).or_default();
+            let account = account
+                .or_else(|| profile.email.clone())
+                .ok_or("No keyring account: use --account or set the profile's email first")?;
+
+            let api_key = if io::stdin().is_terminal() {
+                rpassword::prompt_password("API key: ")?
+            } else {
+                let mut api_key = String::new();
+                io::stdin().read_line(&mut api_key)?;
+                api_key
+            };
+            let api_key = api_key.trim();
+            if api_key.is_empty() {
+                return Err("No API key given on stdin".into());
+            }
+
+            credentials::default_store()?.set(KEYRING_SERVICE, &account, api_key)?;
+            profile.api_key_keyring = Some(account);
+            config.save(path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Fails for settings that must not be given on the command line.
+///
+/// A key passed as an argument ends up in process listings and shell history, so it is
+/// read from stdin by `store-key` instead.
+fn check_settable(key: ConfigKey) -> Result<(), String> {
+    match key {
+        ConfigKey::ApiKey => Err("Refusing to take the API key as an argument: pipe it to \
+             `migadu config store-key`, or set api-key-command or api-key-file"
+            .to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Returns how `config get` shows a setting: the API key is a secret, so only the fact
+/// that one is stored.
+fn shown(key: ConfigKey, value: String) -> String {
+    match key {
+        ConfigKey::ApiKey => MASK.to_string(),
+        _ => value,
+    }
+}
+
+/// Returns the profile field for a (non-global) setting.
+fn field(profile: &mut Profile, key: ConfigKey) -> &mut Option<String> {
+    match key {
+        ConfigKey::Email => &mut profile.email,
+        ConfigKey::ApiKey => &mut profile.api_key,
+        ConfigKey::ApiKeyFile => &mut profile.api_key_file,
+        ConfigKey::ApiKeyCommand => &mut profile.api_key_command,
+        ConfigKey::ApiKeyKeyring => &mut profile.api_key_keyring,
+        ConfigKey::Domain => &mut profile.domain,
+        ConfigKey::BaseUrl => &mut profile.base_url,
+        ConfigKey::DefaultProfile => unreachable!("default-profile is not a profile setting"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_key_cannot_be_set_as_an_argument() {
+        let error = check_settable(ConfigKey::ApiKey).unwrap_err();
+        assert!(error.contains("migadu config store-key"), "{}", error);
+        for key in [
+            ConfigKey::Email,
+            ConfigKey::ApiKeyCommand,
+            ConfigKey::ApiKeyFile,
+            ConfigKey::DefaultProfile,
+        ] {
+            assert_eq!(check_settable(key), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_api_key_is_masked_when_shown() {
+        assert_eq!(shown(ConfigKey::ApiKey, "secret".to_string()), MASK);
+        assert_eq!(
+            shown(ConfigKey::ApiKeyCommand, "pass show migadu".to_string()),
+            "pass show migadu"
+        );
+    }
+}
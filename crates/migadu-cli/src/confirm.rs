@@ -0,0 +1,166 @@
+use std::error::Error;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use migadu_client::{UpdateIdentity, UpdateMailbox};
+use serde::Serialize;
+
+use crate::output::write_details;
+
+/// Asks the user to confirm a destructive action, showing the resource it affects.
+///
+/// Fails when the user declines, and when stdin is not a terminal, since the action
+/// must then be confirmed up front with `--yes`.
+pub fn ask<T: Serialize>(question: &str, current: &T) -> Result<(), Box<dyn Error>> {
+    check_interactive(question, io::stdin().is_terminal())?;
+    write_details(&mut io::stderr().lock(), current)?;
+    eprintln!();
+    prompt(question)
+}
+
+/// Asks the user to confirm an action that has already been described.
+///
+/// Fails like [`ask`].
+pub fn prompt(question: &str) -> Result<(), Box<dyn Error>> {
+    check_interactive(question, io::stdin().is_terminal())?;
+
+    let mut stderr = io::stderr().lock();
+    write!(stderr, "{} [y/N] ", question)?;
+    stderr.flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Ok(()),
+        _ => Err("Aborted".into()),
+    }
+}
+
+/// Fails unless the user can be asked `question`, i.e. stdin is `interactive`.
+fn check_interactive(question: &str, interactive: bool) -> Result<(), Box<dyn Error>> {
+    if interactive {
+        return Ok(());
+    }
+    Err(format!(
+        "{} Refusing to continue without --yes when not running interactively",
+        question
+    )
+    .into())
+}
+
+/// Describes how a mailbox update removes access, if it does.
+///
+/// A new password is not a removal: it is chosen by whoever runs the command, often
+/// from a script with `--password-stdin`.
+pub fn mailbox_access_removals(update: &UpdateMailbox) -> Vec<String> {
+    revoked_permissions(&[
+        ("may_send", update.may_send),
+        ("may_receive", update.may_receive),
+        ("may_access_imap", update.may_access_imap),
+        ("may_access_pop3", update.may_access_pop3),
+        ("may_access_managesieve", update.may_access_managesieve),
+    ])
+}
+
+/// Describes how an identity update removes access, if it does.
+pub fn identity_access_removals(update: &UpdateIdentity) -> Vec<String> {
+    revoked_permissions(&[
+        ("may_send", update.may_send),
+        ("may_receive", update.may_receive),
+        ("may_access_imap", update.may_access_imap),
+        ("may_access_pop3", update.may_access_pop3),
+        ("may_access_managesieve", update.may_access_managesieve),
+    ])
+}
+
+/// Describes destinations that would stop receiving mail when `current` is replaced
+/// by the comma-separated `new` list.
+pub fn destination_removals(current: &[String], new: &str) -> Vec<String> {
+    let new: Vec<&str> = new.split(',').map(str::trim).collect();
+    current
+        .iter()
+        .filter(|dest| !new.iter().any(|n| n.eq_ignore_ascii_case(dest)))
+        .map(|dest| format!("stops delivering to {}", dest))
+        .collect()
+}
+
+fn revoked_permissions(permissions: &[(&str, Option<bool>)]) -> Vec<String> {
+    permissions
+        .iter()
+        .filter(|(_, value)| *value == Some(false))
+        .map(|(name, _)| format!("revokes {}", name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addresses(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_revoking_access_is_a_removal() {
+        let update = UpdateMailbox {
+            may_send: Some(false),
+            may_access_imap: Some(false),
+            may_receive: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            mailbox_access_removals(&update),
+            ["revokes may_send", "revokes may_access_imap"]
+        );
+
+        let update = UpdateIdentity {
+            may_access_pop3: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(
+            identity_access_removals(&update),
+            ["revokes may_access_pop3"]
+        );
+    }
+
+    #[test]
+    fn test_keeping_or_granting_access_is_not_a_removal() {
+        let update = UpdateMailbox {
+            may_send: Some(true),
+            password: Some("new secret".to_string()),
+            name: Some("Alice".to_string()),
+            ..Default::default()
+        };
+        assert!(mailbox_access_removals(&update).is_empty());
+        assert!(identity_access_removals(&UpdateIdentity::default()).is_empty());
+    }
+
+    #[test]
+    fn test_shrinking_destinations_is_a_removal() {
+        let current = addresses(&["a@example.com", "b@example.com", "c@example.com"]);
+        assert_eq!(
+            destination_removals(&current, "a@example.com, c@example.com"),
+            ["stops delivering to b@example.com"]
+        );
+        assert_eq!(destination_removals(&current, "d@example.com").len(), 3);
+    }
+
+    #[test]
+    fn test_reordering_or_adding_destinations_is_not_a_removal() {
+        let current = addresses(&["a@example.com", "b@example.com"]);
+        assert!(destination_removals(&current, "B@example.com,a@example.com").is_empty());
+        assert!(
+            destination_removals(&current, "b@example.com, a@example.com, d@example.com")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_confirmation_needs_a_terminal() {
+        assert!(check_interactive("Delete alice@example.com?", true).is_ok());
+        let error = check_interactive("Delete alice@example.com?", false).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Delete alice@example.com? Refusing to continue without --yes when not running interactively"
+        );
+    }
+}
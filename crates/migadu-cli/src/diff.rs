@@ -0,0 +1,220 @@
+use std::error::Error;
+use std::io::{self, Write};
+
+use migadu_client::diff::{Difference, FieldChange, SnapshotDiff};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::output::{cell, print_list, OutputFormat, Record};
+
+/// A changed field, or an added or removed resource, as printed with `--output csv` or
+/// `tsv`.
+#[derive(Serialize)]
+struct DiffRow {
+    difference: Difference,
+    resource: String,
+    domain: String,
+    id: String,
+    mailbox: Option<String>,
+    field: String,
+    old: Value,
+    new: Value,
+}
+
+impl Record for DiffRow {
+    const COLUMNS: &'static [&'static str] = &[
+        "difference",
+        "resource",
+        "domain",
+        "id",
+        "mailbox",
+        "field",
+        "old",
+        "new",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            match self.difference {
+                Difference::Added => "added",
+                Difference::Removed => "removed",
+                Difference::Changed => "changed",
+            }
+            .to_string(),
+            self.resource.clone(),
+            self.domain.clone(),
+            self.id.clone(),
+            self.mailbox.clone().unwrap_or_default(),
+            self.field.clone(),
+            cell(&self.old),
+            cell(&self.new),
+        ]
+    }
+}
+
+/// One row per changed field, and one per added or removed resource.
+fn rows(diff: &SnapshotDiff) -> Vec<DiffRow> {
+    let mut rows = Vec::new();
+    for resource in &diff.resources {
+        let row = |field: String, old: Value, new: Value| DiffRow {
+            difference: resource.difference,
+            resource: resource.resource.to_string(),
+            domain: resource.domain.clone(),
+            id: resource.id.clone(),
+            mailbox: resource.mailbox.clone(),
+            field,
+            old,
+            new,
+        };
+        if resource.fields.is_empty() {
+            rows.push(row(String::new(), Value::Null, Value::Null));
+        }
+        for field in &resource.fields {
+            rows.push(row(
+                field.field.clone(),
+                field.old.clone(),
+                field.new.clone(),
+            ));
+        }
+    }
+    rows
+}
+
+/// Prints the differences between two snapshots.
+pub fn print(format: OutputFormat, diff: &SnapshotDiff) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(diff)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(diff)?),
+        OutputFormat::Csv | OutputFormat::Tsv => print_list(format, &rows(diff))?,
+        OutputFormat::Table => {
+            let mut out = io::stdout().lock();
+            for resource in &diff.resources {
+                let symbol = match resource.difference {
+                    Difference::Added => '+',
+                    Difference::Removed => '-',
+                    Difference::Changed => '~',
+                };
+                write!(out, "{} {} {}", symbol, resource.resource, resource.id)?;
+                if let Some(mailbox) = &resource.mailbox {
+                    write!(out, " (on {})", mailbox)?;
+                }
+                writeln!(out)?;
+                for field in &resource.fields {
+                    write_field(&mut out, field)?;
+                }
+            }
+            if diff.is_empty() {
+                writeln!(out, "No differences.")?;
+            } else {
+                writeln!(
+                    out,
+                    "\n{} added, {} removed, {} changed.",
+                    diff.count(Difference::Added),
+                    diff.count(Difference::Removed),
+                    diff.count(Difference::Changed)
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes an indented line describing a changed field.
+///
+/// List fields show the entries gained and lost rather than both full lists.
+pub fn write_field(out: &mut impl Write, field: &FieldChange) -> io::Result<()> {
+    if field.added.is_empty() && field.removed.is_empty() {
+        return writeln!(out, "    {}: {} -> {}", field.field, field.old, field.new);
+    }
+    let mut parts = Vec::new();
+    if !field.added.is_empty() {
+        parts.push(format!("gained {}", entries(&field.added)));
+    }
+    if !field.removed.is_empty() {
+        parts.push(format!("lost {}", entries(&field.removed)));
+    }
+    writeln!(out, "    {}: {}", field.field, parts.join(", "))
+}
+
+fn entries(items: &[Value]) -> String {
+    let items: Vec<String> = items
+        .iter()
+        .map(|item| match item {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .collect();
+    format!("{} ({})", items.len(), items.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use migadu_client::diff::ResourceDiff;
+    use migadu_client::state::ResourceKind;
+    use serde_json::json;
+
+    #[test]
+    fn test_rows_list_each_changed_field() {
+        let diff = SnapshotDiff {
+            resources: vec![
+                ResourceDiff {
+                    difference: Difference::Changed,
+                    resource: ResourceKind::Alias,
+                    domain: "example.com".to_string(),
+                    id: "info@example.com".to_string(),
+                    mailbox: None,
+                    fields: vec![
+                        FieldChange::new("destinations", json!(["a@example.com"]), json!([])),
+                        FieldChange::new("is_internal", json!(false), json!(true)),
+                    ],
+                },
+                ResourceDiff {
+                    difference: Difference::Added,
+                    resource: ResourceKind::Identity,
+                    domain: "example.com".to_string(),
+                    id: "sales@example.com".to_string(),
+                    mailbox: Some("alice@example.com".to_string()),
+                    fields: Vec::new(),
+                },
+            ],
+        };
+
+        let rows: Vec<_> = rows(&diff).iter().map(Record::row).collect();
+        assert_eq!(
+            rows,
+            [
+                [
+                    "changed",
+                    "alias",
+                    "example.com",
+                    "info@example.com",
+                    "",
+                    "destinations",
+                    "a@example.com",
+                    ""
+                ],
+                [
+                    "changed",
+                    "alias",
+                    "example.com",
+                    "info@example.com",
+                    "",
+                    "is_internal",
+                    "false",
+                    "true"
+                ],
+                [
+                    "added",
+                    "identity",
+                    "example.com",
+                    "sales@example.com",
+                    "alice@example.com",
+                    "",
+                    "",
+                    ""
+                ],
+            ]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::error::Error;
+use std::io::{self, Write};
+
+use migadu_client::diff::{self, FieldChange};
+use migadu_client::{HttpMethod, MigaduClient, Operation};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::output::{cell, print_list, write_details, write_table, OutputFormat, Record};
+
+/// What a mutation would do, as printed by `--dry-run`.
+#[derive(Serialize)]
+struct DryRun {
+    method: HttpMethod,
+    url: String,
+    /// Request body with passwords masked.
+    body: Option<Value>,
+    /// Field-level changes an update would make to the current resource.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    changes: Vec<FieldChange>,
+    /// Resource a delete would remove.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deletes: Option<Value>,
+}
+
+impl DryRun {
+    /// One row per field the request would set, change or remove.
+    fn rows(&self) -> Vec<FieldRow> {
+        let changes = match self.method {
+            HttpMethod::Post => self
+                .body
+                .as_ref()
+                .map(diff::creation_changes)
+                .unwrap_or_default(),
+            HttpMethod::Delete => self
+                .deletes
+                .as_ref()
+                .map(|deletes| {
+                    diff::creation_changes(deletes)
+                        .into_iter()
+                        .map(|change| FieldChange::new(change.field, change.new, Value::Null))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            _ => self.changes.clone(),
+        };
+        changes
+            .into_iter()
+            .map(|change| FieldRow {
+                method: self.method,
+                url: self.url.clone(),
+                field: change.field,
+                current: change.old,
+                new: change.new,
+            })
+            .collect()
+    }
+}
+
+/// A field a dry run would set, as printed with `--output csv` or `tsv`.
+#[derive(Serialize)]
+struct FieldRow {
+    method: HttpMethod,
+    url: String,
+    field: String,
+    current: Value,
+    new: Value,
+}
+
+impl Record for FieldRow {
+    const COLUMNS: &'static [&'static str] = &["method", "url", "field", "current", "new"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.method.to_string(),
+            self.url.clone(),
+            self.field.clone(),
+            cell(&self.current),
+            cell(&self.new),
+        ]
+    }
+}
+
+/// Prints the request an operation would send, without sending it.
+///
+/// `current` is the resource as it exists now; updates are shown as a diff against
+/// it and deletes show it in full.
+pub fn show<T: Serialize>(
+    client: &MigaduClient,
+    format: OutputFormat,
+    operation: &Operation,
+    current: Option<&T>,
+) -> Result<(), Box<dyn Error>> {
+    let request = operation.request()?.masked();
+    let current = current.map(serde_json::to_value).transpose()?;
+
+    let changes = match (&request.body, &current) {
+        (Some(body), Some(current)) if request.method == HttpMethod::Put => {
+            diff::update_changes(current, body)
+        }
+        _ => Vec::new(),
+    };
+    let deletes = current.filter(|_| request.method == HttpMethod::Delete);
+    let dry_run = DryRun {
+        method: request.method,
+        url: client.request_url(&request),
+        body: request.body,
+        changes,
+        deletes,
+    };
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&dry_run)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&dry_run)?),
+        OutputFormat::Csv | OutputFormat::Tsv => print_list(format, &dry_run.rows())?,
+        OutputFormat::Table => {
+            let mut out = io::stdout().lock();
+            writeln!(out, "{} {}", dry_run.method, dry_run.url)?;
+            if let Some(body) = &dry_run.body {
+                writeln!(out, "{}", serde_json::to_string_pretty(body)?)?;
+            }
+            if !dry_run.changes.is_empty() {
+                writeln!(out)?;
+                let rows = dry_run.changes.iter().map(|change| {
+                    vec![
+                        change.field.clone(),
+                        change.old.to_string(),
+                        change.new.to_string(),
+                    ]
+                });
+                write_table(&mut out, &["field", "current", "new"], rows)?;
+            }
+            if let Some(deletes) = &dry_run.deletes {
+                writeln!(out)?;
+                write_details(&mut out, deletes)?;
+            }
+            writeln!(out, "\nDry run: nothing was changed.")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn dry_run(method: HttpMethod, body: Option<Value>, deletes: Option<Value>) -> DryRun {
+        DryRun {
+            method,
+            url: "https://api.migadu.com/v1/domains/example.com/aliases/info".to_string(),
+            body,
+            changes: Vec::new(),
+            deletes,
+        }
+    }
+
+    #[test]
+    fn test_rows_list_each_field_set_or_removed() {
+        let create = dry_run(
+            HttpMethod::Post,
+            Some(json!({ "local_part": "info", "destinations": ["a@example.com"] })),
+            None,
+        );
+        let rows: Vec<_> = create.rows().iter().map(Record::row).collect();
+        assert_eq!(
+            rows[0][..3],
+            [
+                "POST".to_string(),
+                create.url.clone(),
+                "local_part".to_string()
+            ]
+        );
+        assert_eq!(rows[0][3..], ["", "info"]);
+        assert_eq!(rows[1][2..], ["destinations", "", "a@example.com"]);
+
+        let delete = dry_run(
+            HttpMethod::Delete,
+            None,
+            Some(json!({ "address": "info@example.com" })),
+        );
+        let rows: Vec<_> = delete.rows().iter().map(Record::row).collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0][2..], ["address", "info@example.com", ""]);
+    }
+}
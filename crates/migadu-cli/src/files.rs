@@ -0,0 +1,103 @@
+use std::error::Error;
+use std::path::Path;
+
+use migadu_client::config;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::output::OutputFormat;
+
+/// Serialization formats for files, chosen by extension.
+#[derive(Clone, Copy)]
+enum FileFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl FileFormat {
+    fn of(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Some(FileFormat::Json),
+            Some("yaml" | "yml") => Some(FileFormat::Yaml),
+            Some("toml") => Some(FileFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a JSON, YAML or TOML file, choosing the parser from its extension.
+///
+/// `what` describes the file in error messages, e.g. "desired-state file".
+pub fn read<T: DeserializeOwned>(path: &Path, what: &str) -> Result<T, Box<dyn Error>> {
+    let format = FileFormat::of(path).ok_or_else(|| {
+        format!(
+            "Unsupported {} {}: expected .yaml, .yml, .toml or .json",
+            what,
+            path.display()
+        )
+    })?;
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let value = match format {
+        FileFormat::Json => serde_json::from_str(&contents).map_err(|e| e.to_string()),
+        FileFormat::Yaml => serde_yaml::from_str(&contents).map_err(|e| e.to_string()),
+        FileFormat::Toml => toml::from_str(&contents).map_err(|e| e.to_string()),
+    };
+    value.map_err(|e| format!("Invalid {} {}: {}", what, path.display(), e).into())
+}
+
+/// Writes a value to a file, or to stdout when `path` is `None`.
+///
+/// Files are written in the format matching their extension; stdout gets YAML when
+/// `--output yaml` is given and JSON otherwise.
+pub fn write<T: Serialize>(
+    value: &T,
+    path: Option<&Path>,
+    output: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let format = match path {
+        Some(path) => format_of(path)?,
+        None if output == OutputFormat::Yaml => FileFormat::Yaml,
+        None => FileFormat::Json,
+    };
+    let contents = to_string(value, format)?;
+    match path {
+        Some(path) => std::fs::write(path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?,
+        None => print!("{}", contents),
+    }
+    Ok(())
+}
+
+/// Writes a value containing secrets to a new file readable only by the current user.
+pub fn write_private<T: Serialize>(value: &T, path: &Path) -> Result<(), Box<dyn Error>> {
+    let contents = to_string(value, format_of(path)?)?;
+    create_private(path, &contents)
+}
+
+/// Writes secrets to a new file readable only by the current user.
+///
+/// An existing file is never replaced, so secrets written by an earlier run are not lost.
+pub fn create_private(path: &Path, contents: &str) -> Result<(), Box<dyn Error>> {
+    config::create_private(path, contents)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e).into())
+}
+
+fn format_of(path: &Path) -> Result<FileFormat, Box<dyn Error>> {
+    FileFormat::of(path).ok_or_else(|| {
+        format!(
+            "Unsupported file {}: expected .yaml, .yml, .toml or .json",
+            path.display()
+        )
+        .into()
+    })
+}
+
+fn to_string<T: Serialize>(value: &T, format: FileFormat) -> Result<String, Box<dyn Error>> {
+    Ok(match format {
+        FileFormat::Json => serde_json::to_string_pretty(value)? + "\n",
+        FileFormat::Yaml => serde_yaml::to_string(value)?,
+        FileFormat::Toml => toml::to_string_pretty(value)?,
+    })
+}
@@ -0,0 +1,44 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use migadu_client::graph::Graph;
+use migadu_client::state::DomainState;
+
+/// Options for `graph`.
+#[derive(Args, Debug, Clone)]
+pub struct GraphArgs {
+    /// Snapshot written by `export` [default: live state of the account]
+    pub snapshot: Option<PathBuf>,
+
+    /// Only draw these domains (comma-separated) instead of all of them
+    #[arg(long, value_delimiter = ',')]
+    pub domains: Vec<String>,
+
+    /// How to render the graph
+    #[arg(long, value_enum, default_value_t)]
+    pub format: GraphFormat,
+}
+
+/// Rendering of the delivery graph.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT, e.g. for `dot -Tsvg`
+    #[default]
+    Dot,
+    /// Mermaid flowchart, for Markdown documents
+    Mermaid,
+    /// Nodes and edges as JSON
+    Json,
+}
+
+/// Prints the delivery graph of `domains`.
+pub fn run(args: &GraphArgs, domains: &[DomainState]) -> Result<(), Box<dyn Error>> {
+    let graph = Graph::build(domains);
+    match args.format {
+        GraphFormat::Dot => print!("{}", graph.to_dot()),
+        GraphFormat::Mermaid => print!("{}", graph.to_mermaid()),
+        GraphFormat::Json => println!("{}", serde_json::to_string_pretty(&graph)?),
+    }
+    Ok(())
+}
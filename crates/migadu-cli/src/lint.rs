@@ -0,0 +1,245 @@
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use clap::{Args, ValueEnum};
+use migadu_client::lint::{self, Finding, Level, LintConfig, LintRule};
+use migadu_client::state::DomainState;
+use serde_json::{json, Value};
+
+use crate::files;
+use crate::output::{print_list, OutputFormat, Record};
+
+/// Options for `lint`.
+#[derive(Args, Debug, Clone)]
+pub struct LintArgs {
+    /// Snapshot written by `export` [default: live state of the account]
+    pub snapshot: Option<PathBuf>,
+
+    /// Only lint these domains (comma-separated) instead of all of them
+    #[arg(long, value_delimiter = ',')]
+    pub domains: Vec<String>,
+
+    /// File setting rule levels, e.g. `rules: { missing-recovery-email: off }`
+    /// (.yaml, .toml or .json)
+    #[arg(long)]
+    pub rules_file: Option<PathBuf>,
+
+    /// Set the level of a rule, overriding the rules file (e.g. alias-loop=warning)
+    #[arg(long = "rule", value_name = "RULE=LEVEL", value_parser = parse_rule_level)]
+    pub rules: Vec<(LintRule, Level)>,
+
+    /// Print the findings as SARIF 2.1.0, for code scanning tools
+    #[arg(long)]
+    pub sarif: bool,
+
+    /// Exit with status 1 if there is a finding at this level or above
+    #[arg(long, value_enum, default_value_t)]
+    pub fail_on: FailOn,
+}
+
+/// Lowest level of finding that makes `lint` fail.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum FailOn {
+    Note,
+    Warning,
+    #[default]
+    Error,
+    Never,
+}
+
+fn parse_rule_level(s: &str) -> Result<(LintRule, Level), String> {
+    let (rule, level) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected RULE=LEVEL, got {}", s))?;
+    Ok((rule.parse()?, level.parse()?))
+}
+
+/// Lints `domains`, prints the findings and exits with status 1 if any reach
+/// `--fail-on`.
+pub fn run(
+    format: OutputFormat,
+    args: &LintArgs,
+    domains: &[DomainState],
+) -> Result<(), Box<dyn Error>> {
+    let mut config: LintConfig = match &args.rules_file {
+        Some(path) => files::read(path, "lint rules file")?,
+        None => LintConfig::default(),
+    };
+    config.rules.extend(args.rules.iter().copied());
+
+    let findings = lint::lint(domains, &config, &lint::today());
+    if args.sarif {
+        let sarif = sarif(&findings, &config, args.snapshot.as_deref());
+        println!("{}", serde_json::to_string_pretty(&sarif)?);
+    } else {
+        print(format, &findings)?;
+    }
+
+    let threshold = match args.fail_on {
+        FailOn::Note => Level::Note,
+        FailOn::Warning => Level::Warning,
+        FailOn::Error => Level::Error,
+        FailOn::Never => return Ok(()),
+    };
+    if findings.iter().any(|f| f.level >= threshold) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+impl Record for Finding {
+    const COLUMNS: &'static [&'static str] = &[
+        "rule", "level", "domain", "resource", "id", "mailbox", "message",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.rule.to_string(),
+            self.level.to_string(),
+            self.domain.clone(),
+            self.resource.to_string(),
+            self.id.clone(),
+            self.mailbox.clone().unwrap_or_default(),
+            self.message.clone(),
+        ]
+    }
+}
+
+fn print(format: OutputFormat, findings: &[Finding]) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(findings)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(findings)?),
+        OutputFormat::Csv | OutputFormat::Tsv => print_list(format, findings)?,
+        OutputFormat::Table => {
+            let mut out = io::stdout().lock();
+            for finding in findings {
+                writeln!(
+                    out,
+                    "{}[{}] {}: {}",
+                    finding.level, finding.rule, finding.domain, finding.message
+                )?;
+            }
+            if findings.is_empty() {
+                writeln!(out, "No problems found.")?;
+            } else {
+                let count = |level| findings.iter().filter(|f| f.level == level).count();
+                writeln!(
+                    out,
+                    "\n{} errors, {} warnings, {} notes.",
+                    count(Level::Error),
+                    count(Level::Warning),
+                    count(Level::Note)
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Builds a SARIF log with one result per finding.
+///
+/// Resources have no source lines, so each result names its resource as a logical
+/// location, plus the snapshot file when there is one.
+fn sarif(findings: &[Finding], config: &LintConfig, snapshot: Option<&Path>) -> Value {
+    let rules: Vec<Value> = LintRule::ALL
+        .iter()
+        .map(|rule| {
+            let level = config.level(*rule);
+            json!({
+                "id": rule.id(),
+                "shortDescription": { "text": rule.description() },
+                "defaultConfiguration": {
+                    "enabled": level != Level::Off,
+                    "level": sarif_level(level),
+                },
+            })
+        })
+        .collect();
+
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let mut name = format!("{}/{}", finding.domain, finding.resource);
+            if let Some(mailbox) = &finding.mailbox {
+                name = format!(
+                    "{}/mailbox/{}/{}",
+                    finding.domain, mailbox, finding.resource
+                );
+            }
+            let mut location = json!({
+                "logicalLocations": [{
+                    "name": finding.id,
+                    "fullyQualifiedName": format!("{}/{}", name, finding.id),
+                    "kind": "resource",
+                }],
+            });
+            if let Some(path) = snapshot {
+                location["physicalLocation"] = json!({
+                    "artifactLocation": { "uri": path.display().to_string() },
+                });
+            }
+            json!({
+                "ruleId": finding.rule.id(),
+                "level": sarif_level(finding.level),
+                "message": { "text": finding.message },
+                "locations": [location],
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "migadu",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn sarif_level(level: Level) -> &'static str {
+    match level {
+        Level::Off => "none",
+        Level::Note => "note",
+        Level::Warning => "warning",
+        Level::Error => "error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use migadu_client::state::ResourceKind;
+
+    #[test]
+    fn test_findings_are_rows_with_their_resource() {
+        let finding = Finding {
+            rule: LintRule::MissingRecoveryEmail,
+            level: Level::Warning,
+            domain: "example.com".to_string(),
+            resource: ResourceKind::Mailbox,
+            id: "alice@example.com".to_string(),
+            mailbox: None,
+            message: "alice@example.com has no password recovery email".to_string(),
+        };
+        assert_eq!(
+            finding.row(),
+            [
+                "missing-recovery-email",
+                "warning",
+                "example.com",
+                "mailbox",
+                "alice@example.com",
+                "",
+                "alice@example.com has no password recovery email",
+            ]
+        );
+    }
+}
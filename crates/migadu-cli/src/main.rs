@@ -1,24 +1,89 @@
+mod apply;
+mod config;
+mod confirm;
+mod diff;
+mod dry_run;
+mod files;
+mod graph;
+mod lint;
+mod output;
+mod password;
+mod provision;
+mod resolve;
+mod rules;
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
 use clap::{Parser, Subcommand};
+use migadu_client::clone::CloneOptions;
+use migadu_client::config::{self as config_file, Config};
+use migadu_client::credentials::{
+    CredentialProvider, KeyCommand, KeyFile, Keyring, ProviderChain, StaticKey,
+};
+use migadu_client::rewrite_rules;
+use migadu_client::snapshot::Snapshot;
 use migadu_client::{
-    CreateAlias, CreateIdentity, CreateMailbox, CreateRewrite, MigaduClient, UpdateAlias,
-    UpdateIdentity, UpdateMailbox, UpdateRewrite,
+    Alias, CreateAlias, CreateIdentity, CreateMailbox, CreateRewrite, Identity, Mailbox,
+    MigaduClient, Operation, Rewrite, UpdateAlias, UpdateIdentity, UpdateMailbox, UpdateRewrite,
 };
 
+use crate::apply::OnConflict;
+use crate::config::ConfigAction;
+use crate::output::{print_item, print_list, OutputFormat};
+use crate::password::{BulkPasswordArgs, PasswordArgs};
+
 #[derive(Parser)]
 #[command(name = "migadu")]
 #[command(about = "CLI for Migadu email hosting API", long_about = None)]
 struct Cli {
-    /// Migadu account email
+    /// Migadu account email (overrides the profile)
     #[arg(long, env = "MIGADU_EMAIL")]
-    email: String,
+    email: Option<String>,
+
+    /// Migadu API key (overrides the profile; prefer the options below)
+    #[arg(long, env = "MIGADU_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
 
-    /// Migadu API key
-    #[arg(long, env = "MIGADU_API_KEY")]
-    api_key: String,
+    /// File containing the API key
+    #[arg(long, env = "MIGADU_API_KEY_FILE")]
+    api_key_file: Option<PathBuf>,
 
-    /// Domain to operate on
+    /// Command printing the API key, e.g. "pass show migadu"
+    #[arg(long, env = "MIGADU_API_KEY_COMMAND")]
+    api_key_command: Option<String>,
+
+    /// Keyring account under which the API key is stored
+    #[arg(long, env = "MIGADU_API_KEY_KEYRING")]
+    api_key_keyring: Option<String>,
+
+    /// Domain to operate on (overrides the profile)
     #[arg(long, env = "MIGADU_DOMAIN")]
-    domain: String,
+    domain: Option<String>,
+
+    /// API base URL (overrides the profile)
+    #[arg(long, env = "MIGADU_BASE_URL")]
+    base_url: Option<String>,
+
+    /// Config file profile to use
+    #[arg(long, short, global = true, env = "MIGADU_PROFILE")]
+    profile: Option<String>,
+
+    /// Path to the config file [default: ~/.config/migadu/config.toml]
+    #[arg(long, global = true, env = "MIGADU_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Skip confirmation prompts for destructive actions
+    #[arg(long, short, global = true)]
+    yes: bool,
+
+    /// Print the requests mutations would send instead of sending them
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Output format
+    #[arg(long, short, global = true, value_enum, env = "MIGADU_OUTPUT", default_value_t)]
+    output: OutputFormat,
 
     #[command(subcommand)]
     command: Commands,
@@ -31,6 +96,11 @@ enum Commands {
         #[command(subcommand)]
         action: MailboxAction,
     },
+    /// Manage domains
+    Domains {
+        #[command(subcommand)]
+        action: DomainAction,
+    },
     /// Manage aliases
     Aliases {
         #[command(subcommand)]
@@ -46,6 +116,93 @@ enum Commands {
         #[command(subcommand)]
         action: IdentityAction,
     },
+    /// Show where mail to an address is delivered
+    Resolve {
+        /// Address, or a local part on the selected domain
+        address: String,
+    },
+    /// Show the changes needed to match a desired-state file
+    Plan {
+        /// Desired state as YAML, TOML or JSON
+        file: PathBuf,
+    },
+    /// Apply the changes needed to match a desired-state file
+    Apply {
+        /// Desired state as YAML, TOML or JSON
+        file: PathBuf,
+    },
+    /// Export domains to a snapshot file, without secrets
+    Export {
+        /// File to write (.json, .yaml or .toml); prints to stdout if omitted
+        file: Option<PathBuf>,
+        /// Only export these domains (comma-separated) instead of all of them
+        #[arg(long, value_delimiter = ',')]
+        domains: Vec<String>,
+    },
+    /// Recreate resources from a snapshot on the selected domain
+    Import {
+        /// Snapshot written by `export`
+        snapshot: PathBuf,
+        /// Domain of the snapshot to import [default: the selected domain, or the
+        /// snapshot's only domain]
+        #[arg(long)]
+        from: Option<String>,
+        /// What to do with resources that already exist
+        #[arg(long, value_enum, default_value_t)]
+        on_conflict: OnConflict,
+        #[command(flatten)]
+        password_args: BulkPasswordArgs,
+    },
+    /// Check domains for likely configuration mistakes
+    Lint(lint::LintArgs),
+    /// Draw where mail to each address goes, as DOT, Mermaid or JSON
+    Graph(graph::GraphArgs),
+    /// Show what changed between two snapshots, or between a snapshot and live state
+    Diff {
+        /// Older snapshot
+        old: PathBuf,
+        /// Newer snapshot [default: live state of the domains in the older snapshot]
+        new: Option<PathBuf>,
+        /// Exit with status 1 if there are differences
+        #[arg(long)]
+        exit_code: bool,
+    },
+    /// Manage config file profiles
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum DomainAction {
+    /// List all domains on the account
+    List,
+    /// Get a specific domain
+    Get {
+        /// Domain name
+        name: String,
+    },
+    /// Copy mailboxes, identities, forwardings, aliases and rewrites to another domain
+    Clone {
+        /// Domain to copy from
+        #[arg(long)]
+        from: String,
+        /// Domain to copy to
+        #[arg(long)]
+        to: String,
+        /// What to do with resources that already exist on the target domain
+        #[arg(long, value_enum, default_value_t)]
+        on_conflict: OnConflict,
+        /// Point alias and rewrite destinations on the old domain at the new one
+        #[arg(long)]
+        rewrite_destinations: bool,
+        /// Forward the old domain's mailboxes and aliases to their new addresses
+        #[arg(long)]
+        leave_forwarding: bool,
+        #[command(flatten)]
+        password_args: BulkPasswordArgs,
+    },
 }
 
 #[derive(Subcommand)]
@@ -63,8 +220,10 @@ enum MailboxAction {
         local_part: String,
         /// Display name
         name: String,
-        /// Password
-        password: String,
+        /// Password (visible in process listings; prefer the options below)
+        password: Option<String>,
+        #[command(flatten)]
+        password_args: PasswordArgs,
     },
     /// Update a mailbox
     Update {
@@ -73,15 +232,34 @@ enum MailboxAction {
         /// New display name
         #[arg(long)]
         name: Option<String>,
-        /// New password
+        /// New password (visible in process listings; prefer the options below)
         #[arg(long)]
         password: Option<String>,
+        #[command(flatten)]
+        password_args: PasswordArgs,
+        /// Allow or deny sending
+        #[arg(long)]
+        may_send: Option<bool>,
+        /// Allow or deny receiving
+        #[arg(long)]
+        may_receive: Option<bool>,
+        /// Allow or deny IMAP access
+        #[arg(long)]
+        may_access_imap: Option<bool>,
+        /// Allow or deny POP3 access
+        #[arg(long)]
+        may_access_pop3: Option<bool>,
+        /// Allow or deny ManageSieve access
+        #[arg(long)]
+        may_access_managesieve: Option<bool>,
     },
     /// Delete a mailbox
     Delete {
         /// Local part
         local_part: String,
     },
+    /// Create mailboxes from the rows of a CSV file
+    Import(provision::ImportArgs),
 }
 
 #[derive(Subcommand)]
@@ -149,6 +327,23 @@ enum RewriteAction {
         /// Name
         name: String,
     },
+    /// Show which rule would handle a local part, and rules that can never match
+    Test {
+        /// Local part to test (e.g., "support-billing")
+        local_part: String,
+        /// Try out a new rule with this pattern, as if it had been created
+        #[arg(long, value_name = "LOCAL_PART_RULE")]
+        propose: Option<String>,
+        /// Name of the proposed rule
+        #[arg(long, default_value = "proposed", requires = "propose")]
+        name: String,
+        /// Comma-separated destinations of the proposed rule
+        #[arg(long, default_value = "", requires = "propose")]
+        destinations: String,
+        /// Position of the proposed rule [default: after the existing rules]
+        #[arg(long, requires = "propose")]
+        order_num: Option<i32>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -173,6 +368,8 @@ enum IdentityAction {
         local_part: String,
         /// Display name
         name: String,
+        #[command(flatten)]
+        password_args: PasswordArgs,
     },
     /// Update an identity
     Update {
@@ -183,6 +380,23 @@ enum IdentityAction {
         /// New display name
         #[arg(long)]
         name: Option<String>,
+        #[command(flatten)]
+        password_args: PasswordArgs,
+        /// Allow or deny sending
+        #[arg(long)]
+        may_send: Option<bool>,
+        /// Allow or deny receiving
+        #[arg(long)]
+        may_receive: Option<bool>,
+        /// Allow or deny IMAP access
+        #[arg(long)]
+        may_access_imap: Option<bool>,
+        /// Allow or deny POP3 access
+        #[arg(long)]
+        may_access_pop3: Option<bool>,
+        /// Allow or deny ManageSieve access
+        #[arg(long)]
+        may_access_managesieve: Option<bool>,
     },
     /// Delete an identity
     Delete {
@@ -193,98 +407,469 @@ enum IdentityAction {
     },
 }
 
+/// Builds a client and resolves the domain from flags, environment and the selected profile.
+fn connect(cli: &Cli, config: &Config) -> Result<(MigaduClient, Option<String>), Box<dyn Error>> {
+    let profile = config.profile(cli.profile.as_deref())?;
+
+    let email = cli
+        .email
+        .clone()
+        .or(profile.email.clone())
+        .ok_or("Missing account email: use --email, MIGADU_EMAIL or a config profile")?;
+    // Sources are tried in order and the keyring is only opened when reached, so a key
+    // given on the command line works without a keyring.
+    let chain = ProviderChain::new()
+        .with_opt(cli.api_key.clone().map(StaticKey))
+        .with_opt(cli.api_key_file.clone().map(KeyFile::new))
+        .with_opt(cli.api_key_command.clone().map(KeyCommand::new));
+    let chain = match cli.api_key_keyring.clone() {
+        Some(account) => chain.with_deferred(move || Keyring::new(account.clone())),
+        None => chain,
+    };
+    let api_key = chain
+        .extend(profile.credentials()?)
+        .api_key()?
+        .ok_or("Missing API key: use --api-key-file, --api-key-command, --api-key-keyring, MIGADU_API_KEY or a config profile")?;
+    let domain = cli.domain.clone().or(profile.domain.clone());
+
+    let client = match cli.base_url.clone().or(profile.base_url) {
+        Some(base_url) => MigaduClient::with_base_url(email, api_key, base_url),
+        None => MigaduClient::new(email, api_key),
+    };
+    Ok((client, domain))
+}
+
+fn read_snapshot(path: &Path) -> Result<Snapshot, Box<dyn Error>> {
+    let snapshot: Snapshot = files::read(path, "snapshot")?;
+    snapshot.check_version()?;
+    Ok(snapshot)
+}
+
+/// Loads `domains` (all of them if empty) from `snapshot`, or from the live state of the
+/// account when no snapshot is given.
+async fn load_domains(
+    cli: &Cli,
+    config_path: &Path,
+    snapshot: Option<&Path>,
+    domains: &[String],
+) -> Result<Snapshot, Box<dyn Error>> {
+    if let Some(snapshot) = snapshot {
+        let mut snapshot = read_snapshot(snapshot)?;
+        if !domains.is_empty() {
+            snapshot
+                .domains
+                .retain(|d| domains.iter().any(|n| n.eq_ignore_ascii_case(&d.domain)));
+        }
+        return Ok(snapshot);
+    }
+
+    let (client, _) = connect(cli, &Config::load(config_path)?)?;
+    Ok(if domains.is_empty() {
+        client.export().await?
+    } else {
+        client.export_domains(domains).await?
+    })
+}
+
+/// Prints the differences between snapshots, exiting with status 1 if asked to.
+fn show_diff(
+    format: OutputFormat,
+    old: &Snapshot,
+    new: &Snapshot,
+    exit_code: bool,
+) -> Result<(), Box<dyn Error>> {
+    let changes = migadu_client::diff::diff_snapshots(old, new);
+    diff::print(format, &changes)?;
+    if exit_code && !changes.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
-    let client = MigaduClient::new(&cli.email, &cli.api_key);
-    let domain = &cli.domain;
+    let format = cli.output;
+    let config_path = match &cli.config {
+        Some(path) => path.clone(),
+        None => config_file::default_path()?,
+    };
+
+    if let Commands::Config { action } = cli.command {
+        return config::run(action, &config_path, cli.profile.as_deref(), format);
+    }
+
+    // Comparing two files needs no account.
+    if let Commands::Diff {
+        old,
+        new: Some(new),
+        exit_code,
+    } = &cli.command
+    {
+        return show_diff(
+            format,
+            &read_snapshot(old)?,
+            &read_snapshot(new)?,
+            *exit_code,
+        );
+    }
+
+    // Linting and drawing read a snapshot when given one, and only connect otherwise.
+    match &cli.command {
+        Commands::Lint(args) => {
+            let snapshot =
+                load_domains(&cli, &config_path, args.snapshot.as_deref(), &args.domains).await?;
+            return lint::run(format, args, &snapshot.domains);
+        }
+        Commands::Graph(args) => {
+            let snapshot =
+                load_domains(&cli, &config_path, args.snapshot.as_deref(), &args.domains).await?;
+            return graph::run(args, &snapshot.domains);
+        }
+        _ => {}
+    }
+
+    let config = Config::load(&config_path)?;
+    let (client, domain) = connect(&cli, &config)?;
+    let dry_run = cli.dry_run;
+    // Dry runs change nothing, so they need no confirmation.
+    let yes = cli.yes || dry_run;
+
+    // These commands name their own domains.
+    match &cli.command {
+        Commands::Domains { action } => match action {
+            DomainAction::List => return print_list(format, &client.list_domains().await?),
+            DomainAction::Get { name } => {
+                return print_item(format, &client.get_domain(name).await?)
+            }
+            DomainAction::Clone {
+                from,
+                to,
+                on_conflict,
+                rewrite_destinations,
+                leave_forwarding,
+                password_args,
+            } => {
+                let options = CloneOptions {
+                    conflict: (*on_conflict).into(),
+                    rewrite_destinations: *rewrite_destinations,
+                    leave_forwarding: *leave_forwarding,
+                };
+                let mut passwords = password_args.load()?;
+                let import = client
+                    .plan_clone(from, to, options, |mailbox| {
+                        passwords.get(&format!("{}@{}", mailbox.local_part, to))
+                    })
+                    .await;
+                passwords.check()?;
+                let import = import?;
+                let question = format!(
+                    "Apply {} changes to clone {} into {}?",
+                    import.plan.changes.len(),
+                    from,
+                    to
+                );
+                return apply::run_import(
+                    &client, &import, &passwords, format, dry_run, yes, &question,
+                )
+                .await;
+            }
+        },
+        Commands::Diff {
+            old,
+            new: None,
+            exit_code,
+        } => {
+            let old = read_snapshot(old)?;
+            let domains: Vec<String> = old.domains.iter().map(|d| d.domain.clone()).collect();
+            let live = client.export_domains(&domains).await?;
+            return show_diff(format, &old, &live, *exit_code);
+        }
+        Commands::Resolve { address } => {
+            let address = match (address.contains('@'), &domain) {
+                (true, _) => address.clone(),
+                (false, Some(domain)) => format!("{}@{}", address, domain),
+                (false, None) => {
+                    return Err(format!(
+                        "Missing domain for {}: give a full address or use --domain",
+                        address
+                    )
+                    .into())
+                }
+            };
+            return resolve::print(format, &client.resolve_address(&address).await?);
+        }
+        Commands::Export { file, domains } => {
+            let snapshot = if domains.is_empty() {
+                client.export().await?
+            } else {
+                client.export_domains(domains).await?
+            };
+            return files::write(&snapshot, file.as_deref(), format);
+        }
+        Commands::Plan { file } => {
+            let plan = client.plan(&apply::load(file)?).await?;
+            return apply::print_plan(format, &plan);
+        }
+        Commands::Apply { file } => {
+            let plan = client.plan(&apply::load(file)?).await?;
+            if dry_run || plan.is_empty() {
+                return apply::print_plan(format, &plan);
+            }
+            if !yes {
+                apply::write_plan(&mut std::io::stderr().lock(), &plan)?;
+                eprintln!();
+                confirm::prompt(&format!("Apply {} changes?", plan.changes.len()))?;
+            }
+            let report = client.apply(&plan).await;
+            return apply::print_report(format, &report);
+        }
+        _ => {}
+    }
+
+    let domain =
+        &domain.ok_or("Missing domain: use --domain, MIGADU_DOMAIN or a config profile")?;
+
+    if let Commands::Import {
+        snapshot,
+        from,
+        on_conflict,
+        password_args,
+    } = &cli.command
+    {
+        let snapshot = read_snapshot(snapshot)?;
+        let source = match from {
+            Some(from) => snapshot.domain(from),
+            None => snapshot.domain(domain).or(snapshot
+                .domains
+                .first()
+                .filter(|_| snapshot.domains.len() == 1)),
+        }
+        .ok_or("Snapshot does not contain the domain to import: use --from")?;
+
+        let mut passwords = password_args.load()?;
+        let import = client
+            .plan_import(source, domain, (*on_conflict).into(), |mailbox| {
+                passwords.get(&format!("{}@{}", mailbox.local_part, domain))
+            })
+            .await;
+        passwords.check()?;
+        let import = import?;
+        let question = format!(
+            "Import {} changes into {}?",
+            import.plan.changes.len(),
+            domain
+        );
+        return apply::run_import(
+            &client, &import, &passwords, format, dry_run, yes, &question,
+        )
+        .await;
+    }
 
     match cli.command {
         Commands::Mailboxes { action } => match action {
             MailboxAction::List => {
                 let mailboxes = client.list_mailboxes(domain).await?;
-                for mb in mailboxes {
-                    println!("{}\t{}", mb.address, mb.name);
-                }
+                print_list(format, &mailboxes)?;
             }
             MailboxAction::Get { local_part } => {
                 let mb = client.get_mailbox(domain, &local_part).await?;
-                println!("{}", serde_json::to_string_pretty(&mb)?);
+                print_item(format, &mb)?;
             }
             MailboxAction::Create {
                 local_part,
                 name,
                 password,
+                password_args,
             } => {
-                let create = CreateMailbox::new(&local_part, &name, &password);
+                let password = password_args
+                    .resolve(password, true)?
+                    .ok_or("A password is required")?;
+                let create = CreateMailbox::new(&local_part, &name, &password.value);
+                if dry_run {
+                    let operation = Operation::CreateMailbox {
+                        domain: domain.clone(),
+                        mailbox: create,
+                    };
+                    return dry_run::show::<Mailbox>(&client, format, &operation, None);
+                }
+                password.persist()?;
                 let mb = client.create_mailbox(domain, &create).await?;
-                println!("Created: {}", mb.address);
+                password.announce(&mb.address);
+                print_item(format, &mb)?;
             }
             MailboxAction::Update {
                 local_part,
                 name,
                 password,
+                password_args,
+                may_send,
+                may_receive,
+                may_access_imap,
+                may_access_pop3,
+                may_access_managesieve,
             } => {
+                let password = password_args.resolve(password, false)?;
                 let update = UpdateMailbox {
                     name,
-                    password,
+                    password: password.as_ref().map(|p| p.value.clone()),
+                    may_send,
+                    may_receive,
+                    may_access_imap,
+                    may_access_pop3,
+                    may_access_managesieve,
                     ..Default::default()
                 };
+                let removals = confirm::mailbox_access_removals(&update);
+                let ask = !yes && !removals.is_empty();
+                let current = if ask || dry_run {
+                    Some(client.get_mailbox(domain, &local_part).await?)
+                } else {
+                    None
+                };
+                if let (true, Some(current)) = (ask, &current) {
+                    let question = format!(
+                        "Update mailbox {}? This {}.",
+                        current.address,
+                        removals.join(", ")
+                    );
+                    confirm::ask(&question, current)?;
+                }
+                if dry_run {
+                    let operation = Operation::UpdateMailbox {
+                        domain: domain.clone(),
+                        local_part,
+                        update,
+                    };
+                    return dry_run::show(&client, format, &operation, current.as_ref());
+                }
+                if let Some(password) = &password {
+                    password.persist()?;
+                }
                 let mb = client.update_mailbox(domain, &local_part, &update).await?;
-                println!("Updated: {}", mb.address);
+                if let Some(password) = &password {
+                    password.announce(&mb.address);
+                }
+                print_item(format, &mb)?;
             }
             MailboxAction::Delete { local_part } => {
+                let current = if !yes || dry_run {
+                    Some(client.get_mailbox(domain, &local_part).await?)
+                } else {
+                    None
+                };
+                if let (false, Some(current)) = (yes, &current) {
+                    let question = format!(
+                        "Delete mailbox {} and all of its mail? This cannot be undone.",
+                        current.address
+                    );
+                    confirm::ask(&question, current)?;
+                }
+                if dry_run {
+                    let operation = Operation::DeleteMailbox {
+                        domain: domain.clone(),
+                        local_part,
+                    };
+                    return dry_run::show(&client, format, &operation, current.as_ref());
+                }
                 let mb = client.delete_mailbox(domain, &local_part).await?;
-                println!("Deleted: {}", mb.address);
+                print_item(format, &mb)?;
+            }
+            MailboxAction::Import(args) => {
+                provision::run(&client, domain, &args, format, dry_run, yes).await?;
             }
         },
 
         Commands::Aliases { action } => match action {
             AliasAction::List => {
                 let aliases = client.list_aliases(domain).await?;
-                for alias in aliases {
-                    println!("{}\t{:?}", alias.address, alias.destinations);
-                }
+                print_list(format, &aliases)?;
             }
             AliasAction::Get { local_part } => {
                 let alias = client.get_alias(domain, &local_part).await?;
-                println!("{}", serde_json::to_string_pretty(&alias)?);
+                print_item(format, &alias)?;
             }
             AliasAction::Create {
                 local_part,
                 destinations,
             } => {
                 let create = CreateAlias::new(&local_part, &destinations);
+                if dry_run {
+                    let operation = Operation::CreateAlias {
+                        domain: domain.clone(),
+                        alias: create,
+                    };
+                    return dry_run::show::<Alias>(&client, format, &operation, None);
+                }
                 let alias = client.create_alias(domain, &create).await?;
-                println!("Created: {}", alias.address);
+                print_item(format, &alias)?;
             }
             AliasAction::Update {
                 local_part,
                 destinations,
             } => {
+                let ask = !yes && destinations.is_some();
+                let current = if ask || dry_run {
+                    Some(client.get_alias(domain, &local_part).await?)
+                } else {
+                    None
+                };
+                if let (true, Some(current), Some(new)) = (ask, &current, &destinations) {
+                    let removals = confirm::destination_removals(&current.destinations, new);
+                    if !removals.is_empty() {
+                        let question = format!(
+                            "Update alias {}? This {}.",
+                            current.address,
+                            removals.join(", ")
+                        );
+                        confirm::ask(&question, current)?;
+                    }
+                }
                 let update = UpdateAlias {
                     destinations,
                     ..Default::default()
                 };
+                if dry_run {
+                    let operation = Operation::UpdateAlias {
+                        domain: domain.clone(),
+                        local_part,
+                        update,
+                    };
+                    return dry_run::show(&client, format, &operation, current.as_ref());
+                }
                 let alias = client.update_alias(domain, &local_part, &update).await?;
-                println!("Updated: {}", alias.address);
+                print_item(format, &alias)?;
             }
             AliasAction::Delete { local_part } => {
+                let current = if !yes || dry_run {
+                    Some(client.get_alias(domain, &local_part).await?)
+                } else {
+                    None
+                };
+                if let (false, Some(current)) = (yes, &current) {
+                    let question = format!("Delete alias {}?", current.address);
+                    confirm::ask(&question, current)?;
+                }
+                if dry_run {
+                    let operation = Operation::DeleteAlias {
+                        domain: domain.clone(),
+                        local_part,
+                    };
+                    return dry_run::show(&client, format, &operation, current.as_ref());
+                }
                 let alias = client.delete_alias(domain, &local_part).await?;
-                println!("Deleted: {}", alias.address);
+                print_item(format, &alias)?;
             }
         },
 
         Commands::Rewrites { action } => match action {
             RewriteAction::List => {
                 let rewrites = client.list_rewrites(domain).await?;
-                for rw in rewrites {
-                    println!("{}\t{}\t{:?}", rw.name, rw.local_part_rule, rw.destinations);
-                }
+                print_list(format, &rewrites)?;
             }
             RewriteAction::Get { name } => {
                 let rw = client.get_rewrite(domain, &name).await?;
-                println!("{}", serde_json::to_string_pretty(&rw)?);
+                print_item(format, &rw)?;
             }
             RewriteAction::Create {
                 name,
@@ -292,68 +877,245 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 destinations,
             } => {
                 let create = CreateRewrite::new(&name, &local_part_rule, &destinations);
+                if dry_run {
+                    let operation = Operation::CreateRewrite {
+                        domain: domain.clone(),
+                        rewrite: create,
+                    };
+                    return dry_run::show::<Rewrite>(&client, format, &operation, None);
+                }
                 let rw = client.create_rewrite(domain, &create).await?;
-                println!("Created: {}", rw.name);
+                print_item(format, &rw)?;
             }
             RewriteAction::Update {
                 name,
                 local_part_rule,
                 destinations,
             } => {
+                let ask = !yes && destinations.is_some();
+                let current = if ask || dry_run {
+                    Some(client.get_rewrite(domain, &name).await?)
+                } else {
+                    None
+                };
+                if let (true, Some(current), Some(new)) = (ask, &current, &destinations) {
+                    let removals = confirm::destination_removals(&current.destinations, new);
+                    if !removals.is_empty() {
+                        let question =
+                            format!("Update rewrite {}? This {}.", current.name, removals.join(", "));
+                        confirm::ask(&question, current)?;
+                    }
+                }
                 let update = UpdateRewrite {
                     local_part_rule,
                     destinations,
                     ..Default::default()
                 };
+                if dry_run {
+                    let operation = Operation::UpdateRewrite {
+                        domain: domain.clone(),
+                        name,
+                        update,
+                    };
+                    return dry_run::show(&client, format, &operation, current.as_ref());
+                }
                 let rw = client.update_rewrite(domain, &name, &update).await?;
-                println!("Updated: {}", rw.name);
+                print_item(format, &rw)?;
             }
             RewriteAction::Delete { name } => {
+                let current = if !yes || dry_run {
+                    Some(client.get_rewrite(domain, &name).await?)
+                } else {
+                    None
+                };
+                if let (false, Some(current)) = (yes, &current) {
+                    let question = format!("Delete rewrite {}?", current.name);
+                    confirm::ask(&question, current)?;
+                }
+                if dry_run {
+                    let operation = Operation::DeleteRewrite {
+                        domain: domain.clone(),
+                        name,
+                    };
+                    return dry_run::show(&client, format, &operation, current.as_ref());
+                }
                 let rw = client.delete_rewrite(domain, &name).await?;
-                println!("Deleted: {}", rw.name);
+                print_item(format, &rw)?;
+            }
+            RewriteAction::Test {
+                local_part,
+                propose,
+                name,
+                destinations,
+                order_num,
+            } => {
+                let mut state = client.fetch_domain_state(domain).await?;
+                if let Some(local_part_rule) = propose {
+                    let create = CreateRewrite {
+                        order_num,
+                        ..CreateRewrite::new(name, local_part_rule, destinations)
+                    };
+                    state.rewrites.push(Rewrite::from(&create));
+                }
+                rules::print(format, &rewrite_rules::test_rules(&state, &local_part))?;
             }
         },
 
         Commands::Identities { action } => match action {
             IdentityAction::List { mailbox } => {
                 let identities = client.list_identities(domain, &mailbox).await?;
-                for id in identities {
-                    println!("{}\t{}", id.address, id.name);
-                }
+                print_list(format, &identities)?;
             }
             IdentityAction::Get { mailbox, identity } => {
                 let id = client.get_identity(domain, &mailbox, &identity).await?;
-                println!("{}", serde_json::to_string_pretty(&id)?);
+                print_item(format, &id)?;
             }
             IdentityAction::Create {
                 mailbox,
                 local_part,
                 name,
+                password_args,
             } => {
-                let create = CreateIdentity::new(&local_part, &name);
+                let password = password_args.resolve(None, false)?;
+                let create = CreateIdentity {
+                    password: password.as_ref().map(|p| p.value.clone()),
+                    ..CreateIdentity::new(&local_part, &name)
+                };
+                if dry_run {
+                    let operation = Operation::CreateIdentity {
+                        domain: domain.clone(),
+                        mailbox,
+                        identity: create,
+                    };
+                    return dry_run::show::<Identity>(&client, format, &operation, None);
+                }
+                if let Some(password) = &password {
+                    password.persist()?;
+                }
                 let id = client.create_identity(domain, &mailbox, &create).await?;
-                println!("Created: {}", id.address);
+                if let Some(password) = &password {
+                    password.announce(&id.address);
+                }
+                print_item(format, &id)?;
             }
             IdentityAction::Update {
                 mailbox,
                 identity,
                 name,
+                password_args,
+                may_send,
+                may_receive,
+                may_access_imap,
+                may_access_pop3,
+                may_access_managesieve,
             } => {
+                let password = password_args.resolve(None, false)?;
                 let update = UpdateIdentity {
                     name,
+                    password: password.as_ref().map(|p| p.value.clone()),
+                    may_send,
+                    may_receive,
+                    may_access_imap,
+                    may_access_pop3,
+                    may_access_managesieve,
                     ..Default::default()
                 };
+                let removals = confirm::identity_access_removals(&update);
+                let ask = !yes && !removals.is_empty();
+                let current = if ask || dry_run {
+                    Some(client.get_identity(domain, &mailbox, &identity).await?)
+                } else {
+                    None
+                };
+                if let (true, Some(current)) = (ask, &current) {
+                    let question = format!(
+                        "Update identity {}? This {}.",
+                        current.address,
+                        removals.join(", ")
+                    );
+                    confirm::ask(&question, current)?;
+                }
+                if dry_run {
+                    let operation = Operation::UpdateIdentity {
+                        domain: domain.clone(),
+                        mailbox,
+                        local_part: identity,
+                        update,
+                    };
+                    return dry_run::show(&client, format, &operation, current.as_ref());
+                }
+                if let Some(password) = &password {
+                    password.persist()?;
+                }
                 let id = client
                     .update_identity(domain, &mailbox, &identity, &update)
                     .await?;
-                println!("Updated: {}", id.address);
+                if let Some(password) = &password {
+                    password.announce(&id.address);
+                }
+                print_item(format, &id)?;
             }
             IdentityAction::Delete { mailbox, identity } => {
+                let current = if !yes || dry_run {
+                    Some(client.get_identity(domain, &mailbox, &identity).await?)
+                } else {
+                    None
+                };
+                if let (false, Some(current)) = (yes, &current) {
+                    let question = format!("Delete identity {}?", current.address);
+                    confirm::ask(&question, current)?;
+                }
+                if dry_run {
+                    let operation = Operation::DeleteIdentity {
+                        domain: domain.clone(),
+                        mailbox,
+                        local_part: identity,
+                    };
+                    return dry_run::show(&client, format, &operation, current.as_ref());
+                }
                 let id = client.delete_identity(domain, &mailbox, &identity).await?;
-                println!("Deleted: {}", id.address);
+                print_item(format, &id)?;
             }
         },
+
+        Commands::Config { .. }
+        | Commands::Plan { .. }
+        | Commands::Apply { .. }
+        | Commands::Export { .. }
+        | Commands::Import { .. }
+        | Commands::Diff { .. }
+        | Commands::Domains { .. }
+        | Commands::Resolve { .. }
+        | Commands::Lint(_)
+        | Commands::Graph(_) => {
+            unreachable!("handled before resolving the domain")
+        }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_rules_file_does_not_clash_with_config() {
+        let cli = Cli::try_parse_from([
+            "migadu",
+            "--config",
+            "config.toml",
+            "lint",
+            "--rules-file",
+            "x.yaml",
+        ])
+        .unwrap();
+        assert_eq!(cli.config.as_deref(), Some(Path::new("config.toml")));
+        match cli.command {
+            Commands::Lint(args) => {
+                assert_eq!(args.rules_file.as_deref(), Some(Path::new("x.yaml")))
+            }
+            _ => panic!("expected lint"),
+        }
+    }
+}
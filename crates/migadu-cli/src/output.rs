@@ -0,0 +1,356 @@
+use std::error::Error;
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+use migadu_client::{Alias, Domain, Identity, Mailbox, Rewrite};
+use serde::Serialize;
+
+/// Output format shared by every subcommand.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Pretty-printed JSON
+    Json,
+    /// YAML
+    Yaml,
+    /// Comma-separated values with a header row
+    Csv,
+    /// Aligned columns for humans
+    #[default]
+    Table,
+    /// Tab-separated values with a header row
+    Tsv,
+}
+
+/// A resource that can be rendered as a row with a stable set of columns.
+pub trait Record: Serialize {
+    /// Column names, in output order.
+    const COLUMNS: &'static [&'static str];
+
+    /// Cell values, one per entry in `COLUMNS`.
+    fn row(&self) -> Vec<String>;
+}
+
+impl Record for Domain {
+    const COLUMNS: &'static [&'static str] = &["name", "state", "hosted_dns", "description"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.state.clone().unwrap_or_default(),
+            self.hosted_dns.to_string(),
+            self.description.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+impl Record for Mailbox {
+    const COLUMNS: &'static [&'static str] = &[
+        "address",
+        "name",
+        "is_internal",
+        "may_send",
+        "may_receive",
+        "password_recovery_email",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.address.clone(),
+            self.name.clone(),
+            self.is_internal.to_string(),
+            self.may_send.to_string(),
+            self.may_receive.to_string(),
+            self.password_recovery_email.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+impl Record for Alias {
+    const COLUMNS: &'static [&'static str] = &["address", "destinations", "is_internal"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.address.clone(),
+            self.destinations.join(","),
+            self.is_internal.to_string(),
+        ]
+    }
+}
+
+impl Record for Rewrite {
+    const COLUMNS: &'static [&'static str] =
+        &["name", "local_part_rule", "order_num", "destinations"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.local_part_rule.clone(),
+            self.order_num.map(|n| n.to_string()).unwrap_or_default(),
+            self.destinations.join(","),
+        ]
+    }
+}
+
+impl Record for Identity {
+    const COLUMNS: &'static [&'static str] = &["address", "name", "may_send", "may_receive"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.address.clone(),
+            self.name.clone(),
+            self.may_send.to_string(),
+            self.may_receive.to_string(),
+        ]
+    }
+}
+
+/// Prints a list of resources.
+pub fn print_list<T: Record>(format: OutputFormat, items: &[T]) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(items)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(items)?),
+        OutputFormat::Csv => write_csv(
+            &mut io::stdout().lock(),
+            T::COLUMNS,
+            items.iter().map(Record::row),
+        )?,
+        OutputFormat::Table => write_table(
+            &mut io::stdout().lock(),
+            T::COLUMNS,
+            items.iter().map(Record::row),
+        )?,
+        OutputFormat::Tsv => write_tsv(
+            &mut io::stdout().lock(),
+            T::COLUMNS,
+            items.iter().map(Record::row),
+        )?,
+    }
+    Ok(())
+}
+
+/// Prints a single resource, such as the result of a `get` or a mutation.
+///
+/// Tabular formats use the same columns as [`print_list`], except `table`,
+/// which lists every field of the resource vertically.
+pub fn print_item<T: Record>(format: OutputFormat, item: &T) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(item)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(item)?),
+        OutputFormat::Csv => write_csv(
+            &mut io::stdout().lock(),
+            T::COLUMNS,
+            std::iter::once(item.row()),
+        )?,
+        OutputFormat::Tsv => write_tsv(
+            &mut io::stdout().lock(),
+            T::COLUMNS,
+            std::iter::once(item.row()),
+        )?,
+        OutputFormat::Table => write_details(&mut io::stdout().lock(), item)?,
+    }
+    Ok(())
+}
+
+/// Writes every field of a resource as a vertical `field`/`value` table.
+pub fn write_details<T: Serialize>(out: &mut impl Write, item: &T) -> Result<(), Box<dyn Error>> {
+    let rows: Vec<Vec<String>> = match serde_json::to_value(item)? {
+        serde_json::Value::Object(fields) => fields
+            .into_iter()
+            .map(|(key, value)| vec![key, cell(&value)])
+            .collect(),
+        other => vec![vec![String::new(), cell(&other)]],
+    };
+    write_table(out, &["field", "value"], rows.into_iter())?;
+    Ok(())
+}
+
+/// Renders a JSON value as a single table cell.
+pub fn cell(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(items) => items.iter().map(cell).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+fn write_csv(
+    out: &mut impl Write,
+    columns: &[&str],
+    rows: impl Iterator<Item = Vec<String>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(out);
+    writer.write_record(columns)?;
+    for row in rows {
+        writer.write_record(&row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_tsv(
+    out: &mut impl Write,
+    columns: &[&str],
+    rows: impl Iterator<Item = Vec<String>>,
+) -> io::Result<()> {
+    writeln!(out, "{}", columns.join("\t"))?;
+    for row in rows {
+        let row: Vec<_> = row
+            .iter()
+            .map(|value| value.replace(['\t', '\n', '\r'], " "))
+            .collect();
+        writeln!(out, "{}", row.join("\t"))?;
+    }
+    Ok(())
+}
+
+pub fn write_table(
+    out: &mut impl Write,
+    columns: &[&str],
+    rows: impl Iterator<Item = Vec<String>>,
+) -> io::Result<()> {
+    let header: Vec<String> = columns.iter().map(|c| c.to_uppercase()).collect();
+    let rows: Vec<Vec<String>> = rows
+        .map(|row| {
+            row.iter()
+                .map(|value| value.replace(['\n', '\r'], " "))
+                .collect()
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    for row in std::iter::once(&header).chain(&rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect();
+        writeln!(out, "{}", line.join("  ").trim_end())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(
+        write: impl FnOnce(&mut Vec<u8>, &[&str], std::vec::IntoIter<Vec<String>>),
+        rows: Vec<Vec<&str>>,
+    ) -> String {
+        let rows: Vec<Vec<String>> = rows
+            .into_iter()
+            .map(|row| row.into_iter().map(String::from).collect())
+            .collect();
+        let mut out = Vec::new();
+        write(&mut out, &["address", "name"], rows.into_iter());
+        String::from_utf8(out).unwrap()
+    }
+
+    fn columns_of<T: Record + serde::de::DeserializeOwned>(
+        value: serde_json::Value,
+    ) -> (Vec<&'static str>, usize) {
+        let item: T = serde_json::from_value(value).unwrap();
+        (T::COLUMNS.to_vec(), item.row().len())
+    }
+
+    #[test]
+    fn test_columns_are_stable() {
+        let (columns, cells) = columns_of::<Domain>(json!({ "name": "example.com" }));
+        assert_eq!(columns, ["name", "state", "hosted_dns", "description"]);
+        assert_eq!(cells, columns.len());
+
+        let (columns, cells) = columns_of::<Mailbox>(json!({
+            "local_part": "alice",
+            "domain_name": "example.com",
+            "address": "alice@example.com",
+            "name": "Alice"
+        }));
+        assert_eq!(
+            columns,
+            [
+                "address",
+                "name",
+                "is_internal",
+                "may_send",
+                "may_receive",
+                "password_recovery_email"
+            ]
+        );
+        assert_eq!(cells, columns.len());
+
+        let (columns, cells) = columns_of::<Alias>(json!({
+            "local_part": "info",
+            "domain_name": "example.com",
+            "address": "info@example.com",
+            "destinations": []
+        }));
+        assert_eq!(columns, ["address", "destinations", "is_internal"]);
+        assert_eq!(cells, columns.len());
+
+        let (columns, cells) = columns_of::<Rewrite>(json!({
+            "name": "support",
+            "local_part_rule": "support-*",
+            "destinations": []
+        }));
+        assert_eq!(
+            columns,
+            ["name", "local_part_rule", "order_num", "destinations"]
+        );
+        assert_eq!(cells, columns.len());
+
+        let (columns, cells) = columns_of::<Identity>(json!({
+            "local_part": "sales",
+            "domain_name": "example.com",
+            "address": "sales@example.com",
+            "name": "Sales"
+        }));
+        assert_eq!(columns, ["address", "name", "may_send", "may_receive"]);
+        assert_eq!(cells, columns.len());
+    }
+
+    #[test]
+    fn test_table_aligns_columns() {
+        let table = render(
+            |out, columns, rows| write_table(out, columns, rows).unwrap(),
+            vec![
+                vec!["alice@example.com", "Alice"],
+                vec!["bo@example.com", "Bob\nSmith"],
+            ],
+        );
+        assert_eq!(
+            table,
+            "ADDRESS            NAME\n\
+             alice@example.com  Alice\n\
+             bo@example.com     Bob Smith\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_quotes_special_characters() {
+        let csv = render(
+            |out, columns, rows| write_csv(out, columns, rows).unwrap(),
+            vec![vec!["alice@example.com", "Liddell, \"Alice\""]],
+        );
+        assert_eq!(
+            csv,
+            "address,name\nalice@example.com,\"Liddell, \"\"Alice\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn test_tsv_replaces_tabs_and_newlines() {
+        let tsv = render(
+            |out, columns, rows| write_tsv(out, columns, rows).unwrap(),
+            vec![vec!["alice@example.com", "Alice\tLiddell\r\nJr"]],
+        );
+        assert_eq!(tsv, "address\tname\nalice@example.com\tAlice Liddell  Jr\n");
+    }
+}
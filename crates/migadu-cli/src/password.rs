@@ -0,0 +1,355 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::{self, BufRead, IsTerminal};
+use std::path::PathBuf;
+
+use clap::Args;
+use rand::seq::{IndexedRandom, SliceRandom};
+
+use crate::files;
+
+const LOWERCASE: &[u8] = b"abcdefghijkmnopqrstuvwxyz";
+const UPPERCASE: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+const DIGITS: &[u8] = b"23456789";
+const SYMBOLS: &[u8] = b"!#%+-.:=?@_~";
+
+/// Shortest password `--generate-password` will produce.
+const MIN_GENERATED_LENGTH: usize = 12;
+
+/// Ways to supply a password without putting it on the command line.
+#[derive(Args, Debug, Clone)]
+pub struct PasswordArgs {
+    /// Read the password from the first line of stdin
+    #[arg(long, conflicts_with_all = ["password_prompt", "generate_password"])]
+    pub password_stdin: bool,
+
+    /// Prompt for the password without echoing it
+    #[arg(long, conflicts_with = "generate_password")]
+    pub password_prompt: bool,
+
+    /// Generate a strong random password and print it once
+    #[arg(long)]
+    pub generate_password: bool,
+
+    /// Length of the generated password
+    #[arg(long, default_value_t = 24, requires = "generate_password")]
+    pub length: usize,
+
+    /// Write the generated password to this file (mode 0600) instead of printing it
+    #[arg(long, requires = "generate_password")]
+    pub password_file: Option<PathBuf>,
+}
+
+/// A password obtained from [`PasswordArgs`].
+pub struct Password {
+    pub value: String,
+    generated: bool,
+    file: Option<PathBuf>,
+}
+
+impl PasswordArgs {
+    /// Obtains the password, if one was requested.
+    ///
+    /// `explicit` is a password given directly as an argument. If no source is chosen
+    /// and `prompt_by_default` is set, the user is prompted when stdin is a terminal.
+    pub fn resolve(
+        &self,
+        explicit: Option<String>,
+        prompt_by_default: bool,
+    ) -> Result<Option<Password>, Box<dyn Error>> {
+        let sources = [
+            explicit.is_some(),
+            self.password_stdin,
+            self.password_prompt,
+            self.generate_password,
+        ];
+        if sources.iter().filter(|&&chosen| chosen).count() > 1 {
+            return Err("Give the password in only one way".into());
+        }
+
+        let value = if let Some(password) = explicit {
+            password
+        } else if self.password_stdin {
+            read_stdin()?
+        } else if self.generate_password {
+            generate(self.length)?
+        } else if self.password_prompt || prompt_by_default {
+            if !io::stdin().is_terminal() {
+                return Err(
+                    "No password given: use --password-stdin or --generate-password when not running interactively"
+                        .into(),
+                );
+            }
+            prompt_with_confirmation(|prompt| rpassword::prompt_password(prompt))?
+        } else {
+            return Ok(None);
+        };
+
+        if value.is_empty() {
+            return Err("Password must not be empty".into());
+        }
+
+        Ok(Some(Password {
+            value,
+            generated: self.generate_password,
+            file: self.password_file.clone(),
+        }))
+    }
+}
+
+impl Password {
+    /// Writes a generated password to its `--password-file`, if one was given.
+    ///
+    /// Call this before sending the request that sets the password, so that it is
+    /// never lost once the request succeeds.
+    pub fn persist(&self) -> Result<(), Box<dyn Error>> {
+        match &self.file {
+            Some(path) if self.generated => {
+                files::create_private(path, &format!("{}\n", self.value))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Tells the user about a generated password once the request using it succeeded.
+    ///
+    /// The password goes to stderr so it never mixes with machine-readable output.
+    pub fn announce(&self, address: &str) {
+        if !self.generated {
+            return;
+        }
+        match &self.file {
+            Some(path) => eprintln!(
+                "Generated password for {} written to {}",
+                address,
+                path.display()
+            ),
+            None => eprintln!("Generated password for {}: {}", address, self.value),
+        }
+    }
+}
+
+/// Password options for commands that create many mailboxes at once.
+#[derive(Args, Debug, Clone)]
+pub struct BulkPasswordArgs {
+    /// File mapping mailbox addresses or local parts to passwords (.json, .yaml or .toml)
+    #[arg(long)]
+    pub passwords: Option<PathBuf>,
+
+    /// Generate passwords for other new mailboxes and write them to this new file
+    /// (mode 0600)
+    #[arg(long)]
+    pub generate_passwords: Option<PathBuf>,
+
+    /// Length of generated passwords
+    #[arg(long, default_value_t = 24, requires = "generate_passwords")]
+    pub length: usize,
+}
+
+/// Supplies passwords for mailboxes created in bulk, from a file or by generating them.
+pub struct BulkPasswords {
+    supplied: BTreeMap<String, String>,
+    generate_to: Option<PathBuf>,
+    length: usize,
+    generated: BTreeMap<String, String>,
+    error: Option<String>,
+}
+
+impl BulkPasswordArgs {
+    /// Reads the password file, if one was given.
+    pub fn load(&self) -> Result<BulkPasswords, Box<dyn Error>> {
+        let supplied = match &self.passwords {
+            Some(path) => files::read(path, "password file")?,
+            None => BTreeMap::new(),
+        };
+        Ok(BulkPasswords {
+            supplied,
+            generate_to: self.generate_passwords.clone(),
+            length: self.length,
+            generated: BTreeMap::new(),
+            error: None,
+        })
+    }
+}
+
+impl BulkPasswords {
+    /// Returns the password for a new mailbox at `address`.
+    ///
+    /// The password file is searched by address, then by local part. Otherwise a
+    /// password is generated if `--generate-passwords` was given.
+    pub fn get(&mut self, address: &str) -> Option<String> {
+        let local_part = address.split('@').next().unwrap_or(address);
+        let known = self
+            .supplied
+            .get(address)
+            .or_else(|| self.supplied.get(local_part));
+        if let Some(password) = known {
+            return Some(password.clone());
+        }
+        self.generate_to.as_ref()?;
+        match generate(self.length) {
+            Ok(password) => {
+                self.generated.insert(address.to_string(), password.clone());
+                Some(password)
+            }
+            Err(e) => {
+                self.error.get_or_insert(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Fails if a password could not be generated.
+    pub fn check(&self) -> Result<(), Box<dyn Error>> {
+        match &self.error {
+            Some(e) => Err(e.clone().into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the generated passwords to their file, if any were generated.
+    ///
+    /// Call this before creating the mailboxes, so that no password is lost once a
+    /// mailbox exists.
+    pub fn persist(&self) -> Result<(), Box<dyn Error>> {
+        let Some(path) = self.generate_to.as_deref() else {
+            return Ok(());
+        };
+        if self.generated.is_empty() {
+            return Ok(());
+        }
+        files::write_private(&self.generated, path)?;
+        eprintln!(
+            "Generated passwords for {} mailboxes written to {}",
+            self.generated.len(),
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+/// Generates a random password containing lowercase, uppercase, digit and symbol characters.
+///
+/// Characters that are easily confused (such as `l`, `1`, `O` and `0`) are left out.
+pub fn generate(length: usize) -> Result<String, Box<dyn Error>> {
+    if length < MIN_GENERATED_LENGTH {
+        return Err(format!(
+            "Generated passwords must be at least {} characters long",
+            MIN_GENERATED_LENGTH
+        )
+        .into());
+    }
+
+    let mut rng = rand::rng();
+    let classes = [LOWERCASE, UPPERCASE, DIGITS, SYMBOLS];
+    let all: Vec<u8> = classes.concat();
+
+    let mut password: Vec<u8> = classes
+        .iter()
+        .map(|class| *class.choose(&mut rng).expect("non-empty class"))
+        .collect();
+    password.extend((password.len()..length).map(|_| *all.choose(&mut rng).expect("non-empty")));
+    password.shuffle(&mut rng);
+
+    Ok(String::from_utf8(password).expect("ASCII characters"))
+}
+
+fn read_stdin() -> Result<String, Box<dyn Error>> {
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\n', '\r']).to_string())
+}
+
+/// Asks for the password twice through `prompt`, which shows its argument and reads
+/// a line without echoing it.
+fn prompt_with_confirmation(
+    mut prompt: impl FnMut(&str) -> io::Result<String>,
+) -> Result<String, Box<dyn Error>> {
+    let password = prompt("Password: ")?;
+    let confirmation = prompt("Confirm password: ")?;
+    if password != confirmation {
+        return Err("Passwords do not match".into());
+    }
+    Ok(password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate_args(length: usize, file: Option<PathBuf>) -> PasswordArgs {
+        PasswordArgs {
+            password_stdin: false,
+            password_prompt: false,
+            generate_password: true,
+            length,
+            password_file: file,
+        }
+    }
+
+    #[test]
+    fn test_generated_passwords_use_every_class() {
+        for _ in 0..100 {
+            let password = generate(MIN_GENERATED_LENGTH).unwrap();
+            assert_eq!(password.len(), MIN_GENERATED_LENGTH);
+            for class in [LOWERCASE, UPPERCASE, DIGITS, SYMBOLS] {
+                assert!(
+                    password.bytes().any(|b| class.contains(&b)),
+                    "{} lacks one of {:?}",
+                    password,
+                    String::from_utf8_lossy(class)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_length_is_honoured_down_to_the_minimum() {
+        let password = generate_args(40, None)
+            .resolve(None, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(password.value.len(), 40);
+
+        let error = generate_args(11, None).resolve(None, false).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Generated passwords must be at least 12 characters long"
+        );
+    }
+
+    #[test]
+    fn test_mismatched_confirmation_is_an_error() {
+        let mut answers = ["secret", "secrte"].into_iter();
+        let result = prompt_with_confirmation(|_: &str| Ok(answers.next().unwrap().to_string()));
+        assert_eq!(result.unwrap_err().to_string(), "Passwords do not match");
+
+        let result = prompt_with_confirmation(|_: &str| Ok("secret".to_string()));
+        assert_eq!(result.unwrap(), "secret");
+    }
+
+    #[test]
+    fn test_password_file_is_private_and_never_replaced() {
+        let dir = std::env::temp_dir().join(format!("migadu-password-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("password.txt");
+        let _ = std::fs::remove_file(&path);
+
+        let args = generate_args(16, Some(path.clone()));
+        let password = args.resolve(None, false).unwrap().unwrap();
+        password.persist().unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(written, format!("{}\n", password.value));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let again = args.resolve(None, false).unwrap().unwrap();
+        assert!(again.persist().is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), written);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
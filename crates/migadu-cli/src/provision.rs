@@ -0,0 +1,592 @@
+//! Creating mailboxes in bulk from a CSV file.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use migadu_client::{config, CreateMailbox, MigaduClient, UpdateMailbox};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+
+use crate::confirm;
+use crate::files;
+use crate::output::{print_list, write_table, OutputFormat, Record};
+use crate::password;
+
+/// Options for `mailboxes import`.
+#[derive(Args, Debug, Clone)]
+pub struct ImportArgs {
+    /// CSV file with a header row. Columns: local_part, name and optionally
+    /// password_recovery_email, password, is_internal, may_send, may_receive,
+    /// may_access_imap, may_access_pop3 and may_access_managesieve
+    pub file: PathBuf,
+
+    /// Write the outcome of each row, with generated passwords, to this new CSV file
+    /// (mode 0600); required unless --dry-run is given
+    #[arg(long)]
+    pub results: Option<PathBuf>,
+
+    /// How many mailboxes to create at once
+    #[arg(long, default_value = "4")]
+    pub concurrency: NonZeroUsize,
+
+    /// Length of passwords generated for rows without one
+    #[arg(long, default_value_t = 24)]
+    pub length: usize,
+}
+
+/// Columns the input CSV may have; the first two are required.
+const COLUMNS: &[&str] = &[
+    "local_part",
+    "name",
+    "password_recovery_email",
+    "recovery_email",
+    "password",
+    "is_internal",
+    "may_send",
+    "may_receive",
+    "may_access_imap",
+    "may_access_pop3",
+    "may_access_managesieve",
+];
+
+/// One row of the input CSV. Empty optional fields leave the API defaults.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Row {
+    local_part: String,
+    name: String,
+    #[serde(default, alias = "recovery_email")]
+    password_recovery_email: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    is_internal: Option<bool>,
+    #[serde(default)]
+    may_send: Option<bool>,
+    #[serde(default)]
+    may_receive: Option<bool>,
+    #[serde(default)]
+    may_access_imap: Option<bool>,
+    #[serde(default)]
+    may_access_pop3: Option<bool>,
+    #[serde(default)]
+    may_access_managesieve: Option<bool>,
+}
+
+/// A validated row, ready to be created.
+#[derive(Debug)]
+struct NewMailbox {
+    line: u64,
+    address: String,
+    create: CreateMailbox,
+    /// Permissions to set once the mailbox exists, which creation does not accept.
+    permissions: Option<UpdateMailbox>,
+    generated: bool,
+}
+
+/// One row of the results CSV.
+#[derive(Serialize)]
+struct Outcome {
+    line: u64,
+    address: String,
+    status: &'static str,
+    /// Only set for generated passwords; supplied ones are already known.
+    password: String,
+    error: String,
+}
+
+/// What `--dry-run` shows for each row.
+#[derive(Serialize)]
+struct Planned<'a> {
+    line: u64,
+    address: &'a str,
+    name: &'a str,
+    password_recovery_email: Option<&'a str>,
+    password: &'static str,
+    permissions: Option<&'a UpdateMailbox>,
+}
+
+impl Record for Planned<'_> {
+    const COLUMNS: &'static [&'static str] = &[
+        "line",
+        "address",
+        "name",
+        "password_recovery_email",
+        "password",
+        "permissions",
+    ];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.line.to_string(),
+            self.address.to_string(),
+            self.name.to_string(),
+            self.password_recovery_email.unwrap_or_default().to_string(),
+            self.password.to_string(),
+            self.permissions
+                .map(describe_permissions)
+                .unwrap_or_default(),
+        ]
+    }
+}
+
+impl<'a> From<&'a NewMailbox> for Planned<'a> {
+    fn from(mailbox: &'a NewMailbox) -> Self {
+        Planned {
+            line: mailbox.line,
+            address: &mailbox.address,
+            name: &mailbox.create.name,
+            password_recovery_email: mailbox.create.password_recovery_email.as_deref(),
+            password: if mailbox.generated {
+                "generated"
+            } else {
+                "supplied"
+            },
+            permissions: mailbox.permissions.as_ref(),
+        }
+    }
+}
+
+/// Reads and validates every row of `path`, generating missing passwords.
+///
+/// All problems are reported together, and nothing is created unless the whole file
+/// is valid.
+fn read(path: &Path, domain: &str, length: usize) -> Result<Vec<NewMailbox>, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let headers = reader.headers()?.clone();
+    check_headers(&headers).map_err(|e| format!("Invalid CSV {}: {}", path.display(), e))?;
+
+    let mut mailboxes = Vec::new();
+    let mut errors = Vec::new();
+    let mut seen: HashMap<String, u64> = HashMap::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Invalid CSV {}: {}", path.display(), e))?;
+        let line = record.position().map_or(0, |p| p.line());
+        let row: Row = match record.deserialize(Some(&headers)) {
+            Ok(row) => row,
+            Err(e) => {
+                let message = match e.kind() {
+                    csv::ErrorKind::Deserialize { err, .. } => {
+                        match err.field().and_then(|i| headers.get(i as usize)) {
+                            Some(column) => format!("{}: {}", column, err.kind()),
+                            None => err.kind().to_string(),
+                        }
+                    }
+                    _ => e.to_string(),
+                };
+                errors.push(format!("line {}: {}", line, message));
+                continue;
+            }
+        };
+        if let Err(e) = check(&row) {
+            errors.push(format!("line {}: {}", line, e));
+            continue;
+        }
+        if let Some(first) = seen.insert(row.local_part.to_lowercase(), line) {
+            errors.push(format!(
+                "line {}: {} is already on line {}",
+                line, row.local_part, first
+            ));
+            continue;
+        }
+        mailboxes.push(new_mailbox(row, line, domain, length)?);
+    }
+
+    if !errors.is_empty() {
+        return Err(format!(
+            "Invalid rows in {}:\n  {}",
+            path.display(),
+            errors.join("\n  ")
+        )
+        .into());
+    }
+    if mailboxes.is_empty() {
+        return Err(format!("No mailboxes in {}", path.display()).into());
+    }
+    Ok(mailboxes)
+}
+
+fn check_headers(headers: &csv::StringRecord) -> Result<(), String> {
+    for required in &COLUMNS[..2] {
+        if !headers.iter().any(|h| h == *required) {
+            return Err(format!("missing column {}", required));
+        }
+    }
+    match headers.iter().find(|h| !COLUMNS.contains(h)) {
+        Some(unknown) => Err(format!(
+            "unknown column {} (expected {})",
+            unknown,
+            COLUMNS.join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+fn check(row: &Row) -> Result<(), String> {
+    let local_part = &row.local_part;
+    if local_part.is_empty() {
+        return Err("local_part is empty".into());
+    }
+    if local_part.contains(|c: char| c == '@' || c == ',' || c.is_whitespace()) {
+        return Err(format!("{} is not a valid local part", local_part));
+    }
+    if row.name.is_empty() {
+        return Err(format!("{} has no name", local_part));
+    }
+    if let Some(email) = row.password_recovery_email.as_deref() {
+        match email.split_once('@') {
+            Some((user, host)) if !user.is_empty() && host.contains('.') => {}
+            _ => return Err(format!("{} is not a valid recovery email", email)),
+        }
+    }
+    Ok(())
+}
+
+fn new_mailbox(
+    row: Row,
+    line: u64,
+    domain: &str,
+    length: usize,
+) -> Result<NewMailbox, Box<dyn Error>> {
+    let generated = row.password.as_deref().is_none_or(str::is_empty);
+    let password = match row.password {
+        Some(password) if !password.is_empty() => password,
+        _ => password::generate(length)?,
+    };
+    let permissions = UpdateMailbox {
+        may_send: row.may_send,
+        may_receive: row.may_receive,
+        may_access_imap: row.may_access_imap,
+        may_access_pop3: row.may_access_pop3,
+        may_access_managesieve: row.may_access_managesieve,
+        ..Default::default()
+    };
+    let has_permissions = [
+        row.may_send,
+        row.may_receive,
+        row.may_access_imap,
+        row.may_access_pop3,
+        row.may_access_managesieve,
+    ]
+    .iter()
+    .any(Option::is_some);
+
+    Ok(NewMailbox {
+        line,
+        address: format!("{}@{}", row.local_part, domain),
+        create: CreateMailbox {
+            password_recovery_email: row.password_recovery_email,
+            is_internal: row.is_internal,
+            ..CreateMailbox::new(row.local_part, row.name, password)
+        },
+        permissions: has_permissions.then_some(permissions),
+        generated,
+    })
+}
+
+/// Creates the mailboxes listed in the CSV file, at most `--concurrency` at a time, and
+/// writes the outcome of each row to the results file.
+///
+/// The results file is created before any mailbox, and rewritten as soon as each
+/// outcome is known, so generated passwords survive an interrupted run.
+pub async fn run(
+    client: &MigaduClient,
+    domain: &str,
+    args: &ImportArgs,
+    format: OutputFormat,
+    dry_run: bool,
+    yes: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mailboxes = read(&args.file, domain, args.length)?;
+    if dry_run {
+        return show(format, &mailboxes);
+    }
+    let results = args.results.as_deref().ok_or(
+        "Missing --results: the outcome of each row, with generated passwords, must be saved",
+    )?;
+    if !yes {
+        let planned: Vec<Planned> = mailboxes.iter().map(Planned::from).collect();
+        write_planned(&mut io::stderr().lock(), &planned)?;
+        eprintln!();
+        confirm::prompt(&format!("Create {} mailboxes?", mailboxes.len()))?;
+    }
+
+    files::create_private(results, "")?;
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let total = mailboxes.len();
+    let (mut failed, mut partial) = (0, 0);
+    let mut pending = mailboxes.into_iter();
+    let mut running = JoinSet::new();
+    loop {
+        while running.len() < args.concurrency.get() {
+            let Some(mailbox) = pending.next() else { break };
+            let client = client.clone();
+            let domain = domain.to_string();
+            running.spawn(async move { create(&client, &domain, mailbox).await });
+        }
+        let Some(outcome) = running.join_next().await else {
+            break;
+        };
+        let outcome = outcome?;
+        match outcome.status {
+            "failed" => failed += 1,
+            "partial" => partial += 1,
+            _ => {}
+        }
+        eprintln!(
+            "{} {}{}",
+            outcome.status,
+            outcome.address,
+            if outcome.error.is_empty() {
+                String::new()
+            } else {
+                format!(": {}", outcome.error)
+            }
+        );
+        writer.serialize(&outcome)?;
+        writer.flush()?;
+        config::write_private(results, std::str::from_utf8(writer.get_ref())?)
+            .map_err(|e| format!("Failed to write {}: {}", results.display(), e))?;
+    }
+
+    eprintln!(
+        "Created {} of {} mailboxes{}; results written to {}",
+        total - failed,
+        total,
+        if partial > 0 {
+            format!(", {} of them without their permissions", partial)
+        } else {
+            String::new()
+        },
+        results.display()
+    );
+    match (failed, partial) {
+        (0, 0) => Ok(()),
+        (failed, 0) => Err(format!("{} mailboxes could not be created", failed).into()),
+        (0, partial) => Err(format!(
+            "{} mailboxes were created without their permissions",
+            partial
+        )
+        .into()),
+        (failed, partial) => Err(format!(
+            "{} mailboxes could not be created, and {} were created without their permissions",
+            failed, partial
+        )
+        .into()),
+    }
+}
+
+/// Creates one mailbox and then sets its permissions.
+///
+/// A mailbox whose permissions could not be set is `partial`: it exists, but may allow
+/// more than the row asked for.
+async fn create(client: &MigaduClient, domain: &str, mailbox: NewMailbox) -> Outcome {
+    let mut outcome = Outcome {
+        line: mailbox.line,
+        address: mailbox.address,
+        status: "created",
+        password: String::new(),
+        error: String::new(),
+    };
+    if let Err(e) = client.create_mailbox(domain, &mailbox.create).await {
+        outcome.status = "failed";
+        outcome.error = e.to_string();
+        return outcome;
+    }
+    if mailbox.generated {
+        outcome.password = mailbox.create.password;
+    }
+    if let Some(permissions) = &mailbox.permissions {
+        if let Err(e) = client
+            .update_mailbox(domain, &mailbox.create.local_part, permissions)
+            .await
+        {
+            outcome.status = "partial";
+            outcome.error = format!("setting permissions failed: {}", e);
+        }
+    }
+    outcome
+}
+
+/// Prints the mailboxes a run would create.
+fn show(format: OutputFormat, mailboxes: &[NewMailbox]) -> Result<(), Box<dyn Error>> {
+    let planned: Vec<Planned> = mailboxes.iter().map(Planned::from).collect();
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&planned)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&planned)?),
+        OutputFormat::Csv | OutputFormat::Tsv => print_list(format, &planned)?,
+        OutputFormat::Table => {
+            let mut out = io::stdout().lock();
+            write_planned(&mut out, &planned)?;
+            writeln!(out, "\nDry run: nothing was changed.")?;
+        }
+    }
+    Ok(())
+}
+
+fn write_planned(out: &mut impl Write, planned: &[Planned]) -> io::Result<()> {
+    write_table(out, Planned::COLUMNS, planned.iter().map(Record::row))
+}
+
+/// Lists the permissions an update sets, e.g. "may_send=false, may_access_pop3=false".
+fn describe_permissions(update: &UpdateMailbox) -> String {
+    let Ok(serde_json::Value::Object(fields)) = serde_json::to_value(update) else {
+        return String::new();
+    };
+    fields
+        .iter()
+        .map(|(field, value)| format!("{}={}", field, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_csv(name: &str, text: &str) -> Result<Vec<NewMailbox>, String> {
+        let dir = std::env::temp_dir().join(format!("migadu-provision-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, text).unwrap();
+        read(&path, "example.com", 16).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_headers_must_name_known_columns() {
+        let error = read_csv("missing.csv", "local_part,password\nalice,secret\n").unwrap_err();
+        assert!(error.ends_with("missing column name"), "{}", error);
+
+        let error = read_csv("unknown.csv", "local_part,name,quota\nalice,Alice,1\n").unwrap_err();
+        assert!(error.contains("unknown column quota"), "{}", error);
+    }
+
+    #[test]
+    fn test_invalid_rows_are_reported_together() {
+        let text = "\
+local_part,name,recovery_email,may_send
+alice,Alice,,
+,Nobody,,
+bob smith,Bob,,
+carol,,,
+dave,Dave,dave-at-home,
+erin,Erin,,maybe
+ALICE,Alice Again,,
+";
+        let error = read_csv("invalid.csv", text).unwrap_err();
+        let lines: Vec<&str> = error.lines().skip(1).map(str::trim).collect();
+        assert_eq!(lines.len(), 6, "{}", error);
+        assert_eq!(lines[0], "line 3: local_part is empty");
+        assert_eq!(lines[1], "line 4: bob smith is not a valid local part");
+        assert_eq!(lines[2], "line 5: carol has no name");
+        assert_eq!(
+            lines[3],
+            "line 6: dave-at-home is not a valid recovery email"
+        );
+        assert_eq!(
+            lines[4],
+            "line 7: may_send: provided string was not `true` or `false`"
+        );
+        assert_eq!(lines[5], "line 8: ALICE is already on line 2");
+    }
+
+    #[test]
+    fn test_empty_file_is_an_error() {
+        let error = read_csv("empty.csv", "local_part,name\n").unwrap_err();
+        assert!(error.starts_with("No mailboxes in"), "{}", error);
+    }
+
+    #[test]
+    fn test_rows_become_mailboxes() {
+        let text = "\
+local_part,name,password_recovery_email,password,is_internal,may_send,may_access_pop3
+alice,Alice,alice@home.example,supplied-secret,true,,
+bob,Bob,,,,false,false
+";
+        let mailboxes = read_csv("valid.csv", text).unwrap();
+        assert_eq!(mailboxes.len(), 2);
+
+        let alice = &mailboxes[0];
+        assert_eq!(alice.line, 2);
+        assert_eq!(alice.address, "alice@example.com");
+        assert_eq!(alice.create.local_part, "alice");
+        assert_eq!(alice.create.name, "Alice");
+        assert_eq!(alice.create.password, "supplied-secret");
+        assert_eq!(
+            alice.create.password_recovery_email.as_deref(),
+            Some("alice@home.example")
+        );
+        assert_eq!(alice.create.is_internal, Some(true));
+        assert!(!alice.generated);
+        assert!(alice.permissions.is_none());
+
+        let bob = &mailboxes[1];
+        assert!(bob.generated);
+        assert_eq!(bob.create.password.chars().count(), 16);
+        assert_eq!(bob.create.password_recovery_email, None);
+        let permissions = bob.permissions.as_ref().unwrap();
+        assert_eq!(permissions.may_send, Some(false));
+        assert_eq!(permissions.may_access_pop3, Some(false));
+        assert_eq!(permissions.may_receive, None);
+        assert_eq!(
+            describe_permissions(permissions),
+            "may_send=false, may_access_pop3=false"
+        );
+    }
+
+    #[test]
+    fn test_planned_rows_hide_passwords() {
+        let text = "local_part,name,password,may_send\nalice,Alice,supplied-secret,false\n";
+        let mailboxes = read_csv("planned.csv", text).unwrap();
+        let planned = Planned::from(&mailboxes[0]);
+        assert_eq!(
+            planned.row(),
+            [
+                "2",
+                "alice@example.com",
+                "Alice",
+                "",
+                "supplied",
+                "may_send=false"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_results_file_is_required_unless_dry_running() {
+        let dir = std::env::temp_dir().join(format!("migadu-provision-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("no-results.csv");
+        std::fs::write(&path, "local_part,name\nalice,Alice\n").unwrap();
+        let args = ImportArgs {
+            file: path,
+            results: None,
+            concurrency: NonZeroUsize::new(1).unwrap(),
+            length: 16,
+        };
+        // Nothing listens here, so any request would fail with a different error.
+        let client = MigaduClient::with_base_url("admin@example.com", "key", "http://127.0.0.1:9");
+
+        let import = |dry_run| {
+            run(
+                &client,
+                "example.com",
+                &args,
+                OutputFormat::Json,
+                dry_run,
+                true,
+            )
+        };
+        assert!(import(true).await.is_ok());
+        let error = import(false).await.unwrap_err().to_string();
+        assert!(error.starts_with("Missing --results"), "{}", error);
+    }
+}
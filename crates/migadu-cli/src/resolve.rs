@@ -0,0 +1,146 @@
+use std::error::Error;
+use std::io::{self, Write};
+
+use migadu_client::resolve::{Delivery, Route};
+use serde::Serialize;
+
+use crate::output::{print_list, OutputFormat, Record};
+
+/// One hop of a route, as printed with `--output csv` or `tsv`.
+#[derive(Serialize)]
+struct HopRow {
+    /// Hops from the resolved address, which is at depth 0.
+    depth: usize,
+    /// Address that passed the mail on; empty for the resolved address.
+    from: String,
+    address: String,
+    route: String,
+}
+
+impl Record for HopRow {
+    const COLUMNS: &'static [&'static str] = &["depth", "from", "address", "route"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.depth.to_string(),
+            self.from.clone(),
+            self.address.clone(),
+            self.route.clone(),
+        ]
+    }
+}
+
+/// One row per hop, each followed by the hops it leads to.
+fn rows(delivery: &Delivery, from: &str, depth: usize, hops: &mut Vec<HopRow>) {
+    hops.push(HopRow {
+        depth,
+        from: from.to_string(),
+        address: delivery.address.clone(),
+        route: describe(&delivery.route),
+    });
+    for next in &delivery.destinations {
+        rows(next, &delivery.address, depth + 1, hops);
+    }
+}
+
+/// Prints where mail to an address goes, as a tree in table mode and one row per hop
+/// in CSV and TSV.
+pub fn print(format: OutputFormat, delivery: &Delivery) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(delivery)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(delivery)?),
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let mut hops = Vec::new();
+            rows(delivery, "", 0, &mut hops);
+            print_list(format, &hops)?
+        }
+        OutputFormat::Table => {
+            let mut out = io::stdout().lock();
+            writeln!(out, "{}  {}", delivery.address, describe(&delivery.route))?;
+            write_destinations(&mut out, delivery, "")?;
+        }
+    }
+    Ok(())
+}
+
+fn write_destinations(out: &mut impl Write, delivery: &Delivery, indent: &str) -> io::Result<()> {
+    let count = delivery.destinations.len();
+    for (i, next) in delivery.destinations.iter().enumerate() {
+        let last = i + 1 == count;
+        let branch = if last { "└── " } else { "├── " };
+        writeln!(
+            out,
+            "{}{}{}  {}",
+            indent,
+            branch,
+            next.address,
+            describe(&next.route)
+        )?;
+        let indent = format!("{}{}", indent, if last { "    " } else { "│   " });
+        write_destinations(out, next, &indent)?;
+    }
+    Ok(())
+}
+
+fn describe(route: &Route) -> String {
+    match route {
+        Route::Mailbox { internal: false } => "mailbox".to_string(),
+        Route::Mailbox { internal: true } => "mailbox (internal)".to_string(),
+        Route::Identity { mailbox } => format!("identity of {}", mailbox),
+        Route::Alias { internal: false } => "alias".to_string(),
+        Route::Alias { internal: true } => "alias (internal)".to_string(),
+        Route::Rewrite {
+            name,
+            local_part_rule,
+            order_num,
+        } => match order_num {
+            Some(order) => format!("rewrite {} ({}, order {})", name, local_part_rule, order),
+            None => format!("rewrite {} ({})", name, local_part_rule),
+        },
+        Route::Rejected { reason } => format!("rejected: {}", reason),
+        Route::Undeliverable => "undeliverable: nothing matches this address".to_string(),
+        Route::External => "external".to_string(),
+        Route::Loop => "loop: already delivered above".to_string(),
+        Route::TooDeep => "not followed: too many hops".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use migadu_client::resolve::resolve_address;
+    use migadu_client::state::DomainState;
+    use serde_json::json;
+
+    #[test]
+    fn test_rows_list_each_hop() {
+        let domain: DomainState = serde_json::from_value(json!({
+            "domain": "example.com",
+            "aliases": [{
+                "local_part": "info",
+                "domain_name": "example.com",
+                "address": "info@example.com",
+                "destinations": ["bob@partner.example", "info@example.com"]
+            }]
+        }))
+        .unwrap();
+        let delivery = resolve_address("info@example.com", &[domain]);
+
+        let mut hops = Vec::new();
+        rows(&delivery, "", 0, &mut hops);
+        let hops: Vec<_> = hops.iter().map(Record::row).collect();
+        assert_eq!(
+            hops,
+            [
+                ["0", "", "info@example.com", "alias"],
+                ["1", "info@example.com", "bob@partner.example", "external"],
+                [
+                    "1",
+                    "info@example.com",
+                    "info@example.com",
+                    "loop: already delivered above"
+                ],
+            ]
+        );
+    }
+}
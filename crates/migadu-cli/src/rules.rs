@@ -0,0 +1,133 @@
+use std::error::Error;
+use std::io::{self, Write};
+
+use migadu_client::rewrite_rules::RuleTest;
+use serde::Serialize;
+
+use crate::output::{print_list, write_table, OutputFormat, Record};
+
+/// A rule, whether it matches and why it can never match, as printed with
+/// `--output csv` or `tsv`.
+#[derive(Serialize)]
+struct RuleRow {
+    order_num: Option<i32>,
+    name: String,
+    local_part_rule: String,
+    /// `first match`, `yes` or `no`.
+    matches: &'static str,
+    /// Why the rule can never match, if it cannot.
+    problem: String,
+}
+
+impl Record for RuleRow {
+    const COLUMNS: &'static [&'static str] =
+        &["order_num", "name", "local_part_rule", "matches", "problem"];
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.order_num.map(|n| n.to_string()).unwrap_or_default(),
+            self.name.clone(),
+            self.local_part_rule.clone(),
+            self.matches.to_string(),
+            self.problem.clone(),
+        ]
+    }
+}
+
+/// One row per rule, in evaluation order.
+fn rows(test: &RuleTest) -> Vec<RuleRow> {
+    let mut first = true;
+    test.rules
+        .iter()
+        .map(|rule| {
+            let matches = match (rule.matches, first) {
+                (true, true) => {
+                    first = false;
+                    "first match"
+                }
+                (true, false) => "yes",
+                (false, _) => "no",
+            };
+            let problems: Vec<String> = test
+                .warnings
+                .iter()
+                .filter(|warning| warning.rule == rule.name)
+                .map(ToString::to_string)
+                .collect();
+            RuleRow {
+                order_num: rule.order_num,
+                name: rule.name.clone(),
+                local_part_rule: rule.local_part_rule.clone(),
+                matches,
+                problem: problems.join("; "),
+            }
+        })
+        .collect()
+}
+
+/// Prints which rewrite rule handles a local part, and any rules that can never match.
+pub fn print(format: OutputFormat, test: &RuleTest) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(test)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(test)?),
+        OutputFormat::Csv | OutputFormat::Tsv => print_list(format, &rows(test))?,
+        OutputFormat::Table => {
+            let mut out = io::stdout().lock();
+            let rows = rows(test).into_iter().map(|row| {
+                let mut row = row.row();
+                row.truncate(4);
+                row
+            });
+            write_table(
+                &mut out,
+                &["order", "name", "local part rule", "matches"],
+                rows,
+            )?;
+            writeln!(out)?;
+
+            match (&test.handled_by, &test.matched) {
+                (Some(kind), _) => writeln!(
+                    out,
+                    "{} goes to the {} of that name; rewrite rules are not consulted.",
+                    test.local_part, kind
+                )?,
+                (None, Some(rule)) => {
+                    writeln!(out, "{} is handled by rewrite {}.", test.local_part, rule)?
+                }
+                (None, None) => writeln!(out, "No rule matches {}.", test.local_part)?,
+            }
+            for warning in &test.warnings {
+                writeln!(out, "Warning: {}", warning)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use migadu_client::rewrite_rules::test_rules;
+    use migadu_client::state::DomainState;
+    use serde_json::json;
+
+    #[test]
+    fn test_rows_list_each_rule_with_its_match_and_problem() {
+        let domain: DomainState = serde_json::from_value(json!({
+            "domain": "example.com",
+            "rewrites": [
+                { "name": "all", "local_part_rule": "*", "order_num": 1, "destinations": [] },
+                { "name": "support", "local_part_rule": "support-*", "order_num": 2, "destinations": [] }
+            ]
+        }))
+        .unwrap();
+
+        let rows: Vec<_> = rows(&test_rules(&domain, "support-billing"))
+            .iter()
+            .map(Record::row)
+            .collect();
+        assert_eq!(rows[0], ["1", "all", "*", "first match", ""]);
+        assert_eq!(rows[1][..4], ["2", "support", "support-*", "yes"]);
+        assert!(rows[1][4].contains("is shadowed by all"), "{}", rows[1][4]);
+    }
+}
@@ -1,5 +1,6 @@
 use serde::Deserialize;
 
+use super::paths;
 use crate::client::MigaduClient;
 use crate::error::Result;
 use crate::types::{Alias, CreateAlias, UpdateAlias};
@@ -12,20 +13,20 @@ struct AliasesResponse {
 impl MigaduClient {
     /// Lists all aliases for a domain.
     pub async fn list_aliases(&self, domain: &str) -> Result<Vec<Alias>> {
-        let path = format!("/domains/{}/aliases", domain);
+        let path = paths::aliases(domain)?;
         let response: AliasesResponse = self.get(&path).await?;
         Ok(response.address_aliases)
     }
 
     /// Gets a specific alias by its local part.
     pub async fn get_alias(&self, domain: &str, local_part: &str) -> Result<Alias> {
-        let path = format!("/domains/{}/aliases/{}", domain, local_part);
+        let path = paths::alias(domain, local_part)?;
         self.get(&path).await
     }
 
     /// Creates a new alias.
     pub async fn create_alias(&self, domain: &str, alias: &CreateAlias) -> Result<Alias> {
-        let path = format!("/domains/{}/aliases", domain);
+        let path = paths::aliases(domain)?;
         self.post(&path, alias).await
     }
 
@@ -36,13 +37,13 @@ impl MigaduClient {
         local_part: &str,
         update: &UpdateAlias,
     ) -> Result<Alias> {
-        let path = format!("/domains/{}/aliases/{}", domain, local_part);
+        let path = paths::alias(domain, local_part)?;
         self.put(&path, update).await
     }
 
     /// Deletes an alias.
     pub async fn delete_alias(&self, domain: &str, local_part: &str) -> Result<Alias> {
-        let path = format!("/domains/{}/aliases/{}", domain, local_part);
+        let path = paths::alias(domain, local_part)?;
         self.delete(&path).await
     }
 }
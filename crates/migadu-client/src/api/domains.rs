@@ -0,0 +1,26 @@
+use serde::Deserialize;
+
+use super::paths;
+use crate::client::MigaduClient;
+use crate::error::Result;
+use crate::types::Domain;
+
+#[derive(Deserialize)]
+struct DomainsResponse {
+    domains: Vec<Domain>,
+}
+
+impl MigaduClient {
+    /// Lists all domains on the account.
+    pub async fn list_domains(&self) -> Result<Vec<Domain>> {
+        let path = paths::domains();
+        let response: DomainsResponse = self.get(&path).await?;
+        Ok(response.domains)
+    }
+
+    /// Gets a specific domain by name.
+    pub async fn get_domain(&self, domain: &str) -> Result<Domain> {
+        let path = paths::domain(domain)?;
+        self.get(&path).await
+    }
+}
@@ -1,5 +1,6 @@
 use serde::Deserialize;
 
+use super::paths;
 use crate::client::MigaduClient;
 use crate::error::Result;
 use crate::types::{CreateForwarding, Forwarding, UpdateForwarding};
@@ -16,10 +17,7 @@ impl MigaduClient {
         domain: &str,
         mailbox_local_part: &str,
     ) -> Result<Vec<Forwarding>> {
-        let path = format!(
-            "/domains/{}/mailboxes/{}/forwardings",
-            domain, mailbox_local_part
-        );
+        let path = paths::forwardings(domain, mailbox_local_part)?;
         let response: ForwardingsResponse = self.get(&path).await?;
         Ok(response.forwardings)
     }
@@ -31,10 +29,7 @@ impl MigaduClient {
         mailbox_local_part: &str,
         address: &str,
     ) -> Result<Forwarding> {
-        let path = format!(
-            "/domains/{}/mailboxes/{}/forwardings/{}",
-            domain, mailbox_local_part, address
-        );
+        let path = paths::forwarding(domain, mailbox_local_part, address)?;
         self.get(&path).await
     }
 
@@ -45,10 +40,7 @@ impl MigaduClient {
         mailbox_local_part: &str,
         forwarding: &CreateForwarding,
     ) -> Result<Forwarding> {
-        let path = format!(
-            "/domains/{}/mailboxes/{}/forwardings",
-            domain, mailbox_local_part
-        );
+        let path = paths::forwardings(domain, mailbox_local_part)?;
         self.post(&path, forwarding).await
     }
 
@@ -60,10 +52,7 @@ impl MigaduClient {
         address: &str,
         update: &UpdateForwarding,
     ) -> Result<Forwarding> {
-        let path = format!(
-            "/domains/{}/mailboxes/{}/forwardings/{}",
-            domain, mailbox_local_part, address
-        );
+        let path = paths::forwarding(domain, mailbox_local_part, address)?;
         self.put(&path, update).await
     }
 
@@ -74,10 +63,7 @@ impl MigaduClient {
         mailbox_local_part: &str,
         address: &str,
     ) -> Result<Forwarding> {
-        let path = format!(
-            "/domains/{}/mailboxes/{}/forwardings/{}",
-            domain, mailbox_local_part, address
-        );
+        let path = paths::forwarding(domain, mailbox_local_part, address)?;
         self.delete(&path).await
     }
 }
@@ -1,5 +1,6 @@
 use serde::Deserialize;
 
+use super::paths;
 use crate::client::MigaduClient;
 use crate::error::Result;
 use crate::types::{CreateIdentity, Identity, UpdateIdentity};
@@ -16,10 +17,7 @@ impl MigaduClient {
         domain: &str,
         mailbox_local_part: &str,
     ) -> Result<Vec<Identity>> {
-        let path = format!(
-            "/domains/{}/mailboxes/{}/identities",
-            domain, mailbox_local_part
-        );
+        let path = paths::identities(domain, mailbox_local_part)?;
         let response: IdentitiesResponse = self.get(&path).await?;
         Ok(response.identities)
     }
@@ -31,10 +29,7 @@ impl MigaduClient {
         mailbox_local_part: &str,
         identity_local_part: &str,
     ) -> Result<Identity> {
-        let path = format!(
-            "/domains/{}/mailboxes/{}/identities/{}",
-            domain, mailbox_local_part, identity_local_part
-        );
+        let path = paths::identity(domain, mailbox_local_part, identity_local_part)?;
         self.get(&path).await
     }
 
@@ -45,10 +40,7 @@ impl MigaduClient {
         mailbox_local_part: &str,
         identity: &CreateIdentity,
     ) -> Result<Identity> {
-        let path = format!(
-            "/domains/{}/mailboxes/{}/identities",
-            domain, mailbox_local_part
-        );
+        let path = paths::identities(domain, mailbox_local_part)?;
         self.post(&path, identity).await
     }
 
@@ -60,10 +52,7 @@ impl MigaduClient {
         identity_local_part: &str,
         update: &UpdateIdentity,
     ) -> Result<Identity> {
-        let path = format!(
-            "/domains/{}/mailboxes/{}/identities/{}",
-            domain, mailbox_local_part, identity_local_part
-        );
+        let path = paths::identity(domain, mailbox_local_part, identity_local_part)?;
         self.put(&path, update).await
     }
 
@@ -74,10 +63,7 @@ impl MigaduClient {
         mailbox_local_part: &str,
         identity_local_part: &str,
     ) -> Result<Identity> {
-        let path = format!(
-            "/domains/{}/mailboxes/{}/identities/{}",
-            domain, mailbox_local_part, identity_local_part
-        );
+        let path = paths::identity(domain, mailbox_local_part, identity_local_part)?;
         self.delete(&path).await
     }
 }
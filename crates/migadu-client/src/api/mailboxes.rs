@@ -1,5 +1,6 @@
 use serde::Deserialize;
 
+use super::paths;
 use crate::client::MigaduClient;
 use crate::error::Result;
 use crate::types::{CreateMailbox, Mailbox, UpdateMailbox};
@@ -12,20 +13,20 @@ struct MailboxesResponse {
 impl MigaduClient {
     /// Lists all mailboxes for a domain.
     pub async fn list_mailboxes(&self, domain: &str) -> Result<Vec<Mailbox>> {
-        let path = format!("/domains/{}/mailboxes", domain);
+        let path = paths::mailboxes(domain)?;
         let response: MailboxesResponse = self.get(&path).await?;
         Ok(response.mailboxes)
     }
 
     /// Gets a specific mailbox by its local part.
     pub async fn get_mailbox(&self, domain: &str, local_part: &str) -> Result<Mailbox> {
-        let path = format!("/domains/{}/mailboxes/{}", domain, local_part);
+        let path = paths::mailbox(domain, local_part)?;
         self.get(&path).await
     }
 
     /// Creates a new mailbox.
     pub async fn create_mailbox(&self, domain: &str, mailbox: &CreateMailbox) -> Result<Mailbox> {
-        let path = format!("/domains/{}/mailboxes", domain);
+        let path = paths::mailboxes(domain)?;
         self.post(&path, mailbox).await
     }
 
@@ -36,13 +37,13 @@ impl MigaduClient {
         local_part: &str,
         update: &UpdateMailbox,
     ) -> Result<Mailbox> {
-        let path = format!("/domains/{}/mailboxes/{}", domain, local_part);
+        let path = paths::mailbox(domain, local_part)?;
         self.put(&path, update).await
     }
 
     /// Deletes a mailbox.
     pub async fn delete_mailbox(&self, domain: &str, local_part: &str) -> Result<Mailbox> {
-        let path = format!("/domains/{}/mailboxes/{}", domain, local_part);
+        let path = paths::mailbox(domain, local_part)?;
         self.delete(&path).await
     }
 }
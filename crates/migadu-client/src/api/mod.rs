@@ -1,5 +1,7 @@
 mod aliases;
+mod domains;
 mod forwardings;
 mod identities;
 mod mailboxes;
+pub(crate) mod paths;
 mod rewrites;
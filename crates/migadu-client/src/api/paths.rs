@@ -0,0 +1,111 @@
+//! API paths for each resource, shared by the request methods and [`crate::Operation`].
+//!
+//! Every value is percent-encoded into a single path segment, so a name containing `/`
+//! or `?` cannot address a different resource than the one it names. Empty values and
+//! `.` or `..`, which URLs collapse into the parent path, are refused with
+//! [`Error::Path`].
+
+use crate::error::{Error, Result};
+
+pub(crate) fn domains() -> String {
+    "/domains".to_string()
+}
+
+pub(crate) fn domain(domain: &str) -> Result<String> {
+    Ok(format!("/domains/{}", segment(domain)?))
+}
+
+pub(crate) fn mailboxes(domain: &str) -> Result<String> {
+    Ok(format!("/domains/{}/mailboxes", segment(domain)?))
+}
+
+pub(crate) fn mailbox(domain: &str, local_part: &str) -> Result<String> {
+    Ok(format!(
+        "/domains/{}/mailboxes/{}",
+        segment(domain)?,
+        segment(local_part)?
+    ))
+}
+
+pub(crate) fn aliases(domain: &str) -> Result<String> {
+    Ok(format!("/domains/{}/aliases", segment(domain)?))
+}
+
+pub(crate) fn alias(domain: &str, local_part: &str) -> Result<String> {
+    Ok(format!(
+        "/domains/{}/aliases/{}",
+        segment(domain)?,
+        segment(local_part)?
+    ))
+}
+
+pub(crate) fn rewrites(domain: &str) -> Result<String> {
+    Ok(format!("/domains/{}/rewrites", segment(domain)?))
+}
+
+pub(crate) fn rewrite(domain: &str, name: &str) -> Result<String> {
+    Ok(format!(
+        "/domains/{}/rewrites/{}",
+        segment(domain)?,
+        segment(name)?
+    ))
+}
+
+pub(crate) fn identities(domain: &str, mailbox_local_part: &str) -> Result<String> {
+    Ok(format!(
+        "/domains/{}/mailboxes/{}/identities",
+        segment(domain)?,
+        segment(mailbox_local_part)?
+    ))
+}
+
+pub(crate) fn identity(
+    domain: &str,
+    mailbox_local_part: &str,
+    identity_local_part: &str,
+) -> Result<String> {
+    Ok(format!(
+        "/domains/{}/mailboxes/{}/identities/{}",
+        segment(domain)?,
+        segment(mailbox_local_part)?,
+        segment(identity_local_part)?
+    ))
+}
+
+pub(crate) fn forwardings(domain: &str, mailbox_local_part: &str) -> Result<String> {
+    Ok(format!(
+        "/domains/{}/mailboxes/{}/forwardings",
+        segment(domain)?,
+        segment(mailbox_local_part)?
+    ))
+}
+
+pub(crate) fn forwarding(domain: &str, mailbox_local_part: &str, address: &str) -> Result<String> {
+    Ok(format!(
+        "/domains/{}/mailboxes/{}/forwardings/{}",
+        segment(domain)?,
+        segment(mailbox_local_part)?,
+        segment(address)?
+    ))
+}
+
+/// Percent-encodes `value` for use as one path segment. Unreserved characters and `@`,
+/// which addresses contain, are kept as they are.
+fn segment(value: &str) -> Result<String> {
+    if matches!(value, "" | "." | "..") {
+        return Err(Error::Path(format!(
+            "{:?} is not a valid name or address",
+            value
+        )));
+    }
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    Ok(encoded)
+}
@@ -1,5 +1,6 @@
 use serde::Deserialize;
 
+use super::paths;
 use crate::client::MigaduClient;
 use crate::error::Result;
 use crate::types::{CreateRewrite, Rewrite, UpdateRewrite};
@@ -12,20 +13,20 @@ struct RewritesResponse {
 impl MigaduClient {
     /// Lists all rewrite rules for a domain.
     pub async fn list_rewrites(&self, domain: &str) -> Result<Vec<Rewrite>> {
-        let path = format!("/domains/{}/rewrites", domain);
+        let path = paths::rewrites(domain)?;
         let response: RewritesResponse = self.get(&path).await?;
         Ok(response.rewrites)
     }
 
     /// Gets a specific rewrite rule by its name.
     pub async fn get_rewrite(&self, domain: &str, name: &str) -> Result<Rewrite> {
-        let path = format!("/domains/{}/rewrites/{}", domain, name);
+        let path = paths::rewrite(domain, name)?;
         self.get(&path).await
     }
 
     /// Creates a new rewrite rule.
     pub async fn create_rewrite(&self, domain: &str, rewrite: &CreateRewrite) -> Result<Rewrite> {
-        let path = format!("/domains/{}/rewrites", domain);
+        let path = paths::rewrites(domain)?;
         self.post(&path, rewrite).await
     }
 
@@ -36,13 +37,13 @@ impl MigaduClient {
         name: &str,
         update: &UpdateRewrite,
     ) -> Result<Rewrite> {
-        let path = format!("/domains/{}/rewrites/{}", domain, name);
+        let path = paths::rewrite(domain, name)?;
         self.put(&path, update).await
     }
 
     /// Deletes a rewrite rule.
     pub async fn delete_rewrite(&self, domain: &str, name: &str) -> Result<Rewrite> {
-        let path = format!("/domains/{}/rewrites/{}", domain, name);
+        let path = paths::rewrite(domain, name)?;
         self.delete(&path).await
     }
 }
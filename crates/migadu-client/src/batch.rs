@@ -0,0 +1,194 @@
+//! Executing many operations at once.
+//!
+//! A batch is a list of [`Operation`]s of any kind, sent through
+//! [`MigaduClient::execute`] a few at a time. Each operation gets its own result,
+//! reported in the order the operations were given.
+
+use std::time::Duration;
+
+use futures_util::stream::{FuturesUnordered, StreamExt};
+
+use crate::client::MigaduClient;
+use crate::error::{Error, Result};
+use crate::operation::{Operation, OperationOutput};
+
+/// HTTP status the API uses when requests are being rate limited.
+const TOO_MANY_REQUESTS: u16 = 429;
+
+/// How to execute a batch.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchOptions {
+    /// How many operations may be in flight at once. Zero is treated as one.
+    pub concurrency: usize,
+    /// Start no further operations once one fails. Operations already in flight
+    /// still finish.
+    pub stop_on_error: bool,
+    /// How many times to retry an operation that was rejected by rate limiting.
+    pub rate_limit_retries: u32,
+    /// How long to wait before the first retry; the wait doubles on each retry. A
+    /// `Retry-After` header in the rejection takes precedence.
+    pub rate_limit_delay: Duration,
+    /// Longest wait before a retry, however long the doubled delay or a `Retry-After`
+    /// header would make it.
+    pub max_rate_limit_delay: Duration,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            stop_on_error: false,
+            rate_limit_retries: 3,
+            rate_limit_delay: Duration::from_secs(1),
+            max_rate_limit_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+/// What happened to one operation of a batch.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum BatchResult {
+    /// The operation succeeded, returning the affected resource.
+    Succeeded(OperationOutput),
+    /// The operation failed.
+    Failed(Error),
+    /// The operation was not started because an earlier one failed and
+    /// [`BatchOptions::stop_on_error`] was set.
+    Skipped,
+}
+
+impl BatchResult {
+    /// Returns `true` if the operation succeeded.
+    pub fn is_success(&self) -> bool {
+        matches!(self, BatchResult::Succeeded(_))
+    }
+}
+
+/// Results of a batch, one per operation and in the same order.
+#[derive(Debug)]
+pub struct BatchReport {
+    pub results: Vec<BatchResult>,
+}
+
+impl BatchReport {
+    /// Number of operations that succeeded.
+    pub fn succeeded(&self) -> usize {
+        self.results.iter().filter(|r| r.is_success()).count()
+    }
+
+    /// Number of operations that failed.
+    pub fn failed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|r| matches!(r, BatchResult::Failed(_)))
+            .count()
+    }
+
+    /// Number of operations that were never started.
+    pub fn skipped(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|r| matches!(r, BatchResult::Skipped))
+            .count()
+    }
+
+    /// Returns `true` if every operation succeeded.
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(BatchResult::is_success)
+    }
+}
+
+/// Passed to the progress callback each time an operation finishes.
+#[derive(Debug)]
+pub struct Progress<'a> {
+    /// Position of the operation in the batch.
+    pub index: usize,
+    /// The operation that finished.
+    pub operation: &'a Operation,
+    /// Its result.
+    pub result: &'a Result<OperationOutput>,
+    /// How many operations have finished so far, including this one.
+    pub completed: usize,
+    /// How many operations the batch contains.
+    pub total: usize,
+}
+
+impl MigaduClient {
+    /// Executes `operations`, at most [`BatchOptions::concurrency`] at a time.
+    ///
+    /// Operations rejected by rate limiting (HTTP 429) are retried after a delay, or
+    /// after as long as the API's `Retry-After` header asks.
+    /// `progress` is called as each operation finishes, in completion order.
+    pub async fn execute_batch(
+        &self,
+        operations: &[Operation],
+        options: BatchOptions,
+        mut progress: impl FnMut(Progress<'_>),
+    ) -> BatchReport {
+        let total = operations.len();
+        let mut results: Vec<BatchResult> = (0..total).map(|_| BatchResult::Skipped).collect();
+        let mut pending = operations.iter().enumerate();
+        let mut running = FuturesUnordered::new();
+        let mut completed = 0;
+        let mut stopped = false;
+
+        loop {
+            while !stopped && running.len() < options.concurrency.max(1) {
+                let Some((index, operation)) = pending.next() else {
+                    break;
+                };
+                running.push(async move {
+                    let result = self.execute_with_retries(operation, &options).await;
+                    (index, result)
+                });
+            }
+            let Some((index, result)) = running.next().await else {
+                break;
+            };
+
+            completed += 1;
+            progress(Progress {
+                index,
+                operation: &operations[index],
+                result: &result,
+                completed,
+                total,
+            });
+            results[index] = match result {
+                Ok(output) => BatchResult::Succeeded(output),
+                Err(e) => {
+                    stopped |= options.stop_on_error;
+                    BatchResult::Failed(e)
+                }
+            };
+        }
+
+        BatchReport { results }
+    }
+
+    async fn execute_with_retries(
+        &self,
+        operation: &Operation,
+        options: &BatchOptions,
+    ) -> Result<OperationOutput> {
+        let mut delay = options.rate_limit_delay;
+        let mut retries = 0;
+        loop {
+            match self.execute(operation).await {
+                Err(
+                    error @ Error::Api {
+                        status: TOO_MANY_REQUESTS,
+                        ..
+                    },
+                ) if retries < options.rate_limit_retries => {
+                    let wait = error.retry_after().unwrap_or(delay);
+                    tokio::time::sleep(wait.min(options.max_rate_limit_delay)).await;
+                    delay = delay.saturating_mul(2);
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
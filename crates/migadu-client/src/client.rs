@@ -1,3 +1,6 @@
+use std::time::Duration;
+
+use reqwest::header::RETRY_AFTER;
 use reqwest::{Client, RequestBuilder, Response};
 use serde::{de::DeserializeOwned, Serialize};
 
@@ -47,7 +50,7 @@ impl MigaduClient {
         req.basic_auth(&self.email, Some(&self.api_key))
     }
 
-    fn url(&self, path: &str) -> String {
+    pub(crate) fn url(&self, path: &str) -> String {
         format!("{}{}", self.base_url, path)
     }
 
@@ -55,6 +58,12 @@ impl MigaduClient {
         let status = response.status();
 
         if !status.is_success() {
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+                .map(Duration::from_secs);
             let message = response
                 .text()
                 .await
@@ -62,6 +71,7 @@ impl MigaduClient {
             return Err(Error::Api {
                 status: status.as_u16(),
                 message,
+                retry_after,
             });
         }
 
@@ -0,0 +1,177 @@
+//! Copying the configuration of one domain to another.
+//!
+//! Cloning is an [import](crate::import) of the source domain's live state into the
+//! target domain, with two optional extras for rebranding: destinations on the source
+//! domain can be rewritten to the target domain, and the source domain can be left
+//! forwarding to the new addresses.
+
+use crate::client::MigaduClient;
+use crate::diff::{self, FieldChange};
+use crate::error::Result;
+use crate::import::{plan_import, ConflictStrategy, ImportPlan};
+use crate::operation::Operation;
+use crate::plan::{Change, ChangeAction};
+use crate::state::{DomainState, ResourceKind};
+use crate::types::{CreateForwarding, Mailbox, UpdateAlias};
+
+/// How to clone a domain.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CloneOptions {
+    /// What to do with resources that already exist on the target domain.
+    pub conflict: ConflictStrategy,
+    /// Point alias and rewrite destinations on the source domain at the target domain.
+    pub rewrite_destinations: bool,
+    /// Make the source domain forward to the target: each source mailbox gets a
+    /// forwarding to its new address, and each source alias delivers to its new
+    /// address instead of its old destinations.
+    pub leave_forwarding: bool,
+}
+
+impl MigaduClient {
+    /// Fetches the live state of both domains and plans cloning `from` into `to`.
+    ///
+    /// See [`plan_clone`].
+    pub async fn plan_clone(
+        &self,
+        from: &str,
+        to: &str,
+        options: CloneOptions,
+        password: impl FnMut(&Mailbox) -> Option<String>,
+    ) -> Result<ImportPlan> {
+        let source = self.fetch_domain_state(from).await?;
+        let target = self.fetch_domain_state(to).await?;
+        plan_clone(&source, &target, options, password)
+    }
+}
+
+/// Plans copying the mailboxes, identities, forwardings, aliases and rewrites of
+/// `source` to the domain whose live state is `target`.
+///
+/// Mailbox passwords cannot be copied; `password` supplies them as in
+/// [`plan_import`]. Changes to the source domain, if any, come last.
+pub fn plan_clone(
+    source: &DomainState,
+    target: &DomainState,
+    options: CloneOptions,
+    password: impl FnMut(&Mailbox) -> Option<String>,
+) -> Result<ImportPlan> {
+    let from = source.domain.as_str();
+    let to = target.domain.as_str();
+
+    let mut copy = source.clone();
+    if options.rewrite_destinations {
+        for alias in &mut copy.aliases {
+            rewrite_domain(&mut alias.destinations, from, to);
+        }
+        for rewrite in &mut copy.rewrites {
+            rewrite_domain(&mut rewrite.destinations, from, to);
+        }
+    }
+
+    let mut import = plan_import(&copy, target, options.conflict, password)?;
+    if options.leave_forwarding {
+        import.plan.changes.extend(forwarding_changes(source, to)?);
+    }
+    Ok(import)
+}
+
+/// Replaces the domain of addresses on `from` with `to`.
+fn rewrite_domain(addresses: &mut [String], from: &str, to: &str) {
+    for address in addresses {
+        if let Some((local_part, domain)) = address.rsplit_once('@') {
+            if domain.eq_ignore_ascii_case(from) {
+                *address = format!("{}@{}", local_part, to);
+            }
+        }
+    }
+}
+
+/// Plans forwarding every mailbox and alias of `source` to the same local part on `to`.
+fn forwarding_changes(source: &DomainState, to: &str) -> Result<Vec<Change>> {
+    let from = source.domain.as_str();
+    let mut changes = Vec::new();
+
+    for state in &source.mailboxes {
+        let mailbox = &state.mailbox;
+        let address = format!("{}@{}", mailbox.local_part, to);
+        if state
+            .forwardings
+            .iter()
+            .any(|f| f.address.eq_ignore_ascii_case(&address))
+        {
+            continue;
+        }
+        changes.push(change(
+            ChangeAction::Create,
+            ResourceKind::Forwarding,
+            from,
+            address.clone(),
+            Some(mailbox.address.clone()),
+            None,
+            Operation::CreateForwarding {
+                domain: from.to_string(),
+                mailbox: mailbox.local_part.clone(),
+                forwarding: CreateForwarding::new(address),
+            },
+        )?);
+    }
+
+    for alias in &source.aliases {
+        let address = format!("{}@{}", alias.local_part, to);
+        if alias.destinations.len() == 1 && alias.destinations[0].eq_ignore_ascii_case(&address) {
+            continue;
+        }
+        changes.push(change(
+            ChangeAction::Update,
+            ResourceKind::Alias,
+            from,
+            alias.address.clone(),
+            None,
+            Some(FieldChange::new(
+                "destinations",
+                serde_json::json!(alias.destinations),
+                serde_json::json!([address]),
+            )),
+            Operation::UpdateAlias {
+                domain: from.to_string(),
+                local_part: alias.local_part.clone(),
+                update: UpdateAlias {
+                    destinations: Some(address),
+                    ..Default::default()
+                },
+            },
+        )?);
+    }
+
+    Ok(changes)
+}
+
+fn change(
+    action: ChangeAction,
+    resource: ResourceKind,
+    domain: &str,
+    id: String,
+    mailbox: Option<String>,
+    field: Option<FieldChange>,
+    operation: Operation,
+) -> Result<Change> {
+    let request = operation.request()?.masked();
+    let fields = match field {
+        Some(field) => vec![field],
+        None => request
+            .body
+            .as_ref()
+            .map(diff::creation_changes)
+            .unwrap_or_default(),
+    };
+    Ok(Change {
+        action,
+        resource,
+        domain: domain.to_string(),
+        id,
+        mailbox,
+        fields,
+        request,
+        operation,
+    })
+}
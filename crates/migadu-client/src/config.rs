@@ -0,0 +1,187 @@
+//! The configuration file shared by the CLI and the MCP server.
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::credentials::{KeyCommand, KeyFile, Keyring, ProviderChain, StaticKey};
+use crate::{Error, Result};
+
+/// Name of the profile used when none is selected.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Contents of the configuration file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    /// Profile used when `--profile` is not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// A named set of account settings.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// File containing the API key, used when `api_key` is not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_file: Option<String>,
+    /// Command printing the API key, such as `pass show migadu`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_command: Option<String>,
+    /// Keyring account under which the API key is stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_keyring: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+}
+
+impl Profile {
+    /// Returns the profile's API key sources, in order of precedence.
+    ///
+    /// The keyring is only opened if no other source gives a key.
+    pub fn credentials(&self) -> Result<ProviderChain> {
+        let chain = ProviderChain::new()
+            .with_opt(self.api_key.clone().map(StaticKey))
+            .with_opt(self.api_key_file.clone().map(KeyFile::new))
+            .with_opt(self.api_key_command.clone().map(KeyCommand::new));
+        Ok(match self.api_key_keyring.clone() {
+            Some(account) => chain.with_deferred(move || Keyring::new(account.clone())),
+            None => chain,
+        })
+    }
+
+    /// Describes where the API key comes from, without revealing it.
+    pub fn api_key_source(&self) -> &'static str {
+        if self.api_key.is_some() {
+            "stored"
+        } else if self.api_key_file.is_some() {
+            "file"
+        } else if self.api_key_command.is_some() {
+            "command"
+        } else if self.api_key_keyring.is_some() {
+            "keyring"
+        } else {
+            ""
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration file, returning an empty configuration if it does not exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(|e| {
+                Error::Config(format!("Invalid config file {}: {}", path.display(), e))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(Error::Config(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    /// Writes the configuration file, readable only by the current user.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let write_error = |e: &dyn std::fmt::Display| {
+            Error::Config(format!("Failed to write {}: {}", path.display(), e))
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| write_error(&e))?;
+        }
+        let text = toml::to_string_pretty(self).map_err(|e| write_error(&e))?;
+        write_private(path, &text).map_err(|e| write_error(&e))
+    }
+
+    /// Returns the selected profile.
+    ///
+    /// An explicitly requested profile must exist; otherwise the default profile is used
+    /// if present, and an empty profile if not.
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        match name {
+            Some(name) => self.profiles.get(name).cloned().ok_or_else(|| {
+                Error::Config(format!("Profile '{}' not found in config file", name))
+            }),
+            None => Ok(self
+                .profiles
+                .get(self.default_profile_name())
+                .cloned()
+                .unwrap_or_default()),
+        }
+    }
+
+    /// Name of the profile used when none is selected.
+    pub fn default_profile_name(&self) -> &str {
+        self.default_profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+}
+
+/// Returns the default location of the configuration file.
+///
+/// This is `$XDG_CONFIG_HOME/migadu/config.toml`, falling back to
+/// `~/.config/migadu/config.toml`.
+pub fn default_path() -> Result<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".config"))
+            .ok_or_else(|| {
+                Error::Config(
+                    "Cannot determine config directory; set HOME or use --config".to_string(),
+                )
+            })?,
+    };
+    Ok(base.join("migadu").join("config.toml"))
+}
+
+/// Replaces the file at `path` with `contents`, readable only by the current user.
+///
+/// The contents are written to a temporary file, created with mode 0600 in the same
+/// directory, which is then renamed over `path`. The file is thus never readable by
+/// others, nor left half-written.
+pub fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", std::process::id()));
+    let temp = path.with_file_name(name);
+    // Left behind by an earlier process with the same id.
+    let _ = fs::remove_file(&temp);
+
+    let result = create_private(&temp, contents).and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Creates the file at `path` with `contents`, readable only by the current user.
+///
+/// Fails if the file already exists, checked atomically as the file is created, so an
+/// existing file is never replaced.
+pub fn create_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    let result = file
+        .write_all(contents.as_bytes())
+        .and_then(|()| file.sync_all());
+    if result.is_err() {
+        let _ = fs::remove_file(path);
+    }
+    result
+}
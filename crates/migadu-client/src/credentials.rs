@@ -0,0 +1,431 @@
+//! Sources for the Migadu API key.
+//!
+//! Passing the API key on the command line exposes it in process listings and shell
+//! history. The providers in this module fetch it from somewhere safer instead: an
+//! environment variable, a file, the output of a command such as `pass show migadu`,
+//! or a keyring. Providers can be combined with [`ProviderChain`], which returns the
+//! first key found.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use migadu_client::credentials::{self, CredentialProvider};
+//! use migadu_client::MigaduClient;
+//!
+//! # fn main() -> migadu_client::Result<()> {
+//! let api_key = credentials::from_env()?.require()?;
+//! let client = MigaduClient::new("user@example.com", api_key);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config;
+use crate::error::{Error, Result};
+
+/// Keyring service name under which Migadu API keys are stored.
+pub const KEYRING_SERVICE: &str = "migadu";
+
+/// A source for the Migadu API key.
+pub trait CredentialProvider: Send + Sync {
+    /// Returns the API key, or `None` if this source is not configured.
+    ///
+    /// Errors are reserved for sources that are configured but fail, such as an
+    /// unreadable key file or a command that exits unsuccessfully.
+    fn api_key(&self) -> Result<Option<String>>;
+
+    /// Returns the API key, failing if this source is not configured.
+    fn require(&self) -> Result<String> {
+        self.api_key()?
+            .ok_or_else(|| Error::Credentials("No API key configured".to_string()))
+    }
+}
+
+/// An API key known up front.
+#[derive(Debug, Clone)]
+pub struct StaticKey(pub String);
+
+impl CredentialProvider for StaticKey {
+    fn api_key(&self) -> Result<Option<String>> {
+        Ok(Some(self.0.clone()))
+    }
+}
+
+/// Reads the API key from an environment variable.
+#[derive(Debug, Clone)]
+pub struct EnvKey {
+    pub var: String,
+}
+
+impl EnvKey {
+    pub fn new(var: impl Into<String>) -> Self {
+        Self { var: var.into() }
+    }
+}
+
+impl CredentialProvider for EnvKey {
+    fn api_key(&self) -> Result<Option<String>> {
+        Ok(std::env::var(&self.var).ok().filter(|v| !v.is_empty()))
+    }
+}
+
+/// Reads the API key from a file, ignoring surrounding whitespace.
+#[derive(Debug, Clone)]
+pub struct KeyFile {
+    pub path: PathBuf,
+}
+
+impl KeyFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl CredentialProvider for KeyFile {
+    fn api_key(&self) -> Result<Option<String>> {
+        let key = fs::read_to_string(&self.path).map_err(|e| {
+            Error::Credentials(format!(
+                "Failed to read API key file {}: {}",
+                self.path.display(),
+                e
+            ))
+        })?;
+        match key.trim() {
+            "" => Err(Error::Credentials(format!(
+                "API key file {} is empty",
+                self.path.display()
+            ))),
+            key => Ok(Some(key.to_string())),
+        }
+    }
+}
+
+/// Runs a shell command and uses the first line of its output as the API key.
+///
+/// Only the first line is used so that password managers which print metadata after
+/// the secret (such as `pass show`) work unchanged.
+#[derive(Debug, Clone)]
+pub struct KeyCommand {
+    pub command: String,
+}
+
+impl KeyCommand {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+        }
+    }
+}
+
+impl CredentialProvider for KeyCommand {
+    fn api_key(&self) -> Result<Option<String>> {
+        let mut cmd = if cfg!(windows) {
+            let mut cmd = Command::new("cmd");
+            cmd.arg("/C");
+            cmd
+        } else {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c");
+            cmd
+        };
+        let output = cmd
+            .arg(&self.command)
+            .output()
+            .map_err(|e| Error::Credentials(format!("Failed to run `{}`: {}", self.command, e)))?;
+
+        if !output.status.success() {
+            return Err(Error::Credentials(format!(
+                "`{}` failed ({}): {}",
+                self.command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        match stdout.lines().next().map(str::trim) {
+            Some(key) if !key.is_empty() => Ok(Some(key.to_string())),
+            _ => Err(Error::Credentials(format!(
+                "`{}` printed no API key",
+                self.command
+            ))),
+        }
+    }
+}
+
+/// Storage for secrets indexed by service and account.
+pub trait SecretStore: Send + Sync {
+    /// Returns the stored secret, or `None` if there is none.
+    fn get(&self, service: &str, account: &str) -> Result<Option<String>>;
+
+    /// Stores a secret, replacing any existing one.
+    fn set(&self, service: &str, account: &str, secret: &str) -> Result<()>;
+
+    /// Removes a secret, if present.
+    fn delete(&self, service: &str, account: &str) -> Result<()>;
+}
+
+/// A [`SecretStore`] backed by a JSON file readable only by the current user.
+///
+/// This is a fallback for systems without an OS keyring, and is convenient in tests.
+#[derive(Debug, Clone)]
+pub struct FileSecretStore {
+    path: PathBuf,
+}
+
+type SecretMap = BTreeMap<String, BTreeMap<String, String>>;
+
+impl FileSecretStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&self) -> Result<SecretMap> {
+        match fs::read_to_string(&self.path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| {
+                Error::Credentials(format!(
+                    "Invalid keyring file {}: {}",
+                    self.path.display(),
+                    e
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(SecretMap::new()),
+            Err(e) => Err(self.io_error(e)),
+        }
+    }
+
+    fn save(&self, secrets: &SecretMap) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| self.io_error(e))?;
+        }
+        let text = serde_json::to_string_pretty(secrets).map_err(Error::Parse)?;
+        config::write_private(&self.path, &text).map_err(|e| self.io_error(e))
+    }
+
+    fn io_error(&self, e: std::io::Error) -> Error {
+        Error::Credentials(format!("Keyring file {}: {}", self.path.display(), e))
+    }
+}
+
+impl SecretStore for FileSecretStore {
+    fn get(&self, service: &str, account: &str) -> Result<Option<String>> {
+        Ok(self
+            .load()?
+            .get(service)
+            .and_then(|accounts| accounts.get(account))
+            .cloned())
+    }
+
+    fn set(&self, service: &str, account: &str, secret: &str) -> Result<()> {
+        let mut secrets = self.load()?;
+        secrets
+            .entry(service.to_string())
+            .or_default()
+            .insert(account.to_string(), secret.to_string());
+        self.save(&secrets)
+    }
+
+    fn delete(&self, service: &str, account: &str) -> Result<()> {
+        let mut secrets = self.load()?;
+        if let Some(accounts) = secrets.get_mut(service) {
+            accounts.remove(account);
+            if accounts.is_empty() {
+                secrets.remove(service);
+            }
+        }
+        self.save(&secrets)
+    }
+}
+
+/// The operating system's keyring (Keychain, Windows Credential Manager or, on Linux,
+/// the Secret Service provided by GNOME Keyring or KWallet), whose keys persist across
+/// logins.
+#[cfg(feature = "keyring")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsKeyring;
+
+#[cfg(feature = "keyring")]
+impl OsKeyring {
+    fn entry(service: &str, account: &str) -> Result<keyring::Entry> {
+        keyring::Entry::new(service, account).map_err(|e| Error::Credentials(e.to_string()))
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl SecretStore for OsKeyring {
+    fn get(&self, service: &str, account: &str) -> Result<Option<String>> {
+        match Self::entry(service, account)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(Error::Credentials(e.to_string())),
+        }
+    }
+
+    fn set(&self, service: &str, account: &str, secret: &str) -> Result<()> {
+        Self::entry(service, account)?
+            .set_password(secret)
+            .map_err(|e| Error::Credentials(e.to_string()))
+    }
+
+    fn delete(&self, service: &str, account: &str) -> Result<()> {
+        match Self::entry(service, account)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(Error::Credentials(e.to_string())),
+        }
+    }
+}
+
+/// Returns the keyring used by default.
+///
+/// If `MIGADU_KEYRING_FILE` is set, secrets are kept in that file; otherwise the OS
+/// keyring is used when the crate is built with the `keyring` feature.
+pub fn default_store() -> Result<Box<dyn SecretStore>> {
+    if let Some(path) = std::env::var_os("MIGADU_KEYRING_FILE").filter(|v| !v.is_empty()) {
+        return Ok(Box::new(FileSecretStore::new(path)));
+    }
+    #[cfg(feature = "keyring")]
+    {
+        Ok(Box::new(OsKeyring))
+    }
+    #[cfg(not(feature = "keyring"))]
+    {
+        Err(Error::Credentials(
+            "No keyring available: set MIGADU_KEYRING_FILE or build with the `keyring` feature"
+                .to_string(),
+        ))
+    }
+}
+
+/// Reads the API key from a [`SecretStore`].
+pub struct Keyring {
+    store: Box<dyn SecretStore>,
+    service: String,
+    account: String,
+}
+
+impl Keyring {
+    /// Looks up `account` under [`KEYRING_SERVICE`] in the [`default_store`].
+    pub fn new(account: impl Into<String>) -> Result<Self> {
+        Ok(Self::with_store(default_store()?, KEYRING_SERVICE, account))
+    }
+
+    /// Looks up a secret in a specific store.
+    pub fn with_store(
+        store: Box<dyn SecretStore>,
+        service: impl Into<String>,
+        account: impl Into<String>,
+    ) -> Self {
+        Self {
+            store,
+            service: service.into(),
+            account: account.into(),
+        }
+    }
+}
+
+impl CredentialProvider for Keyring {
+    fn api_key(&self) -> Result<Option<String>> {
+        self.store.get(&self.service, &self.account)
+    }
+}
+
+/// Tries several providers in order and returns the first key found.
+#[derive(Default)]
+pub struct ProviderChain {
+    providers: Vec<Box<dyn CredentialProvider>>,
+}
+
+impl ProviderChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a provider to the chain.
+    pub fn with(mut self, provider: impl CredentialProvider + 'static) -> Self {
+        self.providers.push(Box::new(provider));
+        self
+    }
+
+    /// Appends a provider to the chain if one is given.
+    pub fn with_opt(self, provider: Option<impl CredentialProvider + 'static>) -> Self {
+        match provider {
+            Some(provider) => self.with(provider),
+            None => self,
+        }
+    }
+
+    /// Appends a provider that is only built once every provider before it has given
+    /// no key, for sources such as a keyring that can fail to open.
+    pub fn with_deferred<P: CredentialProvider + 'static>(
+        self,
+        build: impl Fn() -> Result<P> + Send + Sync + 'static,
+    ) -> Self {
+        self.with(Deferred(build))
+    }
+
+    /// Appends all providers of another chain.
+    pub fn extend(mut self, other: ProviderChain) -> Self {
+        self.providers.extend(other.providers);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+}
+
+impl CredentialProvider for ProviderChain {
+    fn api_key(&self) -> Result<Option<String>> {
+        for provider in &self.providers {
+            if let Some(key) = provider.api_key()? {
+                return Ok(Some(key));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// A provider built on first use; see [`ProviderChain::with_deferred`].
+struct Deferred<F>(F);
+
+impl<F, P> CredentialProvider for Deferred<F>
+where
+    F: Fn() -> Result<P> + Send + Sync,
+    P: CredentialProvider,
+{
+    fn api_key(&self) -> Result<Option<String>> {
+        (self.0)()?.api_key()
+    }
+}
+
+/// Builds the standard chain of providers configured through environment variables.
+///
+/// In order of precedence:
+/// - `MIGADU_API_KEY`: the key itself
+/// - `MIGADU_API_KEY_FILE`: a file containing the key
+/// - `MIGADU_API_KEY_COMMAND`: a command that prints the key
+/// - `MIGADU_API_KEY_KEYRING`: a keyring account under which the key is stored
+///
+/// The keyring is only opened if none of the others gives a key, so asking the chain
+/// for a key fails when it gets that far and no keyring is available.
+pub fn from_env() -> Result<ProviderChain> {
+    let env = |var: &str| std::env::var(var).ok().filter(|v| !v.is_empty());
+
+    let chain = ProviderChain::new()
+        .with(EnvKey::new("MIGADU_API_KEY"))
+        .with_opt(env("MIGADU_API_KEY_FILE").map(KeyFile::new))
+        .with_opt(env("MIGADU_API_KEY_COMMAND").map(KeyCommand::new));
+
+    Ok(match env("MIGADU_API_KEY_KEYRING") {
+        Some(account) => chain.with_deferred(move || Keyring::new(account.clone())),
+        None => chain,
+    })
+}
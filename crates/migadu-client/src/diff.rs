@@ -0,0 +1,321 @@
+//! Field-level comparison of resources and snapshots.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::snapshot::Snapshot;
+use crate::state::{DomainState, MailboxState, ResourceKind};
+use crate::types::Mailbox;
+
+/// A field whose value differs between two versions of a resource.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    /// Value before the change (`null` if unset or the resource is new).
+    pub old: Value,
+    /// Value after the change (`null` if unset or the resource is removed).
+    pub new: Value,
+    /// For lists, the entries in `new` but not in `old`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added: Vec<Value>,
+    /// For lists, the entries in `old` but not in `new`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<Value>,
+}
+
+impl FieldChange {
+    pub fn new(field: impl Into<String>, old: Value, new: Value) -> Self {
+        let (added, removed) = match (&old, &new) {
+            (Value::Array(old_items), Value::Array(new_items)) => (
+                missing_from(new_items, old_items),
+                missing_from(old_items, new_items),
+            ),
+            (Value::Null, Value::Array(new_items)) => (new_items.clone(), Vec::new()),
+            (Value::Array(old_items), Value::Null) => (Vec::new(), old_items.clone()),
+            _ => (Vec::new(), Vec::new()),
+        };
+        Self {
+            field: field.into(),
+            old,
+            new,
+            added,
+            removed,
+        }
+    }
+}
+
+/// Whether a resource was added, removed or changed between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difference {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A resource that differs between two snapshots.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceDiff {
+    pub difference: Difference,
+    pub resource: ResourceKind,
+    pub domain: String,
+    /// Address or name identifying the resource.
+    pub id: String,
+    /// Address of the mailbox an identity or forwarding belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mailbox: Option<String>,
+    /// Fields that changed; empty for added and removed resources.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+}
+
+/// Differences between two snapshots.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SnapshotDiff {
+    pub resources: Vec<ResourceDiff>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    /// Counts the resources with the given difference.
+    pub fn count(&self, difference: Difference) -> usize {
+        self.resources
+            .iter()
+            .filter(|r| r.difference == difference)
+            .count()
+    }
+}
+
+/// Compares every field of two versions of a resource.
+pub fn resource_changes(old: &Value, new: &Value) -> Vec<FieldChange> {
+    let old = old.as_object().cloned().unwrap_or_default();
+    let new = new.as_object().cloned().unwrap_or_default();
+    let mut fields: Vec<&String> = old.keys().collect();
+    fields.extend(new.keys().filter(|field| !old.contains_key(*field)));
+
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let old = old.get(field).cloned().unwrap_or(Value::Null);
+            let new = new.get(field).cloned().unwrap_or(Value::Null);
+            (!same_value(&old, &new)).then(|| FieldChange::new(field, old, new))
+        })
+        .collect()
+}
+
+/// Reports the resources added, removed or changed from `old` to `new`.
+pub fn diff_snapshots(old: &Snapshot, new: &Snapshot) -> SnapshotDiff {
+    let mut resources = Vec::new();
+    let empty = |domain: &str| DomainState {
+        domain: domain.to_string(),
+        ..Default::default()
+    };
+    for old_domain in &old.domains {
+        let new_domain = new
+            .domain(&old_domain.domain)
+            .cloned()
+            .unwrap_or_else(|| empty(&old_domain.domain));
+        resources.extend(diff_domains(old_domain, &new_domain).resources);
+    }
+    for new_domain in &new.domains {
+        if old.domain(&new_domain.domain).is_none() {
+            resources.extend(diff_domains(&empty(&new_domain.domain), new_domain).resources);
+        }
+    }
+    SnapshotDiff { resources }
+}
+
+/// Reports the resources added, removed or changed from `old` to `new`.
+pub fn diff_domains(old: &DomainState, new: &DomainState) -> SnapshotDiff {
+    let mut differ = Differ {
+        domain: &new.domain,
+        resources: Vec::new(),
+    };
+
+    let mailboxes = |state: &DomainState| -> Vec<Mailbox> {
+        state.mailboxes.iter().map(|m| m.mailbox.clone()).collect()
+    };
+    differ.compare(
+        ResourceKind::Mailbox,
+        None,
+        &mailboxes(old),
+        &mailboxes(new),
+        |m| m.address.to_lowercase(),
+        |m| m.address.clone(),
+    );
+    for new_mailbox in &new.mailboxes {
+        let old_mailbox = old.mailbox(&new_mailbox.mailbox.local_part);
+        differ.mailbox_children(old_mailbox, Some(new_mailbox));
+    }
+    for old_mailbox in &old.mailboxes {
+        if new.mailbox(&old_mailbox.mailbox.local_part).is_none() {
+            differ.mailbox_children(Some(old_mailbox), None);
+        }
+    }
+    differ.compare(
+        ResourceKind::Alias,
+        None,
+        &old.aliases,
+        &new.aliases,
+        |a| a.address.to_lowercase(),
+        |a| a.address.clone(),
+    );
+    differ.compare(
+        ResourceKind::Rewrite,
+        None,
+        &old.rewrites,
+        &new.rewrites,
+        |r| r.name.to_lowercase(),
+        |r| r.name.clone(),
+    );
+
+    SnapshotDiff {
+        resources: differ.resources,
+    }
+}
+
+struct Differ<'a> {
+    domain: &'a str,
+    resources: Vec<ResourceDiff>,
+}
+
+impl Differ<'_> {
+    /// Compares two lists of resources matched by `key`.
+    fn compare<T: Serialize>(
+        &mut self,
+        resource: ResourceKind,
+        mailbox: Option<&str>,
+        old: &[T],
+        new: &[T],
+        key: impl Fn(&T) -> String,
+        id: impl Fn(&T) -> String,
+    ) {
+        let mut push = |difference, item: &T, fields| {
+            self.resources.push(ResourceDiff {
+                difference,
+                resource,
+                domain: self.domain.to_string(),
+                id: id(item),
+                mailbox: mailbox.map(str::to_string),
+                fields,
+            })
+        };
+        for new_item in new {
+            match old.iter().find(|old_item| key(old_item) == key(new_item)) {
+                Some(old_item) => {
+                    let fields = resource_changes(
+                        &serde_json::to_value(old_item).unwrap_or_default(),
+                        &serde_json::to_value(new_item).unwrap_or_default(),
+                    );
+                    if !fields.is_empty() {
+                        push(Difference::Changed, new_item, fields);
+                    }
+                }
+                None => push(Difference::Added, new_item, Vec::new()),
+            }
+        }
+        for old_item in old {
+            if !new.iter().any(|new_item| key(new_item) == key(old_item)) {
+                push(Difference::Removed, old_item, Vec::new());
+            }
+        }
+    }
+
+    fn mailbox_children(&mut self, old: Option<&MailboxState>, new: Option<&MailboxState>) {
+        let address = new.or(old).map(|m| m.mailbox.address.clone());
+        let identities = |m: Option<&MailboxState>| m.map(|m| m.identities.clone());
+        let forwardings = |m: Option<&MailboxState>| m.map(|m| m.forwardings.clone());
+        self.compare(
+            ResourceKind::Identity,
+            address.as_deref(),
+            &identities(old).unwrap_or_default(),
+            &identities(new).unwrap_or_default(),
+            |i| i.address.to_lowercase(),
+            |i| i.address.clone(),
+        );
+        self.compare(
+            ResourceKind::Forwarding,
+            address.as_deref(),
+            &forwardings(old).unwrap_or_default(),
+            &forwardings(new).unwrap_or_default(),
+            |f| f.address.to_lowercase(),
+            |f| f.address.clone(),
+        );
+    }
+}
+
+/// Compares the fields set in an update body with the current resource.
+///
+/// Only fields present in `update` are considered. Comma-separated destination strings
+/// are compared as lists, and lists are compared regardless of order.
+pub fn update_changes(current: &Value, update: &Value) -> Vec<FieldChange> {
+    let Value::Object(fields) = update else {
+        return Vec::new();
+    };
+
+    fields
+        .iter()
+        .filter_map(|(field, new)| {
+            let old = current.get(field).cloned().unwrap_or(Value::Null);
+            let new = match (&old, new) {
+                (Value::Array(_), Value::String(list)) => split_list(list),
+                _ => new.clone(),
+            };
+            (!same_value(&old, &new)).then(|| FieldChange::new(field, old, new))
+        })
+        .collect()
+}
+
+/// Lists every field set in a resource as a change from `null`.
+pub fn creation_changes(resource: &impl Serialize) -> Vec<FieldChange> {
+    object(resource)
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(field, value)| FieldChange::new(field, Value::Null, value))
+        .collect()
+}
+
+/// Splits a comma-separated list into a JSON array.
+pub(crate) fn split_list(list: &str) -> Value {
+    Value::Array(
+        list.split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| Value::String(item.to_string()))
+            .collect(),
+    )
+}
+
+/// Compares values, treating lists as unordered and a missing list as empty.
+pub(crate) fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Null, Value::Array(list)) | (Value::Array(list), Value::Null) => list.is_empty(),
+        (Value::Array(a), Value::Array(b)) => {
+            let mut a: Vec<String> = a.iter().map(Value::to_string).collect();
+            let mut b: Vec<String> = b.iter().map(Value::to_string).collect();
+            a.sort();
+            b.sort();
+            a == b
+        }
+        _ => a == b,
+    }
+}
+
+/// Returns the entries of `items` that are not in `other`.
+fn missing_from(items: &[Value], other: &[Value]) -> Vec<Value> {
+    items
+        .iter()
+        .filter(|item| !other.contains(item))
+        .cloned()
+        .collect()
+}
+
+fn object(value: &impl Serialize) -> Map<String, Value> {
+    match serde_json::to_value(value) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use thiserror::Error;
 
 /// Result type alias using the crate's Error type.
@@ -11,15 +13,51 @@ pub enum Error {
     Request(#[from] reqwest::Error),
 
     /// API returned an error response.
+    ///
+    /// More fields may be added, so match it with `..`.
     #[error("API error (HTTP {status}): {message}")]
+    #[non_exhaustive]
     Api {
         /// HTTP status code returned by the API.
         status: u16,
         /// Error message from the API response.
         message: String,
+        /// How long the API asked to wait before retrying, from a `Retry-After` header
+        /// giving a number of seconds.
+        retry_after: Option<Duration>,
     },
 
     /// Failed to parse the API response.
     #[error("Failed to parse response: {0}")]
     Parse(#[source] serde_json::Error),
+
+    /// Failed to obtain the API key.
+    #[error("Credential error: {0}")]
+    Credentials(String),
+
+    /// The configuration file cannot be read or written.
+    #[error("{0}")]
+    Config(String),
+
+    /// The desired state cannot be planned.
+    #[error("Invalid desired state: {0}")]
+    Plan(String),
+
+    /// A snapshot cannot be used.
+    #[error("Invalid snapshot: {0}")]
+    Snapshot(String),
+
+    /// A name or address cannot be used in a request path.
+    #[error("Invalid path: {0}")]
+    Path(String),
+}
+
+impl Error {
+    /// How long the API asked to wait before retrying, if it did.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::Api { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
 }
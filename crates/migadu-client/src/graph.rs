@@ -0,0 +1,466 @@
+//! The delivery graph of one or more domains.
+//!
+//! Nodes are addresses (plus one node per rewrite rule) and edges show where mail to
+//! each node is delivered: alias and rewrite destinations, forwardings, and identities
+//! delivering to their mailbox. A destination on a domain of the graph that only a
+//! rewrite rule receives points at the rule's node. [`Graph::to_dot`] and [`Graph::to_mermaid`] render it
+//! for Graphviz and Mermaid, and the graph serializes to JSON as is.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::rewrite_rules::first_match;
+use crate::state::DomainState;
+
+/// What a node of the graph is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Mailbox,
+    Identity,
+    Alias,
+    Rewrite,
+    /// An address on a domain outside the graph.
+    External,
+    /// An address on a domain in the graph that nothing receives.
+    Missing,
+}
+
+/// How an edge delivers mail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    /// An alias or rewrite destination.
+    Destination,
+    /// A mailbox forwarding.
+    Forwarding,
+    /// An identity delivering to its mailbox.
+    Identity,
+}
+
+/// An address, or a rewrite rule.
+#[derive(Debug, Clone, Serialize)]
+pub struct Node {
+    /// The address, or `rewrite:<name>@<domain>` for rewrite rules.
+    pub id: String,
+    pub kind: NodeKind,
+    pub label: String,
+    /// Part of a cycle of deliveries.
+    pub in_cycle: bool,
+    /// Neither receives mail from nor delivers mail to any other node.
+    pub orphan: bool,
+}
+
+/// A delivery from one node to another.
+#[derive(Debug, Clone, Serialize)]
+pub struct Edge {
+    pub from: String,
+    /// The address delivered to, or the rewrite rule receiving it.
+    pub to: String,
+    pub kind: EdgeKind,
+    /// `false` for forwardings that are not active, which deliver nothing.
+    pub active: bool,
+    /// Part of a cycle of deliveries.
+    pub in_cycle: bool,
+}
+
+/// Nodes and delivery edges of one or more domains.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl Graph {
+    /// Builds the delivery graph of `domains`.
+    pub fn build(domains: &[DomainState]) -> Self {
+        let mut builder = Builder::default();
+        for domain in domains {
+            for state in &domain.mailboxes {
+                let mailbox = &state.mailbox;
+                builder.node(&mailbox.address, NodeKind::Mailbox, &mailbox.address);
+                for identity in &state.identities {
+                    builder.node(&identity.address, NodeKind::Identity, &identity.address);
+                    builder.edge(
+                        &identity.address,
+                        &mailbox.address,
+                        EdgeKind::Identity,
+                        true,
+                    );
+                }
+                for forwarding in &state.forwardings {
+                    builder.edge(
+                        &mailbox.address,
+                        &forwarding.address,
+                        EdgeKind::Forwarding,
+                        forwarding.is_active,
+                    );
+                }
+            }
+            for alias in &domain.aliases {
+                builder.node(&alias.address, NodeKind::Alias, &alias.address);
+                for destination in &alias.destinations {
+                    builder.edge(&alias.address, destination, EdgeKind::Destination, true);
+                }
+            }
+            for rewrite in &domain.rewrites {
+                let id = format!("rewrite:{}@{}", rewrite.name, domain.domain);
+                let mut label = format!(
+                    "{}@{} (rewrite {}",
+                    rewrite.local_part_rule, domain.domain, rewrite.name
+                );
+                if let Some(order) = rewrite.order_num {
+                    let _ = write!(label, ", order {}", order);
+                }
+                label.push(')');
+                builder.node(&id, NodeKind::Rewrite, &label);
+                for destination in &rewrite.destinations {
+                    builder.edge(&id, destination, EdgeKind::Destination, true);
+                }
+            }
+        }
+        builder.finish(domains)
+    }
+
+    /// Renders the graph in Graphviz DOT syntax.
+    ///
+    /// External addresses are grey, missing ones red and dashed, orphans orange, and
+    /// cycles drawn in bold red. Inactive forwardings are dashed.
+    pub fn to_dot(&self) -> String {
+        let mut out =
+            String::from("digraph migadu {\n    rankdir=LR;\n    node [fontname=\"Helvetica\"];\n");
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Mailbox => "box",
+                NodeKind::Identity => "note",
+                NodeKind::Alias => "ellipse",
+                NodeKind::Rewrite => "hexagon",
+                NodeKind::External | NodeKind::Missing => "box",
+            };
+            let mut attributes = vec![
+                format!(
+                    "label={}",
+                    dot_string(&format!("{}\n{}", node.label, kind_name(node.kind)))
+                ),
+                format!("shape={}", shape),
+            ];
+            match node.kind {
+                NodeKind::External => attributes.push("style=dashed, color=gray50".into()),
+                NodeKind::Missing => attributes.push("style=dashed, color=red".into()),
+                _ => {}
+            }
+            if node.in_cycle {
+                attributes.push("color=red, penwidth=2".into());
+            } else if node.orphan {
+                attributes.push("color=orange".into());
+            }
+            let _ = writeln!(
+                out,
+                "    {} [{}];",
+                dot_string(&node.id),
+                attributes.join(", ")
+            );
+        }
+        for edge in &self.edges {
+            let mut attributes = Vec::new();
+            match edge.kind {
+                EdgeKind::Forwarding => attributes.push("label=\"forwards\"".to_string()),
+                EdgeKind::Identity => attributes.push("label=\"identity of\"".to_string()),
+                EdgeKind::Destination => {}
+            }
+            if !edge.active {
+                attributes.push("style=dashed".into());
+            }
+            if edge.in_cycle {
+                attributes.push("color=red, penwidth=2".into());
+            }
+            let _ = write!(
+                out,
+                "    {} -> {}",
+                dot_string(&edge.from),
+                dot_string(&edge.to)
+            );
+            if !attributes.is_empty() {
+                let _ = write!(out, " [{}]", attributes.join(", "));
+            }
+            out.push_str(";\n");
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Renders the graph as a Mermaid flowchart.
+    ///
+    /// Highlights use the same colours as [`Graph::to_dot`].
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("flowchart LR\n");
+        let ids: HashMap<&str, String> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.id.as_str(), format!("n{}", i)))
+            .collect();
+
+        for node in &self.nodes {
+            let label = mermaid_string(&format!("{}<br/>{}", node.label, kind_name(node.kind)));
+            let (open, close) = match node.kind {
+                NodeKind::Mailbox => ("[", "]"),
+                NodeKind::Identity => ("[/", "/]"),
+                NodeKind::Alias => ("([", "])"),
+                NodeKind::Rewrite => ("{{", "}}"),
+                NodeKind::External | NodeKind::Missing => (">", "]"),
+            };
+            let _ = writeln!(
+                out,
+                "    {}{}{}{}",
+                ids[node.id.as_str()],
+                open,
+                label,
+                close
+            );
+        }
+
+        let mut cycle_links = Vec::new();
+        for (i, edge) in self.edges.iter().enumerate() {
+            let arrow = match (edge.kind, edge.active) {
+                (EdgeKind::Forwarding, true) => "-- forwards -->",
+                (EdgeKind::Forwarding, false) => "-. forwards .->",
+                (EdgeKind::Identity, _) => "-- identity of -->",
+                (EdgeKind::Destination, _) => "-->",
+            };
+            let _ = writeln!(
+                out,
+                "    {} {} {}",
+                ids[edge.from.as_str()],
+                arrow,
+                ids[edge.to.as_str()]
+            );
+            if edge.in_cycle {
+                cycle_links.push(i.to_string());
+            }
+        }
+
+        out.push_str("    classDef external stroke:#888,stroke-dasharray:4\n");
+        out.push_str("    classDef missing stroke:#d00,stroke-dasharray:4\n");
+        out.push_str("    classDef orphan stroke:#e80,stroke-width:2px\n");
+        out.push_str("    classDef cycle stroke:#d00,stroke-width:3px\n");
+        for node in &self.nodes {
+            let class = if node.in_cycle {
+                "cycle"
+            } else if node.kind == NodeKind::Missing {
+                "missing"
+            } else if node.kind == NodeKind::External {
+                "external"
+            } else if node.orphan {
+                "orphan"
+            } else {
+                continue;
+            };
+            let _ = writeln!(out, "    class {} {}", ids[node.id.as_str()], class);
+        }
+        if !cycle_links.is_empty() {
+            let _ = writeln!(
+                out,
+                "    linkStyle {} stroke:#d00,stroke-width:3px",
+                cycle_links.join(",")
+            );
+        }
+        out
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    nodes: Vec<Node>,
+    /// Index of each node by lowercase id.
+    index: HashMap<String, usize>,
+    edges: Vec<Edge>,
+}
+
+impl Builder {
+    fn node(&mut self, id: &str, kind: NodeKind, label: &str) {
+        let key = id.to_ascii_lowercase();
+        if self.index.contains_key(&key) {
+            return;
+        }
+        self.index.insert(key, self.nodes.len());
+        self.nodes.push(Node {
+            id: id.to_string(),
+            kind,
+            label: label.to_string(),
+            in_cycle: false,
+            orphan: false,
+        });
+    }
+
+    fn edge(&mut self, from: &str, to: &str, kind: EdgeKind, active: bool) {
+        self.edges.push(Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            kind,
+            active,
+            in_cycle: false,
+        });
+    }
+
+    /// Points destinations received by a rewrite rule at its node, adds nodes for
+    /// destinations defined nowhere, and marks cycles and orphans.
+    fn finish(mut self, domains: &[DomainState]) -> Graph {
+        for i in 0..self.edges.len() {
+            let to = self.edges[i].to.clone();
+            if self.index.contains_key(&to.to_ascii_lowercase()) {
+                continue;
+            }
+            let (local_part, domain) = to.rsplit_once('@').unwrap_or((&to, ""));
+            let kind = match domains
+                .iter()
+                .find(|d| d.domain.eq_ignore_ascii_case(domain))
+            {
+                Some(state) => match first_match(&state.rewrites, local_part) {
+                    Some(rewrite) => {
+                        self.edges[i].to = format!("rewrite:{}@{}", rewrite.name, state.domain);
+                        continue;
+                    }
+                    None => NodeKind::Missing,
+                },
+                None => NodeKind::External,
+            };
+            self.node(&to, kind, &to);
+        }
+        // Edges refer to nodes by their canonical id.
+        for edge in &mut self.edges {
+            edge.from = self.nodes[self.index[&edge.from.to_ascii_lowercase()]]
+                .id
+                .clone();
+            edge.to = self.nodes[self.index[&edge.to.to_ascii_lowercase()]]
+                .id
+                .clone();
+        }
+
+        let adjacency = self.adjacency();
+        let components = strongly_connected(&adjacency);
+        for edge in &mut self.edges {
+            let from = self.index[&edge.from.to_ascii_lowercase()];
+            let to = self.index[&edge.to.to_ascii_lowercase()];
+            // Two distinct nodes share a component only if they are on a cycle.
+            if edge.active && components[from] == components[to] {
+                edge.in_cycle = true;
+                self.nodes[from].in_cycle = true;
+                self.nodes[to].in_cycle = true;
+            }
+        }
+
+        let mut connected = vec![false; self.nodes.len()];
+        for edge in &self.edges {
+            connected[self.index[&edge.from.to_ascii_lowercase()]] = true;
+            connected[self.index[&edge.to.to_ascii_lowercase()]] = true;
+        }
+        for (node, connected) in self.nodes.iter_mut().zip(connected) {
+            node.orphan = !connected;
+        }
+
+        Graph {
+            nodes: self.nodes,
+            edges: self.edges,
+        }
+    }
+
+    /// Successors of each node over the edges that deliver mail.
+    fn adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![Vec::new(); self.nodes.len()];
+        for edge in self.edges.iter().filter(|e| e.active) {
+            let from = self.index[&edge.from.to_ascii_lowercase()];
+            let to = self.index[&edge.to.to_ascii_lowercase()];
+            adjacency[from].push(to);
+        }
+        adjacency
+    }
+}
+
+/// Labels each node with its strongly connected component (Tarjan's algorithm).
+fn strongly_connected(adjacency: &[Vec<usize>]) -> Vec<usize> {
+    struct Tarjan<'a> {
+        adjacency: &'a [Vec<usize>],
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next: usize,
+        component: Vec<usize>,
+        components: usize,
+    }
+
+    impl Tarjan<'_> {
+        fn visit(&mut self, v: usize) {
+            self.index[v] = Some(self.next);
+            self.low[v] = self.next;
+            self.next += 1;
+            self.stack.push(v);
+            self.on_stack[v] = true;
+            for &w in &self.adjacency[v] {
+                match self.index[w] {
+                    None => {
+                        self.visit(w);
+                        self.low[v] = self.low[v].min(self.low[w]);
+                    }
+                    Some(index) if self.on_stack[w] => self.low[v] = self.low[v].min(index),
+                    Some(_) => {}
+                }
+            }
+            if Some(self.low[v]) == self.index[v] {
+                while let Some(w) = self.stack.pop() {
+                    self.on_stack[w] = false;
+                    self.component[w] = self.components;
+                    if w == v {
+                        break;
+                    }
+                }
+                self.components += 1;
+            }
+        }
+    }
+
+    let n = adjacency.len();
+    let mut tarjan = Tarjan {
+        adjacency,
+        index: vec![None; n],
+        low: vec![0; n],
+        on_stack: vec![false; n],
+        stack: Vec::new(),
+        next: 0,
+        component: vec![0; n],
+        components: 0,
+    };
+    for v in 0..n {
+        if tarjan.index[v].is_none() {
+            tarjan.visit(v);
+        }
+    }
+    tarjan.component
+}
+
+fn kind_name(kind: NodeKind) -> &'static str {
+    match kind {
+        NodeKind::Mailbox => "mailbox",
+        NodeKind::Identity => "identity",
+        NodeKind::Alias => "alias",
+        NodeKind::Rewrite => "rewrite",
+        NodeKind::External => "external",
+        NodeKind::Missing => "missing",
+    }
+}
+
+fn dot_string(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+fn mermaid_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "#quot;"))
+}
@@ -0,0 +1,203 @@
+//! Recreating a domain from a [`Snapshot`](crate::snapshot::Snapshot).
+//!
+//! Importing plans the changes that create every resource of a snapshot domain on a
+//! target domain, which may be the same domain (to recover from accidental deletes) or
+//! a different one (to clone it). Resources that only exist on the target are never
+//! deleted; resources that exist on both are handled by a [`ConflictStrategy`].
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::client::MigaduClient;
+use crate::error::{Error, Result};
+use crate::plan::{plan_domain, ChangeAction, DomainSpec, Plan};
+use crate::state::{DomainState, ResourceKind};
+use crate::types::Mailbox;
+
+/// What to do with resources that already exist on the target domain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Refuse to import anything.
+    #[default]
+    Fail,
+    /// Leave existing resources as they are.
+    Skip,
+    /// Update existing resources to match the snapshot. Passwords are not changed.
+    Overwrite,
+}
+
+/// A resource present both in the snapshot and on the target domain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Conflict {
+    pub resource: ResourceKind,
+    /// Address or name of the resource on the target domain.
+    pub id: String,
+    /// Address of the mailbox an identity or forwarding belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mailbox: Option<String>,
+}
+
+impl Conflict {
+    fn key(&self) -> (ResourceKind, String, Option<String>) {
+        key(self.resource, &self.id, self.mailbox.as_deref())
+    }
+}
+
+fn key(
+    resource: ResourceKind,
+    id: &str,
+    mailbox: Option<&str>,
+) -> (ResourceKind, String, Option<String>) {
+    (resource, id.to_lowercase(), mailbox.map(str::to_lowercase))
+}
+
+/// Changes an import makes, and the existing resources it leaves alone.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportPlan {
+    pub plan: Plan,
+    /// Resources skipped under [`ConflictStrategy::Skip`].
+    pub skipped: Vec<Conflict>,
+}
+
+impl MigaduClient {
+    /// Fetches the live state of `domain` and plans importing `source` into it.
+    ///
+    /// See [`plan_import`].
+    pub async fn plan_import(
+        &self,
+        source: &DomainState,
+        domain: &str,
+        conflict: ConflictStrategy,
+        password: impl FnMut(&Mailbox) -> Option<String>,
+    ) -> Result<ImportPlan> {
+        let target = self.fetch_domain_state(domain).await?;
+        plan_import(source, &target, conflict, password)
+    }
+}
+
+/// Plans recreating the resources of `source` on the domain whose live state is `target`.
+///
+/// `password` is called for each mailbox that has to be created and returns its
+/// password; returning `None` fails the import, since mailboxes cannot be created
+/// without one.
+pub fn plan_import(
+    source: &DomainState,
+    target: &DomainState,
+    conflict: ConflictStrategy,
+    mut password: impl FnMut(&Mailbox) -> Option<String>,
+) -> Result<ImportPlan> {
+    let conflicts = conflicts(source, target);
+    if conflict == ConflictStrategy::Fail && !conflicts.is_empty() {
+        let list: Vec<String> = conflicts
+            .iter()
+            .map(|c| format!("{} {}", c.resource, c.id))
+            .collect();
+        return Err(Error::Plan(format!(
+            "{} resources already exist on {}: {}",
+            conflicts.len(),
+            target.domain,
+            list.join(", ")
+        )));
+    }
+
+    let mut spec = DomainSpec::from(source);
+    for mailbox in spec.mailboxes.iter_mut().flatten() {
+        if target.mailbox(&mailbox.local_part).is_none() {
+            let source = &source
+                .mailbox(&mailbox.local_part)
+                .expect("spec built from source")
+                .mailbox;
+            mailbox.settings.password = password(source);
+        }
+    }
+
+    let skipped: HashSet<_> = match conflict {
+        ConflictStrategy::Skip => conflicts.iter().map(Conflict::key).collect(),
+        _ => HashSet::new(),
+    };
+    let changes = plan_domain(&target.domain, &spec, target)?
+        .into_iter()
+        .filter(|change| change.action != ChangeAction::Delete)
+        .filter(|change| {
+            !skipped.contains(&key(change.resource, &change.id, change.mailbox.as_deref()))
+        })
+        .collect();
+
+    Ok(ImportPlan {
+        plan: Plan { changes },
+        skipped: match conflict {
+            ConflictStrategy::Skip => conflicts,
+            _ => Vec::new(),
+        },
+    })
+}
+
+/// Lists the resources of `source` that already exist on `target`.
+fn conflicts(source: &DomainState, target: &DomainState) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    for mailbox in &source.mailboxes {
+        let Some(existing) = target.mailbox(&mailbox.mailbox.local_part) else {
+            continue;
+        };
+        conflicts.push(Conflict {
+            resource: ResourceKind::Mailbox,
+            id: existing.mailbox.address.clone(),
+            mailbox: None,
+        });
+        for identity in &existing.identities {
+            if mailbox
+                .identities
+                .iter()
+                .any(|i| i.local_part.eq_ignore_ascii_case(&identity.local_part))
+            {
+                conflicts.push(Conflict {
+                    resource: ResourceKind::Identity,
+                    id: identity.address.clone(),
+                    mailbox: Some(existing.mailbox.address.clone()),
+                });
+            }
+        }
+        for forwarding in &existing.forwardings {
+            if mailbox
+                .forwardings
+                .iter()
+                .any(|f| f.address.eq_ignore_ascii_case(&forwarding.address))
+            {
+                conflicts.push(Conflict {
+                    resource: ResourceKind::Forwarding,
+                    id: forwarding.address.clone(),
+                    mailbox: Some(existing.mailbox.address.clone()),
+                });
+            }
+        }
+    }
+    for alias in &target.aliases {
+        if source
+            .aliases
+            .iter()
+            .any(|a| a.local_part.eq_ignore_ascii_case(&alias.local_part))
+        {
+            conflicts.push(Conflict {
+                resource: ResourceKind::Alias,
+                id: alias.address.clone(),
+                mailbox: None,
+            });
+        }
+    }
+    for rewrite in &target.rewrites {
+        if source
+            .rewrites
+            .iter()
+            .any(|r| r.name.eq_ignore_ascii_case(&rewrite.name))
+        {
+            conflicts.push(Conflict {
+                resource: ResourceKind::Rewrite,
+                id: rewrite.name.clone(),
+                mailbox: None,
+            });
+        }
+    }
+    conflicts
+}
@@ -25,10 +25,25 @@
 //! ```
 
 mod api;
+pub mod batch;
 mod client;
+pub mod clone;
+pub mod config;
+pub mod credentials;
+pub mod diff;
 mod error;
+pub mod graph;
+pub mod import;
+pub mod lint;
+mod operation;
+pub mod plan;
+pub mod resolve;
+pub mod rewrite_rules;
+pub mod snapshot;
+pub mod state;
 mod types;
 
 pub use client::MigaduClient;
 pub use error::{Error, Result};
+pub use operation::{ApiRequest, HttpMethod, Operation, OperationOutput, MASK};
 pub use types::*;
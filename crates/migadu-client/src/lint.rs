@@ -0,0 +1,413 @@
+//! Finding likely mistakes in the configuration of domains.
+//!
+//! [`lint`] runs every [`LintRule`] over the state of one or more domains, live or
+//! from a [snapshot](crate::snapshot), and reports a [`Finding`] for each problem.
+//! Each rule has a default [`Level`] that a [`LintConfig`] can raise, lower or turn off.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::resolve::{resolve_address, Delivery, Route};
+use crate::rewrite_rules::check_rules;
+use crate::state::{DomainState, ResourceKind};
+
+/// A check performed by [`lint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintRule {
+    /// An alias or rewrite destination on a linted domain that nothing receives.
+    DanglingDestination,
+    /// Mail to an alias that comes back to it.
+    AliasLoop,
+    /// A rewrite rule that can never match.
+    ShadowedRewrite,
+    /// A mailbox without a password recovery email.
+    MissingRecoveryEmail,
+    /// An autoresponder still active after its expiry date.
+    ExpiredAutoresponder,
+    /// A forwarding that is blocked or still awaiting confirmation.
+    InactiveForwarding,
+    /// An identity that may neither send nor receive.
+    UnusableIdentity,
+}
+
+impl LintRule {
+    /// Every rule, in the order findings are reported.
+    pub const ALL: [LintRule; 7] = [
+        LintRule::DanglingDestination,
+        LintRule::AliasLoop,
+        LintRule::ShadowedRewrite,
+        LintRule::MissingRecoveryEmail,
+        LintRule::ExpiredAutoresponder,
+        LintRule::InactiveForwarding,
+        LintRule::UnusableIdentity,
+    ];
+
+    /// Identifier used in configuration and reports, e.g. `alias-loop`.
+    pub fn id(self) -> &'static str {
+        match self {
+            LintRule::DanglingDestination => "dangling-destination",
+            LintRule::AliasLoop => "alias-loop",
+            LintRule::ShadowedRewrite => "shadowed-rewrite",
+            LintRule::MissingRecoveryEmail => "missing-recovery-email",
+            LintRule::ExpiredAutoresponder => "expired-autoresponder",
+            LintRule::InactiveForwarding => "inactive-forwarding",
+            LintRule::UnusableIdentity => "unusable-identity",
+        }
+    }
+
+    /// One-line description of what the rule checks.
+    pub fn description(self) -> &'static str {
+        match self {
+            LintRule::DanglingDestination => {
+                "Alias or rewrite destination on a hosted domain that no mailbox, identity, alias or rewrite receives"
+            }
+            LintRule::AliasLoop => "Alias whose mail is delivered back to itself",
+            LintRule::ShadowedRewrite => {
+                "Rewrite rule that can never match because of an earlier rule, mailbox, identity or alias"
+            }
+            LintRule::MissingRecoveryEmail => "Mailbox without a password recovery email",
+            LintRule::ExpiredAutoresponder => "Autoresponder still active after its expiry date",
+            LintRule::InactiveForwarding => "Forwarding that is blocked or awaiting confirmation",
+            LintRule::UnusableIdentity => "Identity that may neither send nor receive",
+        }
+    }
+
+    /// Level reported when the configuration does not say otherwise.
+    pub fn default_level(self) -> Level {
+        match self {
+            LintRule::DanglingDestination | LintRule::AliasLoop => Level::Error,
+            LintRule::ShadowedRewrite
+            | LintRule::ExpiredAutoresponder
+            | LintRule::InactiveForwarding
+            | LintRule::UnusableIdentity => Level::Warning,
+            LintRule::MissingRecoveryEmail => Level::Note,
+        }
+    }
+}
+
+impl fmt::Display for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for LintRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        LintRule::ALL
+            .into_iter()
+            .find(|rule| rule.id() == s)
+            .ok_or_else(|| {
+                let ids: Vec<_> = LintRule::ALL.iter().map(|r| r.id()).collect();
+                format!("unknown lint rule {} (expected {})", s, ids.join(", "))
+            })
+    }
+}
+
+/// How serious a finding is. Ordered from least to most serious.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    /// The rule is not run.
+    Off,
+    Note,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Level::Off => "off",
+            Level::Note => "note",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        })
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Level::Off),
+            "note" => Ok(Level::Note),
+            "warning" => Ok(Level::Warning),
+            "error" => Ok(Level::Error),
+            _ => Err(format!(
+                "unknown level {} (expected off, note, warning or error)",
+                s
+            )),
+        }
+    }
+}
+
+/// Levels for the rules that should not use their default.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LintConfig {
+    #[serde(default)]
+    pub rules: BTreeMap<LintRule, Level>,
+}
+
+impl LintConfig {
+    /// Returns the level of a rule.
+    pub fn level(&self, rule: LintRule) -> Level {
+        self.rules
+            .get(&rule)
+            .copied()
+            .unwrap_or_else(|| rule.default_level())
+    }
+}
+
+/// A problem found by a rule.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub rule: LintRule,
+    pub level: Level,
+    pub domain: String,
+    pub resource: ResourceKind,
+    /// Address of the resource, or name for rewrites.
+    pub id: String,
+    /// Address of the mailbox, for identities and forwardings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mailbox: Option<String>,
+    pub message: String,
+}
+
+/// Runs the enabled rules over `domains`.
+///
+/// Destinations are only checked on the domains given, so lint every domain they
+/// point at to catch all dangling destinations. `today` is the current date as
+/// `YYYY-MM-DD` (see [`today`]), against which expiry dates are compared.
+pub fn lint(domains: &[DomainState], config: &LintConfig, today: &str) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for rule in LintRule::ALL {
+        let level = config.level(rule);
+        if level == Level::Off {
+            continue;
+        }
+        for domain in domains {
+            let mut report = |resource, id: &str, mailbox: Option<&str>, message: String| {
+                findings.push(Finding {
+                    rule,
+                    level,
+                    domain: domain.domain.clone(),
+                    resource,
+                    id: id.to_string(),
+                    mailbox: mailbox.map(String::from),
+                    message,
+                })
+            };
+            let check = match rule {
+                LintRule::DanglingDestination => dangling_destinations,
+                LintRule::AliasLoop => alias_loops,
+                LintRule::ShadowedRewrite => shadowed_rewrites,
+                LintRule::MissingRecoveryEmail => missing_recovery_emails,
+                LintRule::ExpiredAutoresponder => expired_autoresponders,
+                LintRule::InactiveForwarding => inactive_forwardings,
+                LintRule::UnusableIdentity => unusable_identities,
+            };
+            check(domains, domain, today, &mut report);
+        }
+    }
+    findings
+}
+
+/// Records a finding: the resource, its id, its mailbox and a message.
+type Report<'a> = dyn FnMut(ResourceKind, &str, Option<&str>, String) + 'a;
+
+fn dangling_destinations(
+    domains: &[DomainState],
+    domain: &DomainState,
+    _today: &str,
+    report: &mut Report,
+) {
+    let sources = domain
+        .aliases
+        .iter()
+        .map(|a| (ResourceKind::Alias, &a.address, &a.destinations))
+        .chain(
+            domain
+                .rewrites
+                .iter()
+                .map(|r| (ResourceKind::Rewrite, &r.name, &r.destinations)),
+        );
+    for (resource, id, destinations) in sources {
+        for destination in destinations {
+            let delivery = resolve_address(destination, domains);
+            if delivery.route == Route::Undeliverable {
+                report(
+                    resource,
+                    id,
+                    None,
+                    format!(
+                        "{} {} delivers to {}, which nothing receives",
+                        resource, id, destination
+                    ),
+                );
+            }
+        }
+    }
+}
+
+fn alias_loops(domains: &[DomainState], domain: &DomainState, _today: &str, report: &mut Report) {
+    for alias in &domain.aliases {
+        let delivery = resolve_address(&alias.address, domains);
+        let mut path = Vec::new();
+        if find_loop(&delivery, &alias.address, &mut path) {
+            report(
+                ResourceKind::Alias,
+                &alias.address,
+                None,
+                format!(
+                    "mail to {} loops back to it: {}",
+                    alias.address,
+                    path.join(" -> ")
+                ),
+            );
+        }
+    }
+}
+
+fn shadowed_rewrites(_: &[DomainState], domain: &DomainState, _today: &str, report: &mut Report) {
+    for warning in check_rules(domain) {
+        report(
+            ResourceKind::Rewrite,
+            &warning.rule,
+            None,
+            warning.to_string(),
+        );
+    }
+}
+
+fn missing_recovery_emails(
+    _: &[DomainState],
+    domain: &DomainState,
+    _today: &str,
+    report: &mut Report,
+) {
+    for state in &domain.mailboxes {
+        let mailbox = &state.mailbox;
+        let email = mailbox.password_recovery_email.as_deref().unwrap_or("");
+        if email.trim().is_empty() {
+            report(
+                ResourceKind::Mailbox,
+                &mailbox.address,
+                None,
+                format!("mailbox {} has no password recovery email", mailbox.address),
+            );
+        }
+    }
+}
+
+fn expired_autoresponders(
+    _: &[DomainState],
+    domain: &DomainState,
+    today: &str,
+    report: &mut Report,
+) {
+    for state in &domain.mailboxes {
+        let mailbox = &state.mailbox;
+        let Some(expires) = mailbox.autorespond_expires_on.as_deref() else {
+            continue;
+        };
+        let expires = expires.get(..10).unwrap_or(expires);
+        if mailbox.autorespond_active == Some(true) && expires < today {
+            report(
+                ResourceKind::Mailbox,
+                &mailbox.address,
+                None,
+                format!(
+                    "autoresponder of {} is still active but expired on {}",
+                    mailbox.address, expires
+                ),
+            );
+        }
+    }
+}
+
+fn inactive_forwardings(
+    _: &[DomainState],
+    domain: &DomainState,
+    _today: &str,
+    report: &mut Report,
+) {
+    for state in &domain.mailboxes {
+        let mailbox = state.mailbox.address.as_str();
+        for forwarding in &state.forwardings {
+            let problem = if forwarding.blocked_at.is_some() {
+                "is blocked"
+            } else if forwarding.confirmed_at.is_none() {
+                "is awaiting confirmation"
+            } else {
+                continue;
+            };
+            report(
+                ResourceKind::Forwarding,
+                &forwarding.address,
+                Some(mailbox),
+                format!(
+                    "forwarding of {} to {} {}",
+                    mailbox, forwarding.address, problem
+                ),
+            );
+        }
+    }
+}
+
+fn unusable_identities(_: &[DomainState], domain: &DomainState, _today: &str, report: &mut Report) {
+    for state in &domain.mailboxes {
+        for identity in &state.identities {
+            if !identity.may_send && !identity.may_receive {
+                report(
+                    ResourceKind::Identity,
+                    &identity.address,
+                    Some(&state.mailbox.address),
+                    format!("identity {} may neither send nor receive", identity.address),
+                );
+            }
+        }
+    }
+}
+
+/// Finds a chain of deliveries from `delivery` back to `address`, recording it in `path`.
+fn find_loop(delivery: &Delivery, address: &str, path: &mut Vec<String>) -> bool {
+    path.push(delivery.address.clone());
+    if delivery.route == Route::Loop && delivery.address.eq_ignore_ascii_case(address) {
+        return true;
+    }
+    for next in &delivery.destinations {
+        if find_loop(next, address, path) {
+            return true;
+        }
+    }
+    path.pop();
+    false
+}
+
+/// Returns today's date in UTC as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or_default() as i64;
+
+    // Converts days since 1970-01-01 to a civil date (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
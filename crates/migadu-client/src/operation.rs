@@ -0,0 +1,336 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::api::paths;
+use crate::client::MigaduClient;
+use crate::error::Result;
+use crate::types::{
+    Alias, CreateAlias, CreateForwarding, CreateIdentity, CreateMailbox, CreateRewrite, Forwarding,
+    Identity, Mailbox, Rewrite, UpdateAlias, UpdateForwarding, UpdateIdentity, UpdateMailbox,
+    UpdateRewrite,
+};
+
+/// Placeholder shown instead of secrets in [`ApiRequest::masked`].
+pub const MASK: &str = "********";
+
+/// A create, update or delete call, described without sending it.
+///
+/// Operations can be inspected as the HTTP request they would make with
+/// [`Operation::request`], or sent with [`MigaduClient::execute`]. They carry passwords
+/// in plain text and so cannot be serialized; print or log
+/// [`ApiRequest::masked`] instead.
+#[derive(Debug, Clone)]
+pub enum Operation {
+    CreateMailbox {
+        domain: String,
+        mailbox: CreateMailbox,
+    },
+    UpdateMailbox {
+        domain: String,
+        local_part: String,
+        update: UpdateMailbox,
+    },
+    DeleteMailbox {
+        domain: String,
+        local_part: String,
+    },
+    CreateAlias {
+        domain: String,
+        alias: CreateAlias,
+    },
+    UpdateAlias {
+        domain: String,
+        local_part: String,
+        update: UpdateAlias,
+    },
+    DeleteAlias {
+        domain: String,
+        local_part: String,
+    },
+    CreateRewrite {
+        domain: String,
+        rewrite: CreateRewrite,
+    },
+    UpdateRewrite {
+        domain: String,
+        name: String,
+        update: UpdateRewrite,
+    },
+    DeleteRewrite {
+        domain: String,
+        name: String,
+    },
+    CreateIdentity {
+        domain: String,
+        mailbox: String,
+        identity: CreateIdentity,
+    },
+    UpdateIdentity {
+        domain: String,
+        mailbox: String,
+        local_part: String,
+        update: UpdateIdentity,
+    },
+    DeleteIdentity {
+        domain: String,
+        mailbox: String,
+        local_part: String,
+    },
+    CreateForwarding {
+        domain: String,
+        mailbox: String,
+        forwarding: CreateForwarding,
+    },
+    UpdateForwarding {
+        domain: String,
+        mailbox: String,
+        address: String,
+        update: UpdateForwarding,
+    },
+    DeleteForwarding {
+        domain: String,
+        mailbox: String,
+        address: String,
+    },
+}
+
+/// HTTP method of an [`ApiRequest`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    Get,
+    Post,
+    Put,
+    Delete,
+}
+
+impl fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Delete => "DELETE",
+        })
+    }
+}
+
+/// An HTTP request as it would be sent to the API.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApiRequest {
+    pub method: HttpMethod,
+    /// Path relative to the API base URL.
+    pub path: String,
+    /// JSON body, if the request has one.
+    pub body: Option<serde_json::Value>,
+}
+
+impl ApiRequest {
+    fn new(method: HttpMethod, path: String) -> Self {
+        Self {
+            method,
+            path,
+            body: None,
+        }
+    }
+
+    fn with_body(method: HttpMethod, path: String, body: &impl Serialize) -> Self {
+        Self {
+            method,
+            path,
+            body: Some(serde_json::to_value(body).expect("request bodies serialize to JSON")),
+        }
+    }
+
+    /// Returns a copy with passwords replaced by [`MASK`], safe to print or log.
+    pub fn masked(&self) -> Self {
+        let mut masked = self.clone();
+        if let Some(serde_json::Value::Object(fields)) = &mut masked.body {
+            if let Some(password) = fields.get_mut("password") {
+                *password = serde_json::Value::String(MASK.to_string());
+            }
+        }
+        masked
+    }
+}
+
+/// The resource returned by an executed [`Operation`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
+pub enum OperationOutput {
+    Mailbox(Mailbox),
+    Alias(Alias),
+    Rewrite(Rewrite),
+    Identity(Identity),
+    Forwarding(Forwarding),
+}
+
+impl Operation {
+    /// Returns the HTTP request this operation makes.
+    ///
+    /// Fails with [`Error::Path`](crate::Error::Path) if a name or address cannot be
+    /// used in the request path.
+    pub fn request(&self) -> Result<ApiRequest> {
+        use HttpMethod::{Delete, Post, Put};
+
+        Ok(match self {
+            Operation::CreateMailbox { domain, mailbox } => {
+                ApiRequest::with_body(Post, paths::mailboxes(domain)?, mailbox)
+            }
+            Operation::UpdateMailbox {
+                domain,
+                local_part,
+                update,
+            } => ApiRequest::with_body(Put, paths::mailbox(domain, local_part)?, update),
+            Operation::DeleteMailbox { domain, local_part } => {
+                ApiRequest::new(Delete, paths::mailbox(domain, local_part)?)
+            }
+            Operation::CreateAlias { domain, alias } => {
+                ApiRequest::with_body(Post, paths::aliases(domain)?, alias)
+            }
+            Operation::UpdateAlias {
+                domain,
+                local_part,
+                update,
+            } => ApiRequest::with_body(Put, paths::alias(domain, local_part)?, update),
+            Operation::DeleteAlias { domain, local_part } => {
+                ApiRequest::new(Delete, paths::alias(domain, local_part)?)
+            }
+            Operation::CreateRewrite { domain, rewrite } => {
+                ApiRequest::with_body(Post, paths::rewrites(domain)?, rewrite)
+            }
+            Operation::UpdateRewrite {
+                domain,
+                name,
+                update,
+            } => ApiRequest::with_body(Put, paths::rewrite(domain, name)?, update),
+            Operation::DeleteRewrite { domain, name } => {
+                ApiRequest::new(Delete, paths::rewrite(domain, name)?)
+            }
+            Operation::CreateIdentity {
+                domain,
+                mailbox,
+                identity,
+            } => ApiRequest::with_body(Post, paths::identities(domain, mailbox)?, identity),
+            Operation::UpdateIdentity {
+                domain,
+                mailbox,
+                local_part,
+                update,
+            } => ApiRequest::with_body(Put, paths::identity(domain, mailbox, local_part)?, update),
+            Operation::DeleteIdentity {
+                domain,
+                mailbox,
+                local_part,
+            } => ApiRequest::new(Delete, paths::identity(domain, mailbox, local_part)?),
+            Operation::CreateForwarding {
+                domain,
+                mailbox,
+                forwarding,
+            } => ApiRequest::with_body(Post, paths::forwardings(domain, mailbox)?, forwarding),
+            Operation::UpdateForwarding {
+                domain,
+                mailbox,
+                address,
+                update,
+            } => ApiRequest::with_body(Put, paths::forwarding(domain, mailbox, address)?, update),
+            Operation::DeleteForwarding {
+                domain,
+                mailbox,
+                address,
+            } => ApiRequest::new(Delete, paths::forwarding(domain, mailbox, address)?),
+        })
+    }
+}
+
+impl MigaduClient {
+    /// Sends an operation using the corresponding client method.
+    pub async fn execute(&self, operation: &Operation) -> Result<OperationOutput> {
+        Ok(match operation {
+            Operation::CreateMailbox { domain, mailbox } => {
+                OperationOutput::Mailbox(self.create_mailbox(domain, mailbox).await?)
+            }
+            Operation::UpdateMailbox {
+                domain,
+                local_part,
+                update,
+            } => OperationOutput::Mailbox(self.update_mailbox(domain, local_part, update).await?),
+            Operation::DeleteMailbox { domain, local_part } => {
+                OperationOutput::Mailbox(self.delete_mailbox(domain, local_part).await?)
+            }
+            Operation::CreateAlias { domain, alias } => {
+                OperationOutput::Alias(self.create_alias(domain, alias).await?)
+            }
+            Operation::UpdateAlias {
+                domain,
+                local_part,
+                update,
+            } => OperationOutput::Alias(self.update_alias(domain, local_part, update).await?),
+            Operation::DeleteAlias { domain, local_part } => {
+                OperationOutput::Alias(self.delete_alias(domain, local_part).await?)
+            }
+            Operation::CreateRewrite { domain, rewrite } => {
+                OperationOutput::Rewrite(self.create_rewrite(domain, rewrite).await?)
+            }
+            Operation::UpdateRewrite {
+                domain,
+                name,
+                update,
+            } => OperationOutput::Rewrite(self.update_rewrite(domain, name, update).await?),
+            Operation::DeleteRewrite { domain, name } => {
+                OperationOutput::Rewrite(self.delete_rewrite(domain, name).await?)
+            }
+            Operation::CreateIdentity {
+                domain,
+                mailbox,
+                identity,
+            } => OperationOutput::Identity(self.create_identity(domain, mailbox, identity).await?),
+            Operation::UpdateIdentity {
+                domain,
+                mailbox,
+                local_part,
+                update,
+            } => OperationOutput::Identity(
+                self.update_identity(domain, mailbox, local_part, update)
+                    .await?,
+            ),
+            Operation::DeleteIdentity {
+                domain,
+                mailbox,
+                local_part,
+            } => {
+                OperationOutput::Identity(self.delete_identity(domain, mailbox, local_part).await?)
+            }
+            Operation::CreateForwarding {
+                domain,
+                mailbox,
+                forwarding,
+            } => OperationOutput::Forwarding(
+                self.create_forwarding(domain, mailbox, forwarding).await?,
+            ),
+            Operation::UpdateForwarding {
+                domain,
+                mailbox,
+                address,
+                update,
+            } => OperationOutput::Forwarding(
+                self.update_forwarding(domain, mailbox, address, update)
+                    .await?,
+            ),
+            Operation::DeleteForwarding {
+                domain,
+                mailbox,
+                address,
+            } => {
+                OperationOutput::Forwarding(self.delete_forwarding(domain, mailbox, address).await?)
+            }
+        })
+    }
+
+    /// Returns the full URL a request is sent to.
+    pub fn request_url(&self, request: &ApiRequest) -> String {
+        self.url(&request.path)
+    }
+}
@@ -0,0 +1,936 @@
+//! Declarative management of a domain's configuration.
+//!
+//! A [`DesiredState`] describes the mailboxes, identities, forwardings, aliases and
+//! rewrites each domain should have. [`plan`] compares it with the live
+//! [`DomainState`] and produces the minimal set of operations needed to converge, and
+//! [`MigaduClient::apply`] runs them.
+//!
+//! Anything left out of the desired state is not managed: omitting `aliases` leaves a
+//! domain's aliases alone, while `aliases: []` deletes them all. The same goes for a
+//! mailbox's `identities` and `forwardings`, and for individual settings of each
+//! resource. Keys that are not part of the format are refused, so a misspelled setting
+//! is reported rather than left unmanaged. Resources are matched to live ones by local
+//! part, address or rewrite name, regardless of case, and rewrite rules are compared the
+//! same way.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use migadu_client::plan::DesiredState;
+//! use migadu_client::MigaduClient;
+//!
+//! # async fn run(client: MigaduClient, yaml: serde_json::Value) -> migadu_client::Result<()> {
+//! let desired: DesiredState = serde_json::from_value(yaml).unwrap();
+//! let plan = client.plan(&desired).await?;
+//! for change in &plan.changes {
+//!     println!("{}", change);
+//! }
+//! let report = client.apply(&plan).await;
+//! println!("{} changes applied", report.applied.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use serde::de::{self, DeserializeOwned};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+use crate::client::MigaduClient;
+use crate::diff::{self, FieldChange};
+use crate::error::{Error, Result};
+use crate::operation::{ApiRequest, Operation};
+use crate::state::{DomainState, MailboxState, ResourceKind};
+use crate::types::{
+    Alias, CreateAlias, CreateForwarding, CreateIdentity, CreateMailbox, CreateRewrite, Rewrite,
+    UpdateAlias, UpdateForwarding, UpdateIdentity, UpdateMailbox, UpdateRewrite,
+};
+
+/// The configuration each domain should have.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DesiredState {
+    #[serde(default)]
+    pub domains: BTreeMap<String, DomainSpec>,
+}
+
+/// The configuration one domain should have.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DomainSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mailboxes: Option<Vec<MailboxSpec>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aliases: Option<Vec<AliasSpec>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewrites: Option<Vec<RewriteSpec>>,
+}
+
+/// A mailbox and the settings to enforce on it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MailboxSpec {
+    pub local_part: String,
+    /// Settings to enforce. `name` and `password` are required to create the mailbox;
+    /// `password` is ignored for existing mailboxes.
+    #[serde(flatten, deserialize_with = "settings")]
+    pub settings: UpdateMailbox,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identities: Option<Vec<IdentitySpec>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forwardings: Option<Vec<ForwardingSpec>>,
+}
+
+/// An identity and the settings to enforce on it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentitySpec {
+    pub local_part: String,
+    /// Settings to enforce. `name` is required to create the identity; `password` is
+    /// ignored for existing identities.
+    #[serde(flatten, deserialize_with = "settings")]
+    pub settings: UpdateIdentity,
+}
+
+/// A forwarding address and the settings to enforce on it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForwardingSpec {
+    pub address: String,
+    #[serde(flatten, deserialize_with = "settings")]
+    pub settings: UpdateForwarding,
+}
+
+/// An alias and where it should deliver.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AliasSpec {
+    pub local_part: String,
+    pub destinations: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_internal: Option<bool>,
+}
+
+/// A rewrite rule and where it should deliver.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RewriteSpec {
+    pub name: String,
+    pub local_part_rule: String,
+    pub destinations: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_num: Option<i32>,
+}
+
+/// Deserializes the settings flattened into a spec, refusing keys that are not settings.
+///
+/// serde cannot combine `flatten` with `deny_unknown_fields`, and a misspelled setting
+/// would otherwise be left unmanaged while the plan comes out clean. The settings are
+/// only given keys the spec's own fields did not take; those that do not come back when
+/// the settings are serialized again are unknown.
+fn settings<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Serialize,
+{
+    let given = Map::<String, Value>::deserialize(deserializer)?;
+    let settings: T =
+        serde_json::from_value(Value::Object(given.clone())).map_err(de::Error::custom)?;
+    let known = to_value(&settings);
+    let unknown = given
+        .iter()
+        .find(|(key, value)| !value.is_null() && known.get(key.as_str()).is_none());
+    if let Some((key, _)) = unknown {
+        return Err(de::Error::custom(format!("unknown field `{}`", key)));
+    }
+    Ok(settings)
+}
+
+impl From<&DomainState> for DomainSpec {
+    /// Describes a domain exactly as it is, without mailbox passwords.
+    fn from(state: &DomainState) -> Self {
+        Self {
+            mailboxes: Some(state.mailboxes.iter().map(MailboxSpec::from).collect()),
+            aliases: Some(
+                state
+                    .aliases
+                    .iter()
+                    .map(|alias| AliasSpec {
+                        local_part: alias.local_part.clone(),
+                        destinations: alias.destinations.clone(),
+                        is_internal: Some(alias.is_internal),
+                    })
+                    .collect(),
+            ),
+            rewrites: Some(
+                state
+                    .rewrites
+                    .iter()
+                    .map(|rewrite| RewriteSpec {
+                        name: rewrite.name.clone(),
+                        local_part_rule: rewrite.local_part_rule.clone(),
+                        destinations: rewrite.destinations.clone(),
+                        order_num: rewrite.order_num,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<&MailboxState> for MailboxSpec {
+    fn from(state: &MailboxState) -> Self {
+        Self {
+            local_part: state.mailbox.local_part.clone(),
+            settings: UpdateMailbox::from(&state.mailbox),
+            identities: Some(
+                state
+                    .identities
+                    .iter()
+                    .map(|identity| IdentitySpec {
+                        local_part: identity.local_part.clone(),
+                        settings: UpdateIdentity::from(identity),
+                    })
+                    .collect(),
+            ),
+            forwardings: Some(
+                state
+                    .forwardings
+                    .iter()
+                    .map(|forwarding| ForwardingSpec {
+                        address: forwarding.address.clone(),
+                        settings: UpdateForwarding::from(forwarding),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// What a [`Change`] does to its resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// A single planned operation.
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    pub action: ChangeAction,
+    pub resource: ResourceKind,
+    pub domain: String,
+    /// Address or name identifying the resource.
+    pub id: String,
+    /// Address of the mailbox an identity or forwarding belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mailbox: Option<String>,
+    /// Fields this change sets, with passwords masked.
+    pub fields: Vec<FieldChange>,
+    /// The request this change sends, with passwords masked.
+    pub request: ApiRequest,
+    #[serde(skip)]
+    pub operation: Operation,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self.action {
+            ChangeAction::Create => '+',
+            ChangeAction::Update => '~',
+            ChangeAction::Delete => '-',
+        };
+        write!(f, "{} {} {}", symbol, self.resource, self.id)?;
+        if let Some(mailbox) = &self.mailbox {
+            write!(f, " (on {})", mailbox)?;
+        }
+        Ok(())
+    }
+}
+
+/// The operations needed to bring live state in line with a [`DesiredState`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Plan {
+    pub changes: Vec<Change>,
+}
+
+impl Plan {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Counts the changes with the given action.
+    pub fn count(&self, action: ChangeAction) -> usize {
+        self.changes.iter().filter(|c| c.action == action).count()
+    }
+}
+
+/// Outcome of [`MigaduClient::apply`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ApplyReport {
+    /// Changes that succeeded, in order.
+    pub applied: Vec<Change>,
+    /// The change that failed, which stopped the run.
+    pub failed: Option<FailedChange>,
+    /// Number of changes not attempted because of the failure.
+    pub skipped: usize,
+}
+
+/// A change that the API rejected.
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedChange {
+    pub change: Change,
+    pub error: String,
+}
+
+impl ApplyReport {
+    pub fn is_success(&self) -> bool {
+        self.failed.is_none()
+    }
+}
+
+impl MigaduClient {
+    /// Fetches the live state of every domain in `desired` and plans the changes.
+    pub async fn plan(&self, desired: &DesiredState) -> Result<Plan> {
+        let mut live = Vec::new();
+        for domain in desired.domains.keys() {
+            live.push(self.fetch_domain_state(domain).await?);
+        }
+        plan(desired, &live)
+    }
+
+    /// Executes a plan in order, stopping at the first error.
+    pub async fn apply(&self, plan: &Plan) -> ApplyReport {
+        let mut report = ApplyReport::default();
+        for (index, change) in plan.changes.iter().enumerate() {
+            if let Err(e) = self.execute(&change.operation).await {
+                report.failed = Some(FailedChange {
+                    change: change.clone(),
+                    error: e.to_string(),
+                });
+                report.skipped = plan.changes.len() - index - 1;
+                break;
+            }
+            report.applied.push(change.clone());
+        }
+        report
+    }
+}
+
+/// Plans the changes that bring `live` in line with `desired`.
+///
+/// `live` must contain the state of every domain in `desired`.
+pub fn plan(desired: &DesiredState, live: &[DomainState]) -> Result<Plan> {
+    let mut changes = Vec::new();
+    for (domain, spec) in &desired.domains {
+        let state = live
+            .iter()
+            .find(|state| state.domain.eq_ignore_ascii_case(domain))
+            .ok_or_else(|| Error::Plan(format!("No live state for domain {}", domain)))?;
+        changes.extend(plan_domain(domain, spec, state)?);
+    }
+    Ok(Plan { changes })
+}
+
+/// Plans the changes for a single domain.
+///
+/// Mailboxes and their identities and forwardings are created or updated first, then
+/// aliases and rewrites, and mailboxes are deleted last.
+pub fn plan_domain(domain: &str, spec: &DomainSpec, live: &DomainState) -> Result<Vec<Change>> {
+    let mut planner = Planner {
+        domain,
+        changes: Vec::new(),
+    };
+    let mut mailbox_deletes = Vec::new();
+
+    if let Some(mailboxes) = &spec.mailboxes {
+        check_unique(mailboxes.iter().map(|m| m.local_part.as_str()), "mailbox")?;
+        for mailbox in mailboxes {
+            match live.mailbox(&mailbox.local_part) {
+                Some(current) => planner.update_mailbox(mailbox, current)?,
+                None => planner.create_mailbox(mailbox)?,
+            }
+        }
+        for current in &live.mailboxes {
+            let local_part = &current.mailbox.local_part;
+            if !mailboxes
+                .iter()
+                .any(|m| m.local_part.eq_ignore_ascii_case(local_part))
+            {
+                mailbox_deletes.push(planner.change(
+                    ChangeAction::Delete,
+                    ResourceKind::Mailbox,
+                    current.mailbox.address.clone(),
+                    None,
+                    Vec::new(),
+                    Operation::DeleteMailbox {
+                        domain: domain.to_string(),
+                        local_part: local_part.clone(),
+                    },
+                )?);
+            }
+        }
+    }
+
+    if let Some(aliases) = &spec.aliases {
+        check_unique(aliases.iter().map(|a| a.local_part.as_str()), "alias")?;
+        planner.aliases(aliases, &live.aliases)?;
+    }
+
+    if let Some(rewrites) = &spec.rewrites {
+        check_unique(rewrites.iter().map(|r| r.name.as_str()), "rewrite")?;
+        planner.rewrites(rewrites, &live.rewrites)?;
+    }
+
+    planner.changes.extend(mailbox_deletes);
+    Ok(planner.changes)
+}
+
+struct Planner<'a> {
+    domain: &'a str,
+    changes: Vec<Change>,
+}
+
+impl Planner<'_> {
+    fn address(&self, local_part: &str) -> String {
+        format!("{}@{}", local_part, self.domain)
+    }
+
+    fn change(
+        &self,
+        action: ChangeAction,
+        resource: ResourceKind,
+        id: String,
+        mailbox: Option<String>,
+        fields: Vec<FieldChange>,
+        operation: Operation,
+    ) -> Result<Change> {
+        let request = operation.request()?.masked();
+        let fields = match action {
+            ChangeAction::Create => request
+                .body
+                .as_ref()
+                .map(diff::creation_changes)
+                .unwrap_or_default(),
+            _ => fields,
+        };
+        Ok(Change {
+            action,
+            resource,
+            domain: self.domain.to_string(),
+            id,
+            mailbox,
+            fields,
+            request,
+            operation,
+        })
+    }
+
+    fn push(
+        &mut self,
+        action: ChangeAction,
+        resource: ResourceKind,
+        id: String,
+        mailbox: Option<String>,
+        fields: Vec<FieldChange>,
+        operation: Operation,
+    ) -> Result<()> {
+        let change = self.change(action, resource, id, mailbox, fields, operation)?;
+        self.changes.push(change);
+        Ok(())
+    }
+
+    fn create_mailbox(&mut self, spec: &MailboxSpec) -> Result<()> {
+        let address = self.address(&spec.local_part);
+        let settings = &spec.settings;
+        let (Some(name), Some(password)) = (&settings.name, &settings.password) else {
+            return Err(Error::Plan(format!(
+                "Mailbox {} does not exist and needs a name and password to be created",
+                address
+            )));
+        };
+
+        let create = CreateMailbox {
+            password_recovery_email: settings.password_recovery_email.clone(),
+            is_internal: settings.is_internal,
+            ..CreateMailbox::new(&spec.local_part, name, password)
+        };
+        self.push(
+            ChangeAction::Create,
+            ResourceKind::Mailbox,
+            address.clone(),
+            None,
+            Vec::new(),
+            Operation::CreateMailbox {
+                domain: self.domain.to_string(),
+                mailbox: create,
+            },
+        )?;
+
+        let remaining = UpdateMailbox {
+            name: None,
+            password: None,
+            password_recovery_email: None,
+            is_internal: None,
+            ..settings.clone()
+        };
+        let fields = diff::update_changes(&Value::Null, &to_value(&remaining));
+        if !fields.is_empty() {
+            self.push(
+                ChangeAction::Update,
+                ResourceKind::Mailbox,
+                address.clone(),
+                None,
+                fields.clone(),
+                Operation::UpdateMailbox {
+                    domain: self.domain.to_string(),
+                    local_part: spec.local_part.clone(),
+                    update: subset(&to_value(&remaining), &fields),
+                },
+            )?;
+        }
+
+        for identity in spec.identities.iter().flatten() {
+            self.create_identity(&spec.local_part, identity)?;
+        }
+        for forwarding in spec.forwardings.iter().flatten() {
+            self.create_forwarding(&spec.local_part, forwarding)?;
+        }
+        Ok(())
+    }
+
+    fn update_mailbox(&mut self, spec: &MailboxSpec, current: &MailboxState) -> Result<()> {
+        let address = current.mailbox.address.clone();
+        let wanted = without_password(to_value(&spec.settings));
+        let fields = diff::update_changes(&to_value(&current.mailbox), &wanted);
+        if !fields.is_empty() {
+            self.push(
+                ChangeAction::Update,
+                ResourceKind::Mailbox,
+                address.clone(),
+                None,
+                fields.clone(),
+                Operation::UpdateMailbox {
+                    domain: self.domain.to_string(),
+                    local_part: current.mailbox.local_part.clone(),
+                    update: subset(&wanted, &fields),
+                },
+            )?;
+        }
+
+        let mailbox = &current.mailbox.local_part;
+        if let Some(identities) = &spec.identities {
+            check_unique(identities.iter().map(|i| i.local_part.as_str()), "identity")?;
+            for identity in identities {
+                let existing = current
+                    .identities
+                    .iter()
+                    .find(|i| i.local_part.eq_ignore_ascii_case(&identity.local_part));
+                match existing {
+                    Some(existing) => {
+                        let wanted = without_password(to_value(&identity.settings));
+                        let fields = diff::update_changes(&to_value(existing), &wanted);
+                        if !fields.is_empty() {
+                            self.push(
+                                ChangeAction::Update,
+                                ResourceKind::Identity,
+                                existing.address.clone(),
+                                Some(address.clone()),
+                                fields.clone(),
+                                Operation::UpdateIdentity {
+                                    domain: self.domain.to_string(),
+                                    mailbox: mailbox.clone(),
+                                    local_part: existing.local_part.clone(),
+                                    update: subset(&wanted, &fields),
+                                },
+                            )?;
+                        }
+                    }
+                    None => self.create_identity(mailbox, identity)?,
+                }
+            }
+            for existing in &current.identities {
+                if !identities
+                    .iter()
+                    .any(|i| i.local_part.eq_ignore_ascii_case(&existing.local_part))
+                {
+                    self.push(
+                        ChangeAction::Delete,
+                        ResourceKind::Identity,
+                        existing.address.clone(),
+                        Some(address.clone()),
+                        Vec::new(),
+                        Operation::DeleteIdentity {
+                            domain: self.domain.to_string(),
+                            mailbox: mailbox.clone(),
+                            local_part: existing.local_part.clone(),
+                        },
+                    )?;
+                }
+            }
+        }
+
+        if let Some(forwardings) = &spec.forwardings {
+            check_unique(forwardings.iter().map(|f| f.address.as_str()), "forwarding")?;
+            for forwarding in forwardings {
+                let existing = current
+                    .forwardings
+                    .iter()
+                    .find(|f| f.address.eq_ignore_ascii_case(&forwarding.address));
+                match existing {
+                    Some(existing) => {
+                        let wanted = to_value(&forwarding.settings);
+                        let fields = diff::update_changes(&to_value(existing), &wanted);
+                        if !fields.is_empty() {
+                            self.push(
+                                ChangeAction::Update,
+                                ResourceKind::Forwarding,
+                                existing.address.clone(),
+                                Some(address.clone()),
+                                fields.clone(),
+                                Operation::UpdateForwarding {
+                                    domain: self.domain.to_string(),
+                                    mailbox: mailbox.clone(),
+                                    address: existing.address.clone(),
+                                    update: subset(&wanted, &fields),
+                                },
+                            )?;
+                        }
+                    }
+                    None => self.create_forwarding(mailbox, forwarding)?,
+                }
+            }
+            for existing in &current.forwardings {
+                if !forwardings
+                    .iter()
+                    .any(|f| f.address.eq_ignore_ascii_case(&existing.address))
+                {
+                    self.push(
+                        ChangeAction::Delete,
+                        ResourceKind::Forwarding,
+                        existing.address.clone(),
+                        Some(address.clone()),
+                        Vec::new(),
+                        Operation::DeleteForwarding {
+                            domain: self.domain.to_string(),
+                            mailbox: mailbox.clone(),
+                            address: existing.address.clone(),
+                        },
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn create_identity(&mut self, mailbox: &str, spec: &IdentitySpec) -> Result<()> {
+        let address = self.address(&spec.local_part);
+        let settings = &spec.settings;
+        let Some(name) = &settings.name else {
+            return Err(Error::Plan(format!(
+                "Identity {} does not exist and needs a name to be created",
+                address
+            )));
+        };
+
+        let create = CreateIdentity {
+            password: settings.password.clone(),
+            may_send: settings.may_send,
+            may_receive: settings.may_receive,
+            may_access_imap: settings.may_access_imap,
+            may_access_pop3: settings.may_access_pop3,
+            may_access_managesieve: settings.may_access_managesieve,
+            ..CreateIdentity::new(&spec.local_part, name)
+        };
+        self.push(
+            ChangeAction::Create,
+            ResourceKind::Identity,
+            address.clone(),
+            Some(self.address(mailbox)),
+            Vec::new(),
+            Operation::CreateIdentity {
+                domain: self.domain.to_string(),
+                mailbox: mailbox.to_string(),
+                identity: create,
+            },
+        )?;
+
+        let remaining = UpdateIdentity {
+            footer_active: settings.footer_active,
+            footer_plain_body: settings.footer_plain_body.clone(),
+            footer_html_body: settings.footer_html_body.clone(),
+            ..Default::default()
+        };
+        let fields = diff::update_changes(&Value::Null, &to_value(&remaining));
+        if !fields.is_empty() {
+            self.push(
+                ChangeAction::Update,
+                ResourceKind::Identity,
+                address,
+                Some(self.address(mailbox)),
+                fields.clone(),
+                Operation::UpdateIdentity {
+                    domain: self.domain.to_string(),
+                    mailbox: mailbox.to_string(),
+                    local_part: spec.local_part.clone(),
+                    update: subset(&to_value(&remaining), &fields),
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    fn create_forwarding(&mut self, mailbox: &str, spec: &ForwardingSpec) -> Result<()> {
+        self.push(
+            ChangeAction::Create,
+            ResourceKind::Forwarding,
+            spec.address.clone(),
+            Some(self.address(mailbox)),
+            Vec::new(),
+            Operation::CreateForwarding {
+                domain: self.domain.to_string(),
+                mailbox: mailbox.to_string(),
+                forwarding: CreateForwarding::new(&spec.address),
+            },
+        )?;
+
+        let fields = diff::update_changes(&Value::Null, &to_value(&spec.settings));
+        if !fields.is_empty() {
+            self.push(
+                ChangeAction::Update,
+                ResourceKind::Forwarding,
+                spec.address.clone(),
+                Some(self.address(mailbox)),
+                fields.clone(),
+                Operation::UpdateForwarding {
+                    domain: self.domain.to_string(),
+                    mailbox: mailbox.to_string(),
+                    address: spec.address.clone(),
+                    update: subset(&to_value(&spec.settings), &fields),
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    fn aliases(&mut self, specs: &[AliasSpec], live: &[Alias]) -> Result<()> {
+        for spec in specs {
+            let address = self.address(&spec.local_part);
+            let existing = live
+                .iter()
+                .find(|a| a.local_part.eq_ignore_ascii_case(&spec.local_part));
+            let Some(existing) = existing else {
+                let create = CreateAlias {
+                    is_internal: spec.is_internal,
+                    ..CreateAlias::from_destinations(&spec.local_part, &spec.destinations)
+                };
+                self.push(
+                    ChangeAction::Create,
+                    ResourceKind::Alias,
+                    address,
+                    None,
+                    Vec::new(),
+                    Operation::CreateAlias {
+                        domain: self.domain.to_string(),
+                        alias: create,
+                    },
+                )?;
+                continue;
+            };
+
+            let mut fields = Vec::new();
+            let mut update = UpdateAlias::default();
+            if !same_addresses(&existing.destinations, &spec.destinations) {
+                fields.push(FieldChange::new(
+                    "destinations",
+                    to_value(&existing.destinations),
+                    to_value(&spec.destinations),
+                ));
+                update.destinations = Some(spec.destinations.join(","));
+            }
+            if let Some(is_internal) = spec.is_internal.filter(|v| *v != existing.is_internal) {
+                fields.push(FieldChange::new(
+                    "is_internal",
+                    Value::Bool(existing.is_internal),
+                    Value::Bool(is_internal),
+                ));
+                update.is_internal = Some(is_internal);
+            }
+            if !fields.is_empty() {
+                self.push(
+                    ChangeAction::Update,
+                    ResourceKind::Alias,
+                    existing.address.clone(),
+                    None,
+                    fields,
+                    Operation::UpdateAlias {
+                        domain: self.domain.to_string(),
+                        local_part: existing.local_part.clone(),
+                        update,
+                    },
+                )?;
+            }
+        }
+
+        for existing in live {
+            if !specs
+                .iter()
+                .any(|a| a.local_part.eq_ignore_ascii_case(&existing.local_part))
+            {
+                self.push(
+                    ChangeAction::Delete,
+                    ResourceKind::Alias,
+                    existing.address.clone(),
+                    None,
+                    Vec::new(),
+                    Operation::DeleteAlias {
+                        domain: self.domain.to_string(),
+                        local_part: existing.local_part.clone(),
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn rewrites(&mut self, specs: &[RewriteSpec], live: &[Rewrite]) -> Result<()> {
+        for spec in specs {
+            let existing = live
+                .iter()
+                .find(|r| r.name.eq_ignore_ascii_case(&spec.name));
+            let Some(existing) = existing else {
+                let create = CreateRewrite {
+                    order_num: spec.order_num,
+                    ..CreateRewrite::from_destinations(
+                        &spec.name,
+                        &spec.local_part_rule,
+                        &spec.destinations,
+                    )
+                };
+                self.push(
+                    ChangeAction::Create,
+                    ResourceKind::Rewrite,
+                    spec.name.clone(),
+                    None,
+                    Vec::new(),
+                    Operation::CreateRewrite {
+                        domain: self.domain.to_string(),
+                        rewrite: create,
+                    },
+                )?;
+                continue;
+            };
+
+            let mut fields = Vec::new();
+            let mut update = UpdateRewrite::default();
+            if !existing
+                .local_part_rule
+                .eq_ignore_ascii_case(&spec.local_part_rule)
+            {
+                fields.push(FieldChange::new(
+                    "local_part_rule",
+                    to_value(&existing.local_part_rule),
+                    to_value(&spec.local_part_rule),
+                ));
+                update.local_part_rule = Some(spec.local_part_rule.clone());
+            }
+            if !same_addresses(&existing.destinations, &spec.destinations) {
+                fields.push(FieldChange::new(
+                    "destinations",
+                    to_value(&existing.destinations),
+                    to_value(&spec.destinations),
+                ));
+                update.destinations = Some(spec.destinations.join(","));
+            }
+            if spec.order_num.is_some() && spec.order_num != existing.order_num {
+                fields.push(FieldChange::new(
+                    "order_num",
+                    to_value(&existing.order_num),
+                    to_value(&spec.order_num),
+                ));
+                update.order_num = spec.order_num;
+            }
+            if !fields.is_empty() {
+                self.push(
+                    ChangeAction::Update,
+                    ResourceKind::Rewrite,
+                    existing.name.clone(),
+                    None,
+                    fields,
+                    Operation::UpdateRewrite {
+                        domain: self.domain.to_string(),
+                        name: existing.name.clone(),
+                        update,
+                    },
+                )?;
+            }
+        }
+
+        for existing in live {
+            if !specs
+                .iter()
+                .any(|r| r.name.eq_ignore_ascii_case(&existing.name))
+            {
+                self.push(
+                    ChangeAction::Delete,
+                    ResourceKind::Rewrite,
+                    existing.name.clone(),
+                    None,
+                    Vec::new(),
+                    Operation::DeleteRewrite {
+                        domain: self.domain.to_string(),
+                        name: existing.name.clone(),
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_value(value: &impl Serialize) -> Value {
+    serde_json::to_value(value).expect("resources serialize to JSON")
+}
+
+fn without_password(mut value: Value) -> Value {
+    if let Value::Object(fields) = &mut value {
+        fields.remove("password");
+    }
+    value
+}
+
+/// Builds an update body from the fields of `wanted` that changed.
+fn subset<T: DeserializeOwned>(wanted: &Value, changes: &[FieldChange]) -> T {
+    let fields: Map<String, Value> = changes
+        .iter()
+        .filter_map(|change| {
+            wanted
+                .get(&change.field)
+                .map(|value| (change.field.clone(), value.clone()))
+        })
+        .collect();
+    serde_json::from_value(Value::Object(fields)).expect("update fields round-trip")
+}
+
+/// Compares address lists regardless of order and case.
+fn same_addresses(a: &[String], b: &[String]) -> bool {
+    let normalize = |list: &[String]| {
+        let mut list: Vec<String> = list.iter().map(|s| s.trim().to_lowercase()).collect();
+        list.sort();
+        list.dedup();
+        list
+    };
+    normalize(a) == normalize(b)
+}
+
+fn check_unique<'a>(keys: impl Iterator<Item = &'a str>, kind: &str) -> Result<()> {
+    let mut seen = HashSet::new();
+    for key in keys {
+        if !seen.insert(key.to_lowercase()) {
+            return Err(Error::Plan(format!("Duplicate {} '{}'", kind, key)));
+        }
+    }
+    Ok(())
+}
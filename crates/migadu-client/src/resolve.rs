@@ -0,0 +1,230 @@
+//! Working out where mail to an address is delivered.
+//!
+//! An address on a hosted domain is handled by the first of these that matches its
+//! local part: a mailbox, an identity of a mailbox, an alias, or a rewrite rule
+//! (tried in `order_num` order). Mailboxes pass mail on to their forwardings that are
+//! active, confirmed and not blocked, identities to their mailbox, and aliases and
+//! rewrites to their destinations, which are resolved in turn.
+
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+use crate::client::MigaduClient;
+use crate::error::Result;
+use crate::rewrite_rules;
+use crate::state::DomainState;
+
+/// Deepest chain of aliases, rewrites and forwardings that is followed.
+const MAX_DEPTH: usize = 16;
+
+/// How mail to one address is handled, and where it goes next.
+#[derive(Debug, Clone, Serialize)]
+pub struct Delivery {
+    pub address: String,
+    #[serde(flatten)]
+    pub route: Route,
+    /// Addresses the mail is passed on to.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub destinations: Vec<Delivery>,
+}
+
+/// What handles mail to an address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "route", rename_all = "snake_case")]
+pub enum Route {
+    /// Stored in the mailbox with this address, and copied to the forwardings that
+    /// deliver (see [`Forwarding::delivers`](crate::Forwarding::delivers)).
+    Mailbox {
+        /// Only accepts mail from senders on the same domain.
+        internal: bool,
+    },
+    /// An identity, delivered to the mailbox it belongs to.
+    Identity { mailbox: String },
+    /// An alias, delivered to its destinations.
+    Alias {
+        /// Only accepts mail from senders on the same domain.
+        internal: bool,
+    },
+    /// Matched by a rewrite rule, delivered to its destinations.
+    Rewrite {
+        name: String,
+        local_part_rule: String,
+        order_num: Option<i32>,
+    },
+    /// Refused by the mailbox or identity with this address.
+    Rejected { reason: String },
+    /// Nothing on the hosted domain matches the address.
+    Undeliverable,
+    /// Handled by another mail provider.
+    External,
+    /// Already delivered higher up this chain, so it is not followed again.
+    Loop,
+    /// Not followed because the chain is too long.
+    TooDeep,
+}
+
+impl MigaduClient {
+    /// Works out where mail to `address` is delivered.
+    ///
+    /// The live state of each hosted domain the mail passes through is fetched, so
+    /// aliases and forwardings pointing at other domains on the account are followed.
+    pub async fn resolve_address(&self, address: &str) -> Result<Delivery> {
+        let hosted: Vec<String> = self
+            .list_domains()
+            .await?
+            .into_iter()
+            .map(|d| d.name.to_ascii_lowercase())
+            .collect();
+        let mut states: Vec<DomainState> = Vec::new();
+        loop {
+            let mut missing = BTreeSet::new();
+            let delivery = Resolver {
+                states: &states,
+                hosted: &hosted,
+                missing: &mut missing,
+            }
+            .resolve(address, &mut Vec::new());
+            if missing.is_empty() {
+                return Ok(delivery);
+            }
+            for domain in missing {
+                states.push(self.fetch_domain_state(&domain).await?);
+            }
+        }
+    }
+}
+
+/// Works out where mail to `address` is delivered, given the live state of the
+/// hosted domains.
+///
+/// Addresses on domains not in `domains` are treated as external.
+pub fn resolve_address(address: &str, domains: &[DomainState]) -> Delivery {
+    let hosted: Vec<String> = domains
+        .iter()
+        .map(|d| d.domain.to_ascii_lowercase())
+        .collect();
+    Resolver {
+        states: domains,
+        hosted: &hosted,
+        missing: &mut BTreeSet::new(),
+    }
+    .resolve(address, &mut Vec::new())
+}
+
+struct Resolver<'a> {
+    states: &'a [DomainState],
+    /// Lowercase names of the hosted domains.
+    hosted: &'a [String],
+    /// Hosted domains that were reached but whose state is not loaded yet.
+    missing: &'a mut BTreeSet<String>,
+}
+
+impl Resolver<'_> {
+    /// Resolves `address`, given the addresses already on the chain leading to it.
+    fn resolve(&mut self, address: &str, path: &mut Vec<String>) -> Delivery {
+        let key = address.to_ascii_lowercase();
+        let (local_part, domain) = key.rsplit_once('@').unwrap_or((key.as_str(), ""));
+
+        if path.contains(&key) {
+            return leaf(address, Route::Loop);
+        }
+        if path.len() >= MAX_DEPTH {
+            return leaf(address, Route::TooDeep);
+        }
+        if !self.hosted.iter().any(|d| d == domain) {
+            return leaf(address, Route::External);
+        }
+        let Some(state) = self
+            .states
+            .iter()
+            .find(|s| s.domain.eq_ignore_ascii_case(domain))
+        else {
+            self.missing.insert(domain.to_string());
+            return leaf(address, Route::Undeliverable);
+        };
+
+        let (route, next) = match_address(state, local_part);
+        path.push(key);
+        let destinations = next.iter().map(|next| self.resolve(next, path)).collect();
+        path.pop();
+        Delivery {
+            address: address.to_string(),
+            route,
+            destinations,
+        }
+    }
+}
+
+/// Finds what handles `local_part` on a domain, and the addresses it passes mail to.
+fn match_address(state: &DomainState, local_part: &str) -> (Route, Vec<String>) {
+    if let Some(mailbox) = state.mailbox(local_part) {
+        if !mailbox.mailbox.may_receive {
+            return rejected("the mailbox may not receive mail");
+        }
+        let forwardings = mailbox
+            .forwardings
+            .iter()
+            .filter(|f| f.delivers())
+            .map(|f| f.address.clone())
+            .collect();
+        let route = Route::Mailbox {
+            internal: mailbox.mailbox.is_internal,
+        };
+        return (route, forwardings);
+    }
+
+    for mailbox in &state.mailboxes {
+        let identity = mailbox
+            .identities
+            .iter()
+            .find(|i| i.local_part.eq_ignore_ascii_case(local_part));
+        if let Some(identity) = identity {
+            if !identity.may_receive {
+                return rejected("the identity may not receive mail");
+            }
+            let address = mailbox.mailbox.address.clone();
+            let route = Route::Identity {
+                mailbox: address.clone(),
+            };
+            return (route, vec![address]);
+        }
+    }
+
+    if let Some(alias) = state
+        .aliases
+        .iter()
+        .find(|a| a.local_part.eq_ignore_ascii_case(local_part))
+    {
+        let route = Route::Alias {
+            internal: alias.is_internal,
+        };
+        return (route, alias.destinations.clone());
+    }
+
+    if let Some(rewrite) = rewrite_rules::first_match(&state.rewrites, local_part) {
+        let route = Route::Rewrite {
+            name: rewrite.name.clone(),
+            local_part_rule: rewrite.local_part_rule.clone(),
+            order_num: rewrite.order_num,
+        };
+        return (route, rewrite.destinations.clone());
+    }
+
+    (Route::Undeliverable, Vec::new())
+}
+
+fn rejected(reason: &str) -> (Route, Vec<String>) {
+    let route = Route::Rejected {
+        reason: reason.to_string(),
+    };
+    (route, Vec::new())
+}
+
+fn leaf(address: &str, route: Route) -> Delivery {
+    Delivery {
+        address: address.to_string(),
+        route,
+        destinations: Vec::new(),
+    }
+}
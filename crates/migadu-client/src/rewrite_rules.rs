@@ -0,0 +1,186 @@
+//! Evaluating rewrite rules locally, as the server would.
+//!
+//! Mail to a local part with no mailbox, identity or alias goes to the first rewrite
+//! rule, in `order_num` order, whose `local_part_rule` matches it (see
+//! [`Rewrite::matches`]). These functions show which rule that is, and find rules
+//! that can never match, so new rules can be tried before they are created.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::state::{DomainState, ResourceKind};
+use crate::types::{wildcard_match, Rewrite};
+
+/// Whether a rule matches the local part being tested.
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleMatch {
+    pub name: String,
+    pub local_part_rule: String,
+    pub order_num: Option<i32>,
+    pub matches: bool,
+}
+
+/// How the rules of a domain handle one local part.
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleTest {
+    pub local_part: String,
+    /// Resource with this local part, which takes precedence over every rule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handled_by: Option<ResourceKind>,
+    /// Every rule, in evaluation order.
+    pub rules: Vec<RuleMatch>,
+    /// Name of the first matching rule, if any.
+    pub matched: Option<String>,
+    /// Problems with the rules themselves, independent of the local part.
+    pub warnings: Vec<RuleWarning>,
+}
+
+/// A rule that can never match.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuleWarning {
+    pub rule: String,
+    pub order_num: Option<i32>,
+    #[serde(flatten)]
+    pub problem: RuleProblem,
+}
+
+/// Why a rule can never match.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum RuleProblem {
+    /// Every local part it matches is matched by an earlier rule.
+    Shadowed {
+        by: String,
+        by_order_num: Option<i32>,
+    },
+    /// It matches a single local part, which a mailbox, identity or alias already
+    /// handles.
+    Unreachable { handled_by: ResourceKind },
+}
+
+impl fmt::Display for RuleWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rewrite {}{} ", self.rule, order(self.order_num))?;
+        match &self.problem {
+            RuleProblem::Shadowed { by, by_order_num } => write!(
+                f,
+                "is shadowed by {}{}: every local part it matches is matched there first",
+                by,
+                order(*by_order_num)
+            ),
+            RuleProblem::Unreachable { handled_by } => write!(
+                f,
+                "is unreachable: mail to its local part goes to the {} of that name",
+                handled_by
+            ),
+        }
+    }
+}
+
+fn order(order_num: Option<i32>) -> String {
+    order_num
+        .map(|n| format!(" (order {})", n))
+        .unwrap_or_default()
+}
+
+/// Returns the rules in the order the server tries them, see [`cmp_evaluation_order`].
+pub fn evaluation_order(rewrites: &[Rewrite]) -> Vec<&Rewrite> {
+    let mut rules: Vec<&Rewrite> = rewrites.iter().collect();
+    rules.sort_by(|a, b| cmp_evaluation_order(a, b));
+    rules
+}
+
+/// Orders rules as the server tries them: by `order_num`, with rules that have none
+/// last, and by name among rules with the same `order_num`.
+pub fn cmp_evaluation_order(a: &Rewrite, b: &Rewrite) -> Ordering {
+    fn key(rule: &Rewrite) -> (bool, Option<i32>, &str) {
+        (rule.order_num.is_none(), rule.order_num, &rule.name)
+    }
+    key(a).cmp(&key(b))
+}
+
+/// Returns the first rule that matches `local_part`.
+pub fn first_match<'a>(rewrites: &'a [Rewrite], local_part: &str) -> Option<&'a Rewrite> {
+    evaluation_order(rewrites)
+        .into_iter()
+        .find(|r| r.matches(local_part))
+}
+
+/// Returns `true` if every local part matching `pattern` also matches `covering`.
+pub fn covers(covering: &str, pattern: &str) -> bool {
+    // Matching the pattern itself as text treats its wildcards as characters that
+    // only a wildcard in `covering` can match, which is exactly containment.
+    wildcard_match(covering, pattern)
+}
+
+/// Shows how the rules of a domain handle `local_part`.
+pub fn test_rules(state: &DomainState, local_part: &str) -> RuleTest {
+    let rules: Vec<RuleMatch> = evaluation_order(&state.rewrites)
+        .into_iter()
+        .map(|r| RuleMatch {
+            name: r.name.clone(),
+            local_part_rule: r.local_part_rule.clone(),
+            order_num: r.order_num,
+            matches: r.matches(local_part),
+        })
+        .collect();
+    let matched = rules.iter().find(|r| r.matches).map(|r| r.name.clone());
+    RuleTest {
+        local_part: local_part.to_string(),
+        handled_by: handled_by(state, local_part),
+        rules,
+        matched,
+        warnings: check_rules(state),
+    }
+}
+
+/// Finds the rules of a domain that can never match.
+pub fn check_rules(state: &DomainState) -> Vec<RuleWarning> {
+    let rules = evaluation_order(&state.rewrites);
+    let mut warnings = Vec::new();
+    for (i, rule) in rules.iter().enumerate() {
+        let shadowing = rules[..i]
+            .iter()
+            .find(|earlier| covers(&earlier.local_part_rule, &rule.local_part_rule));
+        let problem = if let Some(earlier) = shadowing {
+            RuleProblem::Shadowed {
+                by: earlier.name.clone(),
+                by_order_num: earlier.order_num,
+            }
+        } else if rule.local_part_rule.contains('*') {
+            continue;
+        } else if let Some(kind) = handled_by(state, &rule.local_part_rule) {
+            RuleProblem::Unreachable { handled_by: kind }
+        } else {
+            continue;
+        };
+        warnings.push(RuleWarning {
+            rule: rule.name.clone(),
+            order_num: rule.order_num,
+            problem,
+        });
+    }
+    warnings
+}
+
+/// Finds the mailbox, identity or alias that handles `local_part` before any rule.
+pub fn handled_by(state: &DomainState, local_part: &str) -> Option<ResourceKind> {
+    if state.mailbox(local_part).is_some() {
+        return Some(ResourceKind::Mailbox);
+    }
+    let identity = state.mailboxes.iter().any(|m| {
+        m.identities
+            .iter()
+            .any(|i| i.local_part.eq_ignore_ascii_case(local_part))
+    });
+    if identity {
+        return Some(ResourceKind::Identity);
+    }
+    state
+        .aliases
+        .iter()
+        .any(|a| a.local_part.eq_ignore_ascii_case(local_part))
+        .then_some(ResourceKind::Alias)
+}
@@ -0,0 +1,99 @@
+//! Portable snapshots of an account's configuration.
+//!
+//! A [`Snapshot`] holds the [`DomainState`] of each domain on the account, sorted so that
+//! two exports of the same configuration serialize identically. Rewrites are sorted in
+//! the order the server tries them. Snapshots never contain
+//! secrets: the API does not return passwords, and only the typed fields of each
+//! resource are kept.
+
+use serde::{Deserialize, Serialize};
+
+use crate::client::MigaduClient;
+use crate::error::{Error, Result};
+use crate::rewrite_rules::cmp_evaluation_order;
+use crate::state::DomainState;
+
+/// Format version written by this crate.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Configuration of one or more domains at a point in time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Format version, see [`SNAPSHOT_VERSION`].
+    pub version: u32,
+    #[serde(default)]
+    pub domains: Vec<DomainState>,
+}
+
+impl Snapshot {
+    /// Creates a snapshot of the given domains in a stable order.
+    pub fn new(domains: Vec<DomainState>) -> Self {
+        let mut snapshot = Self {
+            version: SNAPSHOT_VERSION,
+            domains,
+        };
+        snapshot.sort();
+        snapshot
+    }
+
+    /// Finds a domain by name.
+    pub fn domain(&self, name: &str) -> Option<&DomainState> {
+        self.domains
+            .iter()
+            .find(|d| d.domain.eq_ignore_ascii_case(name))
+    }
+
+    /// Fails if the snapshot was written by a newer, incompatible version.
+    pub fn check_version(&self) -> Result<()> {
+        if self.version == 0 || self.version > SNAPSHOT_VERSION {
+            return Err(Error::Snapshot(format!(
+                "unsupported version {} (expected {})",
+                self.version, SNAPSHOT_VERSION
+            )));
+        }
+        Ok(())
+    }
+
+    fn sort(&mut self) {
+        self.domains.sort_by(|a, b| a.domain.cmp(&b.domain));
+        for domain in &mut self.domains {
+            domain
+                .mailboxes
+                .sort_by(|a, b| a.mailbox.local_part.cmp(&b.mailbox.local_part));
+            for mailbox in &mut domain.mailboxes {
+                mailbox
+                    .identities
+                    .sort_by(|a, b| a.local_part.cmp(&b.local_part));
+                mailbox
+                    .forwardings
+                    .sort_by(|a, b| a.address.cmp(&b.address));
+            }
+            domain
+                .aliases
+                .sort_by(|a, b| a.local_part.cmp(&b.local_part));
+            domain.rewrites.sort_by(cmp_evaluation_order);
+        }
+    }
+}
+
+impl MigaduClient {
+    /// Exports every domain on the account.
+    pub async fn export(&self) -> Result<Snapshot> {
+        let domains: Vec<String> = self
+            .list_domains()
+            .await?
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+        self.export_domains(&domains).await
+    }
+
+    /// Exports the given domains.
+    pub async fn export_domains(&self, domains: &[String]) -> Result<Snapshot> {
+        let mut states = Vec::new();
+        for domain in domains {
+            states.push(self.fetch_domain_state(domain).await?);
+        }
+        Ok(Snapshot::new(states))
+    }
+}
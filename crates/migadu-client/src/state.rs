@@ -0,0 +1,89 @@
+//! Live configuration of a domain, as returned by the `list_*` endpoints.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::client::MigaduClient;
+use crate::error::Result;
+use crate::types::{Alias, Forwarding, Identity, Mailbox, Rewrite};
+
+/// Everything configured on a domain.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DomainState {
+    pub domain: String,
+    #[serde(default)]
+    pub mailboxes: Vec<MailboxState>,
+    #[serde(default)]
+    pub aliases: Vec<Alias>,
+    #[serde(default)]
+    pub rewrites: Vec<Rewrite>,
+}
+
+/// A mailbox together with its identities and forwardings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MailboxState {
+    pub mailbox: Mailbox,
+    #[serde(default)]
+    pub identities: Vec<Identity>,
+    #[serde(default)]
+    pub forwardings: Vec<Forwarding>,
+}
+
+impl DomainState {
+    /// Finds a mailbox by its local part.
+    pub fn mailbox(&self, local_part: &str) -> Option<&MailboxState> {
+        self.mailboxes
+            .iter()
+            .find(|m| m.mailbox.local_part.eq_ignore_ascii_case(local_part))
+    }
+}
+
+/// The kinds of resource managed through the API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceKind {
+    Mailbox,
+    Identity,
+    Forwarding,
+    Alias,
+    Rewrite,
+}
+
+impl fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ResourceKind::Mailbox => "mailbox",
+            ResourceKind::Identity => "identity",
+            ResourceKind::Forwarding => "forwarding",
+            ResourceKind::Alias => "alias",
+            ResourceKind::Rewrite => "rewrite",
+        })
+    }
+}
+
+impl MigaduClient {
+    /// Fetches the complete configuration of a domain.
+    ///
+    /// This makes one request per resource type, plus two per mailbox for its
+    /// identities and forwardings.
+    pub async fn fetch_domain_state(&self, domain: &str) -> Result<DomainState> {
+        let mut mailboxes = Vec::new();
+        for mailbox in self.list_mailboxes(domain).await? {
+            let identities = self.list_identities(domain, &mailbox.local_part).await?;
+            let forwardings = self.list_forwardings(domain, &mailbox.local_part).await?;
+            mailboxes.push(MailboxState {
+                mailbox,
+                identities,
+                forwardings,
+            });
+        }
+
+        Ok(DomainState {
+            domain: domain.to_string(),
+            mailboxes,
+            aliases: self.list_aliases(domain).await?,
+            rewrites: self.list_rewrites(domain).await?,
+        })
+    }
+}
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 /// An email alias that forwards to one or more destinations.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Alias {
     pub local_part: String,
     pub domain_name: String,
@@ -13,11 +14,14 @@ pub struct Alias {
 }
 
 /// Request body for creating a new alias.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CreateAlias {
+    /// Local part of the address (before @).
     pub local_part: String,
     /// Destinations as a comma-separated string (e.g., "one@domain.tld,two@domain.tld").
     pub destinations: String,
+    /// Only accept mail from the account's own domains.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_internal: Option<bool>,
 }
@@ -47,11 +51,13 @@ impl CreateAlias {
 }
 
 /// Request body for updating an existing alias.
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UpdateAlias {
     /// Destinations as a comma-separated string.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub destinations: Option<String>,
+    /// Only accept mail from the account's own domains.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_internal: Option<bool>,
 }
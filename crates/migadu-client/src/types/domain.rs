@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// A domain hosted on the account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Domain {
+    pub name: String,
+    pub description: Option<String>,
+    pub state: Option<String>,
+    #[serde(default, deserialize_with = "crate::types::nullable_bool")]
+    pub hosted_dns: bool,
+}
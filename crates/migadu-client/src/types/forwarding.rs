@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 /// A forwarding address associated with a mailbox.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Forwarding {
     pub address: String,
     pub blocked_at: Option<String>,
@@ -13,9 +14,19 @@ pub struct Forwarding {
     pub remove_upon_expiry: Option<bool>,
 }
 
+impl Forwarding {
+    /// Returns `true` if mail is forwarded: the forwarding is active, confirmed by its
+    /// recipient and not blocked.
+    pub fn delivers(&self) -> bool {
+        self.is_active && self.confirmed_at.is_some() && self.blocked_at.is_none()
+    }
+}
+
 /// Request body for creating a new forwarding address.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CreateForwarding {
+    /// Address to forward to.
     pub address: String,
 }
 
@@ -29,12 +40,30 @@ impl CreateForwarding {
 }
 
 /// Request body for updating an existing forwarding address.
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UpdateForwarding {
+    /// Enable or pause the forwarding.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_active: Option<bool>,
+    /// Date the forwarding expires (YYYY-MM-DD).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub expires_on: Option<String>,
+    /// Delete the forwarding when it expires.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub remove_upon_expiry: Option<bool>,
 }
+
+impl From<&Forwarding> for UpdateForwarding {
+    /// Copies the expiry settings of a forwarding.
+    ///
+    /// `is_active` is left out: a new forwarding only becomes active once its recipient
+    /// confirms it.
+    fn from(forwarding: &Forwarding) -> Self {
+        Self {
+            is_active: None,
+            expires_on: forwarding.expires_on.clone(),
+            remove_upon_expiry: forwarding.remove_upon_expiry,
+        }
+    }
+}
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 /// An identity associated with a mailbox.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Identity {
     pub local_part: String,
     pub domain_name: String,
@@ -24,20 +25,29 @@ pub struct Identity {
 }
 
 /// Request body for creating a new identity.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CreateIdentity {
+    /// Local part of the address (before @).
     pub local_part: String,
+    /// Display name.
     pub name: String,
+    /// Password, to log in as the identity.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub password: Option<String>,
+    /// Allow sending mail.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_send: Option<bool>,
+    /// Allow receiving mail.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_receive: Option<bool>,
+    /// Allow IMAP access.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_access_imap: Option<bool>,
+    /// Allow POP3 access.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_access_pop3: Option<bool>,
+    /// Allow ManageSieve access.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_access_managesieve: Option<bool>,
 }
@@ -59,26 +69,55 @@ impl CreateIdentity {
 }
 
 /// Request body for updating an existing identity.
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UpdateIdentity {
+    /// Display name.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// New password.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub password: Option<String>,
+    /// Allow sending mail.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_send: Option<bool>,
+    /// Allow receiving mail.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_receive: Option<bool>,
+    /// Allow IMAP access.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_access_imap: Option<bool>,
+    /// Allow POP3 access.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_access_pop3: Option<bool>,
+    /// Allow ManageSieve access.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_access_managesieve: Option<bool>,
+    /// Append a footer to outgoing mail.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub footer_active: Option<bool>,
+    /// Plain text footer.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub footer_plain_body: Option<String>,
+    /// HTML footer.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub footer_html_body: Option<String>,
 }
+
+impl From<&Identity> for UpdateIdentity {
+    /// Copies every setting of an identity, except its password which is never returned.
+    fn from(identity: &Identity) -> Self {
+        Self {
+            name: Some(identity.name.clone()),
+            password: None,
+            may_send: Some(identity.may_send),
+            may_receive: Some(identity.may_receive),
+            may_access_imap: Some(identity.may_access_imap),
+            may_access_pop3: Some(identity.may_access_pop3),
+            may_access_managesieve: Some(identity.may_access_managesieve),
+            footer_active: Some(identity.footer_active),
+            footer_plain_body: identity.footer_plain_body.clone(),
+            footer_html_body: identity.footer_html_body.clone(),
+        }
+    }
+}
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 /// A mailbox in the Migadu system.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Mailbox {
     pub local_part: String,
     pub domain_name: String,
@@ -43,13 +44,19 @@ pub struct Mailbox {
 }
 
 /// Request body for creating a new mailbox.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CreateMailbox {
+    /// Local part of the address (before @).
     pub local_part: String,
+    /// Display name.
     pub name: String,
+    /// Initial password.
     pub password: String,
+    /// Address for password recovery.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub password_recovery_email: Option<String>,
+    /// Only accept mail from the account's own domains.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_internal: Option<bool>,
 }
@@ -72,48 +79,99 @@ impl CreateMailbox {
 }
 
 /// Request body for updating an existing mailbox.
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UpdateMailbox {
+    /// Display name.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// New password.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub password: Option<String>,
+    /// Address for password recovery.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub password_recovery_email: Option<String>,
+    /// Only accept mail from the account's own domains.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_internal: Option<bool>,
+    /// Allow sending mail.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_send: Option<bool>,
+    /// Allow receiving mail.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_receive: Option<bool>,
+    /// Allow IMAP access.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_access_imap: Option<bool>,
+    /// Allow POP3 access.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_access_pop3: Option<bool>,
+    /// Allow ManageSieve access.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub may_access_managesieve: Option<bool>,
+    /// What to do with spam (e.g. "folder", "tag" or "reject").
     #[serde(skip_serializing_if = "Option::is_none")]
     pub spam_action: Option<String>,
+    /// How aggressively to filter spam (e.g. "default" or "strict").
     #[serde(skip_serializing_if = "Option::is_none")]
     pub spam_aggressiveness: Option<String>,
+    /// Senders whose mail is rejected.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sender_denylist: Option<Vec<String>>,
+    /// Senders whose mail is never treated as spam.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sender_allowlist: Option<Vec<String>>,
+    /// Recipient addresses whose mail is rejected.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub recipient_denylist: Option<Vec<String>>,
+    /// Enable the autoresponder.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub autorespond_active: Option<bool>,
+    /// Autoresponder subject.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub autorespond_subject: Option<String>,
+    /// Autoresponder body.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub autorespond_body: Option<String>,
+    /// Date the autoresponder stops (YYYY-MM-DD).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub autorespond_expires_on: Option<String>,
+    /// Append a footer to outgoing mail.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub footer_active: Option<bool>,
+    /// Plain text footer.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub footer_plain_body: Option<String>,
+    /// HTML footer.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub footer_html_body: Option<String>,
 }
+
+impl From<&Mailbox> for UpdateMailbox {
+    /// Copies every setting of a mailbox, except its password which is never returned.
+    fn from(mailbox: &Mailbox) -> Self {
+        Self {
+            name: Some(mailbox.name.clone()),
+            password: None,
+            password_recovery_email: mailbox.password_recovery_email.clone(),
+            is_internal: Some(mailbox.is_internal),
+            may_send: Some(mailbox.may_send),
+            may_receive: Some(mailbox.may_receive),
+            may_access_imap: Some(mailbox.may_access_imap),
+            may_access_pop3: Some(mailbox.may_access_pop3),
+            may_access_managesieve: Some(mailbox.may_access_managesieve),
+            spam_action: mailbox.spam_action.clone(),
+            spam_aggressiveness: mailbox.spam_aggressiveness.clone(),
+            sender_denylist: Some(mailbox.sender_denylist.clone()),
+            sender_allowlist: Some(mailbox.sender_allowlist.clone()),
+            recipient_denylist: Some(mailbox.recipient_denylist.clone()),
+            autorespond_active: mailbox.autorespond_active,
+            autorespond_subject: mailbox.autorespond_subject.clone(),
+            autorespond_body: mailbox.autorespond_body.clone(),
+            autorespond_expires_on: mailbox.autorespond_expires_on.clone(),
+            footer_active: Some(mailbox.footer_active),
+            footer_plain_body: mailbox.footer_plain_body.clone(),
+            footer_html_body: mailbox.footer_html_body.clone(),
+        }
+    }
+}
@@ -1,12 +1,14 @@
 use serde::{Deserialize, Deserializer};
 
 mod alias;
+mod domain;
 mod forwarding;
 mod identity;
 mod mailbox;
 mod rewrite;
 
 pub use alias::*;
+pub use domain::*;
 pub use forwarding::*;
 pub use identity::*;
 pub use mailbox::*;
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 /// A rewrite rule that matches patterns and forwards to destinations.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Rewrite {
     pub name: String,
     #[serde(default)]
@@ -12,8 +13,41 @@ pub struct Rewrite {
     pub destinations: Vec<String>,
 }
 
+impl Rewrite {
+    /// Returns `true` if `local_part` matches this rule's `local_part_rule`.
+    ///
+    /// `*` matches any run of characters, including none; everything else matches
+    /// itself, ignoring ASCII case.
+    pub fn matches(&self, local_part: &str) -> bool {
+        wildcard_match(&self.local_part_rule, local_part)
+    }
+}
+
+pub(crate) fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let text = text.to_ascii_lowercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard: the whole text must match.
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
 /// Request body for creating a new rewrite rule.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct CreateRewrite {
     /// A slug identifier for the rewrite rule.
     pub name: String,
@@ -21,6 +55,7 @@ pub struct CreateRewrite {
     pub local_part_rule: String,
     /// Destinations as a comma-separated string.
     pub destinations: String,
+    /// Position in which rules are tried, lowest first.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub order_num: Option<i32>,
 }
@@ -46,7 +81,10 @@ impl CreateRewrite {
         local_part_rule: impl Into<String>,
         destinations: impl IntoIterator<Item = impl AsRef<str>>,
     ) -> Self {
-        let destinations: Vec<_> = destinations.into_iter().map(|s| s.as_ref().to_string()).collect();
+        let destinations: Vec<_> = destinations
+            .into_iter()
+            .map(|s| s.as_ref().to_string())
+            .collect();
         Self {
             name: name.into(),
             local_part_rule: local_part_rule.into(),
@@ -56,15 +94,39 @@ impl CreateRewrite {
     }
 }
 
+/// The rule a creation request would add, for trying it out locally.
+impl From<&CreateRewrite> for Rewrite {
+    fn from(create: &CreateRewrite) -> Self {
+        Rewrite {
+            name: create.name.clone(),
+            domain_name: None,
+            local_part_rule: create.local_part_rule.clone(),
+            order_num: create.order_num,
+            destinations: create
+                .destinations
+                .split(',')
+                .map(str::trim)
+                .filter(|d| !d.is_empty())
+                .map(String::from)
+                .collect(),
+        }
+    }
+}
+
 /// Request body for updating an existing rewrite rule.
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UpdateRewrite {
+    /// New slug identifier for the rewrite rule.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// Pattern to match (e.g., "support-*" or "sales-*").
     #[serde(skip_serializing_if = "Option::is_none")]
     pub local_part_rule: Option<String>,
+    /// Destinations as a comma-separated string.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub destinations: Option<String>,
+    /// Position in which rules are tried, lowest first.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub order_num: Option<i32>,
 }
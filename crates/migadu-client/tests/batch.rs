@@ -0,0 +1,192 @@
+//! Tests for executing batches of operations.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use migadu_client::batch::{BatchOptions, BatchResult};
+use migadu_client::{Error, MigaduClient, Operation};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// A client whose requests all fail, since nothing listens on the discard port.
+fn unreachable_client() -> MigaduClient {
+    MigaduClient::with_base_url("user@example.com", "api-key", "http://127.0.0.1:9")
+}
+
+/// An HTTP response closing the connection, with `headers` ending in `\r\n`.
+fn response(status: &str, headers: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        headers,
+        body.len(),
+        body
+    )
+}
+
+fn rate_limited() -> String {
+    response("429 Too Many Requests", "", "")
+}
+
+fn deleted() -> String {
+    let alias =
+        r#"{"local_part":"alias0","domain_name":"example.com","address":"alias0@example.com"}"#;
+    response("200 OK", "Content-Type: application/json\r\n", alias)
+}
+
+/// A client for a local server that sends `responses` in turn, one per request, and
+/// the number of requests the server has received.
+async fn scripted_client(responses: Vec<String>) -> (MigaduClient, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    tokio::spawn(async move {
+        for response in responses {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            counter.fetch_add(1, Ordering::SeqCst);
+            stream.write_all(response.as_bytes()).await.unwrap();
+            stream.shutdown().await.unwrap();
+        }
+    });
+    let client = MigaduClient::with_base_url("user@example.com", "api-key", base_url);
+    (client, requests)
+}
+
+fn deletes(count: usize) -> Vec<Operation> {
+    (0..count)
+        .map(|i| Operation::DeleteAlias {
+            domain: "example.com".into(),
+            local_part: format!("alias{}", i),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_batch_reports_every_operation_in_order() {
+    let operations = deletes(5);
+    let mut finished = Vec::new();
+    let report = unreachable_client()
+        .execute_batch(&operations, BatchOptions::default(), |progress| {
+            assert_eq!(progress.total, 5);
+            assert!(progress.result.is_err());
+            finished.push(progress.index);
+        })
+        .await;
+
+    finished.sort();
+    assert_eq!(finished, [0, 1, 2, 3, 4]);
+    assert_eq!(report.results.len(), 5);
+    assert_eq!(report.failed(), 5);
+    assert!(!report.is_success());
+}
+
+#[tokio::test]
+async fn test_stop_on_error_skips_remaining_operations() {
+    let operations = deletes(4);
+    let options = BatchOptions {
+        concurrency: 1,
+        stop_on_error: true,
+        ..Default::default()
+    };
+    let mut calls = 0;
+    let report = unreachable_client()
+        .execute_batch(&operations, options, |_| calls += 1)
+        .await;
+
+    assert_eq!(calls, 1);
+    assert!(matches!(report.results[0], BatchResult::Failed(_)));
+    assert_eq!(report.skipped(), 3);
+}
+
+#[tokio::test]
+async fn test_rate_limited_operation_is_retried() {
+    let (client, requests) = scripted_client(vec![rate_limited(), deleted()]).await;
+    let options = BatchOptions {
+        rate_limit_delay: Duration::from_millis(10),
+        ..Default::default()
+    };
+    let report = client.execute_batch(&deletes(1), options, |_| {}).await;
+
+    assert!(report.is_success(), "{:?}", report);
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_rate_limited_operation_fails_once_retries_run_out() {
+    let (client, requests) = scripted_client(vec![rate_limited(); 3]).await;
+    let options = BatchOptions {
+        rate_limit_retries: 2,
+        rate_limit_delay: Duration::from_millis(10),
+        ..Default::default()
+    };
+    let report = client.execute_batch(&deletes(1), options, |_| {}).await;
+
+    assert!(
+        matches!(
+            report.results[0],
+            BatchResult::Failed(Error::Api { status: 429, .. })
+        ),
+        "{:?}",
+        report
+    );
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_retry_after_header_overrides_the_delay() {
+    let rate_limited = response("429 Too Many Requests", "Retry-After: 0\r\n", "");
+    let (client, requests) = scripted_client(vec![rate_limited, deleted()]).await;
+    let options = BatchOptions {
+        rate_limit_delay: Duration::from_secs(3600),
+        ..Default::default()
+    };
+    let operations = deletes(1);
+    let batch = client.execute_batch(&operations, options, |_| {});
+    let report = tokio::time::timeout(Duration::from_secs(10), batch)
+        .await
+        .expect("Retry-After was ignored");
+
+    assert!(report.is_success(), "{:?}", report);
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_waits_are_capped() {
+    // A day-long Retry-After, then enough retries for the doubled delay to overflow.
+    let rate_limited = response("429 Too Many Requests", "Retry-After: 86400\r\n", "");
+    let mut responses = vec![rate_limited; 2];
+    responses.extend(vec![self::rate_limited(); 60]);
+    let (client, requests) = scripted_client(responses).await;
+    let options = BatchOptions {
+        rate_limit_retries: 61,
+        rate_limit_delay: Duration::from_secs(3600),
+        max_rate_limit_delay: Duration::from_millis(1),
+        ..Default::default()
+    };
+    let operations = deletes(1);
+    let batch = client.execute_batch(&operations, options, |_| {});
+    let report = tokio::time::timeout(Duration::from_secs(10), batch)
+        .await
+        .expect("the wait was not capped");
+
+    assert!(
+        matches!(
+            report.results[0],
+            BatchResult::Failed(Error::Api { status: 429, .. })
+        ),
+        "{:?}",
+        report
+    );
+    assert_eq!(requests.load(Ordering::SeqCst), 62);
+}
@@ -0,0 +1,90 @@
+//! Tests for the configuration file.
+
+use std::path::PathBuf;
+
+use migadu_client::config::{self, Config, Profile};
+
+fn temp_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("migadu-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+#[test]
+fn test_missing_config_file_is_empty() {
+    let config = Config::load(&temp_path("does-not-exist.toml")).unwrap();
+    assert!(config.profiles.is_empty());
+    assert_eq!(config.default_profile_name(), "default");
+}
+
+#[test]
+fn test_config_roundtrip_and_profile_selection() {
+    let path = temp_path("config.toml");
+    let mut config = Config {
+        default_profile: Some("work".to_string()),
+        ..Config::default()
+    };
+    config.profiles.insert(
+        "work".to_string(),
+        Profile {
+            email: Some("admin@example.com".to_string()),
+            api_key_file: Some("/run/secrets/migadu".to_string()),
+            domain: Some("example.com".to_string()),
+            ..Profile::default()
+        },
+    );
+    config.save(&path).unwrap();
+
+    let config = Config::load(&path).unwrap();
+    let profile = config.profile(None).unwrap();
+    assert_eq!(profile.domain.as_deref(), Some("example.com"));
+    assert_eq!(profile.api_key_source(), "file");
+
+    let error = config.profile(Some("home")).unwrap_err().to_string();
+    assert_eq!(error, "Profile 'home' not found in config file");
+}
+
+#[test]
+fn test_invalid_config_file_is_an_error() {
+    let path = temp_path("invalid.toml");
+    std::fs::write(&path, "profiles = 3").unwrap();
+
+    let error = Config::load(&path).unwrap_err().to_string();
+    assert!(
+        error.starts_with("Invalid config file"),
+        "Unexpected error: {}",
+        error
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_saved_config_is_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = temp_path("private.toml");
+    std::fs::write(&path, "").unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+    Config::default().save(&path).unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
+
+#[test]
+fn test_private_file_is_only_created_once() {
+    let path = temp_path("secrets.txt");
+    let _ = std::fs::remove_file(&path);
+
+    config::create_private(&path, "first").unwrap();
+    let error = config::create_private(&path, "second").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "first");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
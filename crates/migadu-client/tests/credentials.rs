@@ -0,0 +1,123 @@
+//! Tests for the API key providers.
+//!
+//! These tests run offline and use temporary files instead of the OS keyring.
+
+use std::path::PathBuf;
+
+use migadu_client::credentials::{
+    CredentialProvider, FileSecretStore, KeyCommand, KeyFile, Keyring, ProviderChain, SecretStore,
+    StaticKey,
+};
+
+fn temp_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("migadu-credentials-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+#[test]
+fn test_key_file_trims_whitespace() {
+    let path = temp_path("key-file");
+    std::fs::write(&path, "secret-key\n").unwrap();
+
+    let key = KeyFile::new(&path).api_key().unwrap();
+    assert_eq!(key.as_deref(), Some("secret-key"));
+}
+
+#[test]
+fn test_missing_key_file_is_an_error() {
+    let result = KeyFile::new(temp_path("does-not-exist")).api_key();
+    assert!(result.is_err(), "Expected an error, got {:?}", result);
+}
+
+#[test]
+fn test_empty_key_file_is_an_error() {
+    let path = temp_path("empty-key-file");
+    std::fs::write(&path, " \n").unwrap();
+
+    let message = KeyFile::new(&path).api_key().unwrap_err().to_string();
+    assert!(
+        message.contains("is empty"),
+        "Unexpected error: {}",
+        message
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_key_command_uses_first_line() {
+    let key = KeyCommand::new("printf 'from-command\\nlogin: admin\\n'")
+        .api_key()
+        .unwrap();
+    assert_eq!(key.as_deref(), Some("from-command"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_failing_key_command_is_an_error() {
+    let result = KeyCommand::new("echo nope >&2; exit 3").api_key();
+    let message = result.unwrap_err().to_string();
+    assert!(message.contains("nope"), "Unexpected error: {}", message);
+}
+
+#[test]
+fn test_file_secret_store_roundtrip() {
+    let store = FileSecretStore::new(temp_path("keyring.json"));
+
+    store
+        .set("migadu", "admin@example.com", "stored-key")
+        .unwrap();
+    assert_eq!(
+        store.get("migadu", "admin@example.com").unwrap().as_deref(),
+        Some("stored-key")
+    );
+    assert_eq!(store.get("migadu", "other@example.com").unwrap(), None);
+
+    let keyring = Keyring::with_store(Box::new(store.clone()), "migadu", "admin@example.com");
+    assert_eq!(keyring.api_key().unwrap().as_deref(), Some("stored-key"));
+
+    store.delete("migadu", "admin@example.com").unwrap();
+    assert_eq!(keyring.api_key().unwrap(), None);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(store.path())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
+
+#[test]
+fn test_chain_returns_first_key_found() {
+    let store = FileSecretStore::new(temp_path("empty-keyring.json"));
+    let chain = ProviderChain::new()
+        .with(Keyring::with_store(Box::new(store), "migadu", "nobody"))
+        .with(StaticKey("fallback".to_string()))
+        .with(StaticKey("unused".to_string()));
+
+    assert_eq!(chain.require().unwrap(), "fallback");
+    assert!(ProviderChain::new().require().is_err());
+}
+
+#[test]
+fn test_deferred_provider_is_only_built_when_reached() {
+    let unavailable = || -> migadu_client::Result<StaticKey> {
+        Err(migadu_client::Error::Credentials(
+            "No keyring available".to_string(),
+        ))
+    };
+
+    let chain = ProviderChain::new()
+        .with(StaticKey("first".to_string()))
+        .with_deferred(unavailable);
+    assert_eq!(chain.require().unwrap(), "first");
+
+    let chain = ProviderChain::new().with_deferred(unavailable);
+    assert!(matches!(
+        chain.require(),
+        Err(migadu_client::Error::Credentials(message)) if message == "No keyring available"
+    ));
+}
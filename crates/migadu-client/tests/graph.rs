@@ -0,0 +1,147 @@
+//! Tests for the delivery graph.
+
+use migadu_client::graph::{EdgeKind, Graph, NodeKind};
+use migadu_client::state::DomainState;
+use serde_json::json;
+
+fn domains() -> Vec<DomainState> {
+    serde_json::from_value(json!([{
+        "domain": "example.com",
+        "mailboxes": [{
+            "mailbox": {
+                "local_part": "alice",
+                "domain_name": "example.com",
+                "address": "alice@example.com",
+                "name": "Alice"
+            },
+            "identities": [{
+                "local_part": "ali",
+                "domain_name": "example.com",
+                "address": "ali@example.com",
+                "name": "Ali"
+            }],
+            "forwardings": [
+                { "address": "alice@home.example", "is_active": true },
+                { "address": "old@home.example", "is_active": false }
+            ]
+        }, {
+            "mailbox": {
+                "local_part": "bob",
+                "domain_name": "example.com",
+                "address": "bob@example.com",
+                "name": "Bob"
+            }
+        }],
+        "aliases": [
+            { "local_part": "a", "domain_name": "example.com", "address": "a@example.com", "destinations": ["b@example.com"] },
+            { "local_part": "b", "domain_name": "example.com", "address": "b@example.com", "destinations": ["A@example.com", "gone@example.com"] },
+            { "local_part": "info", "domain_name": "example.com", "address": "info@example.com", "destinations": ["sales@example.org"] }
+        ],
+        "rewrites": [
+            { "name": "team", "local_part_rule": "team-*", "order_num": 1, "destinations": ["alice@example.com"] }
+        ]
+    }, {
+        "domain": "example.org",
+        "mailboxes": [{
+            "mailbox": {
+                "local_part": "sales",
+                "domain_name": "example.org",
+                "address": "sales@example.org",
+                "name": "Sales"
+            }
+        }]
+    }]))
+    .unwrap()
+}
+
+#[test]
+fn test_graph_marks_external_missing_cycles_and_orphans() {
+    let graph = Graph::build(&domains());
+    let node = |id: &str| graph.nodes.iter().find(|n| n.id == id).unwrap();
+
+    assert_eq!(node("alice@home.example").kind, NodeKind::External);
+    assert_eq!(node("gone@example.com").kind, NodeKind::Missing);
+    // The other domain is part of the graph, so its mailbox is not external.
+    assert_eq!(node("sales@example.org").kind, NodeKind::Mailbox);
+    assert_eq!(node("rewrite:team@example.com").kind, NodeKind::Rewrite);
+
+    let cyclic: Vec<_> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.in_cycle)
+        .map(|n| n.id.as_str())
+        .collect();
+    assert_eq!(cyclic, ["a@example.com", "b@example.com"]);
+    let cycle_edges = graph.edges.iter().filter(|e| e.in_cycle).count();
+    assert_eq!(cycle_edges, 2);
+    // Destinations are matched case-insensitively but name the node itself.
+    assert!(graph
+        .edges
+        .iter()
+        .any(|e| e.from == "b@example.com" && e.to == "a@example.com"));
+
+    let orphans: Vec<_> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.orphan)
+        .map(|n| n.id.as_str())
+        .collect();
+    assert_eq!(orphans, ["bob@example.com"]);
+
+    let inactive: Vec<_> = graph.edges.iter().filter(|e| !e.active).collect();
+    assert_eq!(inactive.len(), 1);
+    assert_eq!(inactive[0].kind, EdgeKind::Forwarding);
+    assert_eq!(inactive[0].to, "old@home.example");
+}
+
+#[test]
+fn test_graph_follows_destinations_through_rewrites() {
+    let domains: Vec<DomainState> = serde_json::from_value(json!([{
+        "domain": "example.com",
+        "aliases": [
+            { "local_part": "all", "domain_name": "example.com", "address": "all@example.com", "destinations": ["list-all@example.com"] }
+        ],
+        "rewrites": [
+            { "name": "lists", "local_part_rule": "list-*", "order_num": 1, "destinations": ["all@example.com"] },
+            { "name": "catchall", "local_part_rule": "*", "order_num": 2, "destinations": ["all@example.com"] }
+        ]
+    }]))
+    .unwrap();
+    let graph = Graph::build(&domains);
+    let node = |id: &str| graph.nodes.iter().find(|n| n.id == id);
+
+    // The first matching rule receives the destination, so no node is missing.
+    assert!(node("list-all@example.com").is_none());
+    assert!(graph
+        .edges
+        .iter()
+        .any(|e| e.from == "all@example.com" && e.to == "rewrite:lists@example.com"));
+
+    let cyclic: Vec<_> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.in_cycle)
+        .map(|n| n.id.as_str())
+        .collect();
+    assert_eq!(cyclic, ["all@example.com", "rewrite:lists@example.com"]);
+    assert!(!node("rewrite:catchall@example.com").unwrap().in_cycle);
+}
+
+#[test]
+fn test_graph_renders_dot_and_mermaid() {
+    let graph = Graph::build(&domains());
+
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph migadu {"));
+    assert!(dot.contains("\"ali@example.com\" -> \"alice@example.com\" [label=\"identity of\"];"));
+    assert!(dot.contains(
+        "\"alice@example.com\" -> \"old@home.example\" [label=\"forwards\", style=dashed];"
+    ));
+    assert!(dot.contains("\"a@example.com\" -> \"b@example.com\" [color=red, penwidth=2];"));
+
+    let mermaid = graph.to_mermaid();
+    assert!(mermaid.starts_with("flowchart LR\n"));
+    assert!(mermaid.contains("{{\"team-*@example.com (rewrite team, order 1)<br/>rewrite\"}}"));
+    assert!(mermaid.contains("-. forwards .->"));
+    assert!(mermaid.contains("linkStyle "));
+}
@@ -0,0 +1,180 @@
+//! Tests for planning imports from a snapshot and clones of a domain.
+
+use migadu_client::clone::{plan_clone, CloneOptions};
+use migadu_client::import::{plan_import, ConflictStrategy};
+use migadu_client::plan::ChangeAction;
+use migadu_client::state::{DomainState, ResourceKind};
+use serde_json::json;
+
+fn mailbox(local_part: &str, domain: &str) -> serde_json::Value {
+    json!({
+        "mailbox": {
+            "local_part": local_part,
+            "domain_name": domain,
+            "address": format!("{}@{}", local_part, domain),
+            "name": local_part.to_uppercase(),
+            "may_send": true,
+            "may_receive": true
+        }
+    })
+}
+
+fn source() -> DomainState {
+    serde_json::from_value(json!({
+        "domain": "prod.example",
+        "mailboxes": [mailbox("alice", "prod.example"), mailbox("bob", "prod.example")],
+        "rewrites": [{
+            "name": "support",
+            "local_part_rule": "support-*",
+            "order_num": 2,
+            "destinations": ["alice@prod.example"]
+        }]
+    }))
+    .unwrap()
+}
+
+fn target() -> DomainState {
+    let mut alice = mailbox("alice", "staging.example");
+    alice["mailbox"]["name"] = json!("Alice (staging)");
+    serde_json::from_value(json!({
+        "domain": "staging.example",
+        "mailboxes": [alice, mailbox("carol", "staging.example")]
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_conflicts_fail_by_default() {
+    let result = plan_import(&source(), &target(), ConflictStrategy::default(), |_| {
+        Some("secret".to_string())
+    });
+    let message = result.unwrap_err().to_string();
+    assert!(
+        message.contains("alice@staging.example"),
+        "Unexpected error: {}",
+        message
+    );
+}
+
+#[test]
+fn test_skip_creates_only_missing_resources() {
+    let mut asked = Vec::new();
+    let import = plan_import(&source(), &target(), ConflictStrategy::Skip, |mailbox| {
+        asked.push(mailbox.local_part.clone());
+        Some("secret".to_string())
+    })
+    .unwrap();
+
+    assert_eq!(asked, ["bob"]);
+    assert_eq!(import.skipped.len(), 1);
+    let created: Vec<_> = import
+        .plan
+        .changes
+        .iter()
+        .filter(|c| c.action == ChangeAction::Create)
+        .map(|c| (c.resource, c.id.as_str()))
+        .collect();
+    assert_eq!(
+        created,
+        [
+            (ResourceKind::Mailbox, "bob@staging.example"),
+            (ResourceKind::Rewrite, "support"),
+        ]
+    );
+    assert!(import
+        .plan
+        .changes
+        .iter()
+        .all(|c| c.id != "alice@staging.example"));
+    let rewrite = import.plan.changes.last().unwrap();
+    assert_eq!(rewrite.request.body.as_ref().unwrap()["order_num"], 2);
+}
+
+#[test]
+fn test_rewrite_conflicts_ignore_case() {
+    let target: DomainState = serde_json::from_value(json!({
+        "domain": "staging.example",
+        "rewrites": [{
+            "name": "Support",
+            "local_part_rule": "help-*",
+            "destinations": ["alice@staging.example"]
+        }]
+    }))
+    .unwrap();
+    let import = plan_import(&source(), &target, ConflictStrategy::Skip, |_| {
+        Some("secret".to_string())
+    })
+    .unwrap();
+
+    assert_eq!(import.skipped.len(), 1);
+    assert_eq!(import.skipped[0].id, "Support");
+    assert!(import
+        .plan
+        .changes
+        .iter()
+        .all(|c| c.resource != ResourceKind::Rewrite));
+}
+
+#[test]
+fn test_overwrite_updates_but_never_deletes() {
+    let import = plan_import(&source(), &target(), ConflictStrategy::Overwrite, |_| {
+        Some("secret".to_string())
+    })
+    .unwrap();
+
+    let alice = import
+        .plan
+        .changes
+        .iter()
+        .find(|c| c.id == "alice@staging.example")
+        .unwrap();
+    assert_eq!(alice.action, ChangeAction::Update);
+    assert_eq!(alice.request.body, Some(json!({ "name": "ALICE" })));
+    assert_eq!(import.plan.count(ChangeAction::Delete), 0);
+}
+
+#[test]
+fn test_clone_rewrites_destinations_and_leaves_forwarding() {
+    let target: DomainState =
+        serde_json::from_value(json!({ "domain": "staging.example" })).unwrap();
+    let options = CloneOptions {
+        rewrite_destinations: true,
+        leave_forwarding: true,
+        ..Default::default()
+    };
+    let import = plan_clone(&source(), &target, options, |_| Some("secret".to_string())).unwrap();
+
+    let rewrite = import
+        .plan
+        .changes
+        .iter()
+        .find(|c| c.resource == ResourceKind::Rewrite)
+        .unwrap();
+    assert_eq!(
+        rewrite.request.body.as_ref().unwrap()["destinations"],
+        "alice@staging.example"
+    );
+
+    let forwardings: Vec<_> = import
+        .plan
+        .changes
+        .iter()
+        .filter(|c| c.domain == "prod.example")
+        .map(|c| (c.resource, c.id.as_str(), c.mailbox.as_deref()))
+        .collect();
+    assert_eq!(
+        forwardings,
+        [
+            (
+                ResourceKind::Forwarding,
+                "alice@staging.example",
+                Some("alice@prod.example")
+            ),
+            (
+                ResourceKind::Forwarding,
+                "bob@staging.example",
+                Some("bob@prod.example")
+            ),
+        ]
+    );
+}
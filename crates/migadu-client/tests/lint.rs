@@ -0,0 +1,135 @@
+//! Tests for linting domain configuration.
+
+use migadu_client::lint::{lint, Level, LintConfig, LintRule};
+use migadu_client::state::DomainState;
+use serde_json::json;
+
+fn domain() -> DomainState {
+    serde_json::from_value(json!({
+        "domain": "example.com",
+        "mailboxes": [{
+            "mailbox": {
+                "local_part": "alice",
+                "domain_name": "example.com",
+                "address": "alice@example.com",
+                "name": "Alice",
+                "may_receive": true,
+                "password_recovery_email": "alice@home.example",
+                "autorespond_active": true,
+                "autorespond_expires_on": "2024-01-31"
+            },
+            "identities": [{
+                "local_part": "old",
+                "domain_name": "example.com",
+                "address": "old@example.com",
+                "name": "Old",
+                "may_send": false,
+                "may_receive": false
+            }],
+            "forwardings": [
+                { "address": "a@home.example", "is_active": true, "confirmed_at": "2024-01-01" },
+                { "address": "b@home.example", "is_active": false },
+                { "address": "c@home.example", "is_active": false, "blocked_at": "2024-02-01" }
+            ]
+        }, {
+            "mailbox": {
+                "local_part": "bob",
+                "domain_name": "example.com",
+                "address": "bob@example.com",
+                "name": "Bob",
+                "may_receive": true
+            }
+        }],
+        "aliases": [
+            { "local_part": "a", "domain_name": "example.com", "address": "a@example.com", "destinations": ["b@example.com"] },
+            { "local_part": "b", "domain_name": "example.com", "address": "b@example.com", "destinations": ["a@example.com", "gone@example.com"] }
+        ],
+        "rewrites": [
+            { "name": "all", "local_part_rule": "team-*", "order_num": 1, "destinations": ["alice@example.com"] },
+            { "name": "sales", "local_part_rule": "team-sales", "order_num": 2, "destinations": ["bob@example.com"] }
+        ]
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_every_rule_reports_its_problem() {
+    let findings = lint(&[domain()], &LintConfig::default(), "2024-06-01");
+    let found: Vec<_> = findings
+        .iter()
+        .map(|f| (f.rule, f.id.as_str(), f.level))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (LintRule::DanglingDestination, "b@example.com", Level::Error),
+            (LintRule::AliasLoop, "a@example.com", Level::Error),
+            (LintRule::AliasLoop, "b@example.com", Level::Error),
+            (LintRule::ShadowedRewrite, "sales", Level::Warning),
+            (
+                LintRule::MissingRecoveryEmail,
+                "bob@example.com",
+                Level::Note
+            ),
+            (
+                LintRule::ExpiredAutoresponder,
+                "alice@example.com",
+                Level::Warning
+            ),
+            (
+                LintRule::InactiveForwarding,
+                "b@home.example",
+                Level::Warning
+            ),
+            (
+                LintRule::InactiveForwarding,
+                "c@home.example",
+                Level::Warning
+            ),
+            (
+                LintRule::UnusableIdentity,
+                "old@example.com",
+                Level::Warning
+            ),
+        ]
+    );
+    assert!(findings[1]
+        .message
+        .contains("a@example.com -> b@example.com -> a@example.com"));
+}
+
+#[test]
+fn test_unconfirmed_forwarding_is_reported_even_if_active() {
+    let mut domain = domain();
+    domain.mailboxes[0].forwardings = serde_json::from_value(json!([
+        { "address": "d@home.example", "is_active": true }
+    ]))
+    .unwrap();
+    let findings = lint(&[domain], &LintConfig::default(), "2024-06-01");
+    let forwardings: Vec<_> = findings
+        .iter()
+        .filter(|f| f.rule == LintRule::InactiveForwarding)
+        .collect();
+    assert_eq!(forwardings.len(), 1);
+    assert_eq!(forwardings[0].id, "d@home.example");
+    assert!(forwardings[0].message.ends_with("is awaiting confirmation"));
+}
+
+#[test]
+fn test_config_changes_levels_and_disables_rules() {
+    let config: LintConfig = serde_json::from_value(json!({
+        "rules": { "alias-loop": "off", "missing-recovery-email": "error" }
+    }))
+    .unwrap();
+    let findings = lint(&[domain()], &config, "2024-01-01");
+
+    assert!(findings.iter().all(|f| f.rule != LintRule::AliasLoop));
+    assert!(findings
+        .iter()
+        .all(|f| f.rule != LintRule::ExpiredAutoresponder));
+    let recovery = findings
+        .iter()
+        .find(|f| f.rule == LintRule::MissingRecoveryEmail)
+        .unwrap();
+    assert_eq!(recovery.level, Level::Error);
+}
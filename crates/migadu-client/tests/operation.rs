@@ -0,0 +1,102 @@
+//! Tests for describing operations as HTTP requests without sending them.
+
+use migadu_client::{CreateMailbox, Error, HttpMethod, MigaduClient, Operation, UpdateAlias, MASK};
+
+#[test]
+fn test_create_request_masks_password() {
+    let operation = Operation::CreateMailbox {
+        domain: "example.com".into(),
+        mailbox: CreateMailbox::new("demo", "Demo User", "SecurePass123!"),
+    };
+
+    let request = operation.request().unwrap();
+    assert_eq!(request.method, HttpMethod::Post);
+    assert_eq!(request.path, "/domains/example.com/mailboxes");
+    assert_eq!(request.body.as_ref().unwrap()["password"], "SecurePass123!");
+
+    let masked = request.masked();
+    assert_eq!(masked.body.as_ref().unwrap()["password"], MASK);
+    assert_eq!(masked.body.as_ref().unwrap()["local_part"], "demo");
+}
+
+#[test]
+fn test_update_and_delete_requests() {
+    let update = Operation::UpdateAlias {
+        domain: "example.com".into(),
+        local_part: "support".into(),
+        update: UpdateAlias::with_destinations("a@example.com,b@example.com"),
+    };
+    let request = update.request().unwrap();
+    assert_eq!(request.method, HttpMethod::Put);
+    assert_eq!(request.path, "/domains/example.com/aliases/support");
+    assert_eq!(
+        request.body,
+        Some(serde_json::json!({"destinations": "a@example.com,b@example.com"}))
+    );
+
+    let delete = Operation::DeleteIdentity {
+        domain: "example.com".into(),
+        mailbox: "admin".into(),
+        local_part: "sales".into(),
+    };
+    let request = delete.request().unwrap();
+    assert_eq!(request.method, HttpMethod::Delete);
+    assert_eq!(
+        request.path,
+        "/domains/example.com/mailboxes/admin/identities/sales"
+    );
+    assert_eq!(request.body, None);
+
+    let client = MigaduClient::with_base_url("user@example.com", "key", "http://localhost/v1");
+    assert_eq!(
+        client.request_url(&request),
+        "http://localhost/v1/domains/example.com/mailboxes/admin/identities/sales"
+    );
+}
+
+#[test]
+fn test_path_values_are_encoded_as_one_segment() {
+    let delete = Operation::DeleteMailbox {
+        domain: "example.com".into(),
+        local_part: "../../other.org/mailboxes/bob".into(),
+    };
+    assert_eq!(
+        delete.request().unwrap().path,
+        "/domains/example.com/mailboxes/..%2F..%2Fother.org%2Fmailboxes%2Fbob"
+    );
+
+    let delete = Operation::DeleteForwarding {
+        domain: "example.com".into(),
+        mailbox: "alice".into(),
+        address: "alice+home@home.example?x".into(),
+    };
+    assert_eq!(
+        delete.request().unwrap().path,
+        "/domains/example.com/mailboxes/alice/forwardings/alice%2Bhome@home.example%3Fx"
+    );
+}
+
+#[test]
+fn test_dot_segments_and_empty_values_are_refused() {
+    for local_part in ["", ".", ".."] {
+        let delete = Operation::DeleteMailbox {
+            domain: "example.com".into(),
+            local_part: local_part.into(),
+        };
+        assert!(matches!(delete.request(), Err(Error::Path(_))));
+    }
+}
+
+#[tokio::test]
+async fn test_client_refuses_dot_segments_without_sending() {
+    // Nothing listens on the discard port, so a request that was sent would fail with
+    // `Error::Request` instead.
+    let client = MigaduClient::with_base_url("user@example.com", "key", "http://127.0.0.1:9");
+    let error = client
+        .delete_mailbox("example.com", "..")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::Path(_)));
+    let error = client.get_alias(".", "info").await.unwrap_err();
+    assert!(matches!(error, Error::Path(_)));
+}
@@ -0,0 +1,178 @@
+//! Tests for planning changes from a desired state.
+//!
+//! These tests run offline against hand-written live state.
+
+use migadu_client::plan::{plan_domain, ChangeAction, DesiredState};
+use migadu_client::state::{DomainState, ResourceKind};
+use migadu_client::{HttpMethod, MASK};
+use serde_json::json;
+
+fn live() -> DomainState {
+    serde_json::from_value(json!({
+        "domain": "example.com",
+        "mailboxes": [{
+            "mailbox": {
+                "local_part": "alice",
+                "domain_name": "example.com",
+                "address": "alice@example.com",
+                "name": "Alice",
+                "may_send": true,
+                "may_receive": true
+            }
+        }, {
+            "mailbox": {
+                "local_part": "old",
+                "domain_name": "example.com",
+                "address": "old@example.com",
+                "name": "Old"
+            }
+        }],
+        "aliases": [{
+            "local_part": "info",
+            "domain_name": "example.com",
+            "address": "info@example.com",
+            "destinations": ["alice@example.com", "bob@example.com"]
+        }],
+        "rewrites": [{
+            "name": "Support",
+            "local_part_rule": "support-*",
+            "order_num": 1,
+            "destinations": ["alice@example.com"]
+        }]
+    }))
+    .unwrap()
+}
+
+fn desired(value: serde_json::Value) -> DesiredState {
+    serde_json::from_value(json!({ "domains": { "example.com": value } })).unwrap()
+}
+
+#[test]
+fn test_matching_state_plans_nothing() {
+    let desired = desired(json!({
+        "mailboxes": [
+            { "local_part": "alice", "name": "Alice", "may_send": true, "password": "ignored" },
+            { "local_part": "old" }
+        ],
+        "aliases": [
+            { "local_part": "info", "destinations": ["BOB@example.com", "alice@example.com"] }
+        ]
+    }));
+
+    let changes = plan_domain("example.com", &desired.domains["example.com"], &live()).unwrap();
+    assert!(changes.is_empty(), "Unexpected changes: {:?}", changes);
+}
+
+#[test]
+fn test_plan_creates_updates_and_deletes() {
+    let desired = desired(json!({
+        "mailboxes": [
+            { "local_part": "alice", "name": "Alice Liddell", "may_send": true },
+            { "local_part": "carol", "name": "Carol", "password": "secret", "may_send": false }
+        ],
+        "aliases": []
+    }));
+
+    let changes = plan_domain("example.com", &desired.domains["example.com"], &live()).unwrap();
+    let summary: Vec<_> = changes
+        .iter()
+        .map(|c| (c.action, c.resource, c.id.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (
+                ChangeAction::Update,
+                ResourceKind::Mailbox,
+                "alice@example.com"
+            ),
+            (
+                ChangeAction::Create,
+                ResourceKind::Mailbox,
+                "carol@example.com"
+            ),
+            (
+                ChangeAction::Update,
+                ResourceKind::Mailbox,
+                "carol@example.com"
+            ),
+            (
+                ChangeAction::Delete,
+                ResourceKind::Alias,
+                "info@example.com"
+            ),
+            (
+                ChangeAction::Delete,
+                ResourceKind::Mailbox,
+                "old@example.com"
+            ),
+        ]
+    );
+
+    let update = &changes[0];
+    assert_eq!(update.request.method, HttpMethod::Put);
+    assert_eq!(
+        update.request.body,
+        Some(json!({ "name": "Alice Liddell" }))
+    );
+
+    let create = &changes[1];
+    assert_eq!(create.request.body.as_ref().unwrap()["password"], MASK);
+    assert!(create
+        .fields
+        .iter()
+        .any(|f| f.field == "password" && f.new == MASK));
+}
+
+#[test]
+fn test_new_mailbox_without_password_is_an_error() {
+    let desired = desired(json!({
+        "mailboxes": [{ "local_part": "dave", "name": "Dave" }]
+    }));
+
+    let result = plan_domain("example.com", &desired.domains["example.com"], &live());
+    assert!(result.is_err(), "Expected an error, got {:?}", result);
+}
+
+#[test]
+fn test_rewrites_are_matched_by_name_and_rule_regardless_of_case() {
+    let desired = desired(json!({
+        "rewrites": [{
+            "name": "support",
+            "local_part_rule": "Support-*",
+            "destinations": ["alice@example.com"]
+        }]
+    }));
+
+    let changes = plan_domain("example.com", &desired.domains["example.com"], &live()).unwrap();
+    assert!(changes.is_empty(), "Unexpected changes: {:?}", changes);
+}
+
+#[test]
+fn test_unknown_keys_are_refused() {
+    let parse = |value: serde_json::Value| {
+        serde_json::from_value::<DesiredState>(json!({ "domains": { "example.com": value } }))
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    };
+
+    let mailbox = parse(json!({ "mailboxes": [{ "local_part": "alice", "may_sendd": false }] }));
+    assert_eq!(mailbox.unwrap_err(), "unknown field `may_sendd`");
+
+    let identity = parse(json!({ "mailboxes": [{
+        "local_part": "alice",
+        "identities": [{ "local_part": "sales", "footer_actve": true }]
+    }] }));
+    assert_eq!(identity.unwrap_err(), "unknown field `footer_actve`");
+
+    let alias = parse(
+        json!({ "aliases": [{ "local_part": "info", "destinations": [], "internal": true }] }),
+    );
+    assert!(alias.unwrap_err().starts_with("unknown field `internal`"));
+
+    let domain = parse(json!({ "mailbox": [] }));
+    assert!(domain.unwrap_err().starts_with("unknown field `mailbox`"));
+
+    let unset = parse(json!({ "mailboxes": [{ "local_part": "alice", "may_send": null }] }));
+    assert_eq!(unset, Ok(()));
+}
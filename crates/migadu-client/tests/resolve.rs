@@ -0,0 +1,119 @@
+//! Tests for working out where mail to an address is delivered.
+
+use migadu_client::resolve::{resolve_address, Route};
+use migadu_client::state::DomainState;
+use migadu_client::Rewrite;
+use serde_json::json;
+
+fn domain() -> DomainState {
+    serde_json::from_value(json!({
+        "domain": "example.com",
+        "mailboxes": [{
+            "mailbox": {
+                "local_part": "alice",
+                "domain_name": "example.com",
+                "address": "alice@example.com",
+                "name": "Alice",
+                "may_receive": true
+            },
+            "identities": [{
+                "local_part": "sales",
+                "domain_name": "example.com",
+                "address": "sales@example.com",
+                "name": "Sales",
+                "may_receive": true
+            }],
+            "forwardings": [
+                { "address": "alice@home.example", "is_active": true, "confirmed_at": "2024-01-01" },
+                { "address": "paused@home.example", "is_active": false, "confirmed_at": "2024-01-01" },
+                { "address": "pending@home.example", "is_active": true },
+                {
+                    "address": "blocked@home.example",
+                    "is_active": true,
+                    "confirmed_at": "2024-01-01",
+                    "blocked_at": "2024-02-01"
+                }
+            ]
+        }],
+        "aliases": [
+            {
+                "local_part": "info",
+                "domain_name": "example.com",
+                "address": "info@example.com",
+                "destinations": ["team@example.com", "bob@partner.example"]
+            },
+            {
+                "local_part": "team",
+                "domain_name": "example.com",
+                "address": "team@example.com",
+                "destinations": ["sales@example.com", "info@example.com"]
+            }
+        ],
+        "rewrites": [
+            { "name": "late", "local_part_rule": "*", "order_num": 9, "destinations": ["alice@example.com"] },
+            { "name": "support", "local_part_rule": "support-*", "order_num": 1, "destinations": ["nobody@example.com"] }
+        ]
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_rewrite_wildcards() {
+    let rule = |pattern: &str| Rewrite {
+        name: "r".into(),
+        domain_name: None,
+        local_part_rule: pattern.into(),
+        order_num: None,
+        destinations: vec![],
+    };
+    assert!(rule("support-*").matches("Support-Billing"));
+    assert!(rule("support-*").matches("support-"));
+    assert!(!rule("support-*").matches("support"));
+    assert!(rule("*-team-*").matches("a-team-b"));
+    assert!(rule("exact").matches("EXACT"));
+    assert!(!rule("exact").matches("exactly"));
+}
+
+#[test]
+fn test_aliases_are_followed_through_identities_to_mailboxes() {
+    let delivery = resolve_address("info@example.com", &[domain()]);
+
+    assert_eq!(delivery.route, Route::Alias { internal: false });
+    let [team, bob] = delivery.destinations.as_slice() else {
+        panic!("Unexpected destinations: {:?}", delivery.destinations);
+    };
+    assert_eq!(bob.route, Route::External);
+
+    let [sales, info] = team.destinations.as_slice() else {
+        panic!("Unexpected destinations: {:?}", team.destinations);
+    };
+    assert_eq!(info.route, Route::Loop);
+    assert_eq!(
+        sales.route,
+        Route::Identity {
+            mailbox: "alice@example.com".into()
+        }
+    );
+    let alice = &sales.destinations[0];
+    assert_eq!(alice.route, Route::Mailbox { internal: false });
+    let forwarded: Vec<_> = alice.destinations.iter().map(|d| &d.address).collect();
+    assert_eq!(forwarded, ["alice@home.example"]);
+}
+
+#[test]
+fn test_only_confirmed_unblocked_forwardings_are_followed() {
+    let delivery = resolve_address("alice@example.com", &[domain()]);
+    let forwarded: Vec<_> = delivery.destinations.iter().map(|d| &d.address).collect();
+    assert_eq!(forwarded, ["alice@home.example"]);
+}
+
+#[test]
+fn test_rewrites_apply_in_order() {
+    let delivery = resolve_address("support-billing@example.com", &[domain()]);
+    assert!(matches!(delivery.route, Route::Rewrite { ref name, .. } if name == "support"));
+
+    // nobody@ has no mailbox, so the catch-all rule further down picks it up.
+    let nobody = &delivery.destinations[0];
+    assert!(matches!(nobody.route, Route::Rewrite { ref name, .. } if name == "late"));
+    assert_eq!(nobody.destinations[0].address, "alice@example.com");
+}
@@ -0,0 +1,75 @@
+//! Tests for evaluating rewrite rules locally.
+
+use migadu_client::rewrite_rules::{check_rules, covers, test_rules, RuleProblem};
+use migadu_client::state::{DomainState, ResourceKind};
+use migadu_client::{CreateRewrite, Rewrite};
+use serde_json::json;
+
+fn domain() -> DomainState {
+    serde_json::from_value(json!({
+        "domain": "example.com",
+        "aliases": [{
+            "local_part": "billing",
+            "domain_name": "example.com",
+            "address": "billing@example.com",
+            "destinations": ["alice@example.com"]
+        }],
+        "rewrites": [
+            { "name": "support-billing", "local_part_rule": "support-billing-*", "order_num": 5, "destinations": ["b@example.com"] },
+            { "name": "support", "local_part_rule": "support-*", "order_num": 1, "destinations": ["s@example.com"] },
+            { "name": "billing", "local_part_rule": "billing", "order_num": 7, "destinations": ["b@example.com"] }
+        ]
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_covers() {
+    assert!(covers("support-*", "support-billing-*"));
+    assert!(covers("*", "anything-*"));
+    assert!(covers("*a*", "a*"));
+    assert!(!covers("support-billing-*", "support-*"));
+    assert!(!covers("*ab*", "a*b"));
+    assert!(!covers("exact", "exact*"));
+}
+
+#[test]
+fn test_shadowed_and_unreachable_rules() {
+    let warnings = check_rules(&domain());
+    let problems: Vec<_> = warnings
+        .iter()
+        .map(|w| (w.rule.as_str(), &w.problem))
+        .collect();
+    assert_eq!(
+        problems,
+        [
+            (
+                "support-billing",
+                &RuleProblem::Shadowed {
+                    by: "support".into(),
+                    by_order_num: Some(1)
+                }
+            ),
+            (
+                "billing",
+                &RuleProblem::Unreachable {
+                    handled_by: ResourceKind::Alias
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_proposed_rule_takes_effect_by_order() {
+    let mut state = domain();
+    let mut proposed = CreateRewrite::new("vip", "support-vip*", "vip@example.com");
+    proposed.order_num = Some(0);
+    state.rewrites.push(Rewrite::from(&proposed));
+
+    let test = test_rules(&state, "support-vip-acme");
+    assert_eq!(test.matched.as_deref(), Some("vip"));
+    assert_eq!(test.rules[0].name, "vip");
+    assert_eq!(test.rules.iter().filter(|r| r.matches).count(), 2);
+    assert_eq!(test.handled_by, None);
+}
@@ -0,0 +1,119 @@
+//! Tests for account snapshots and diffing them.
+
+use migadu_client::diff::{diff_snapshots, Difference};
+use migadu_client::rewrite_rules::evaluation_order;
+use migadu_client::snapshot::{Snapshot, SNAPSHOT_VERSION};
+use migadu_client::state::DomainState;
+use serde_json::json;
+
+fn domain(name: &str, aliases: &[&str]) -> DomainState {
+    let aliases: Vec<_> = aliases
+        .iter()
+        .map(|local_part| {
+            json!({
+                "local_part": local_part,
+                "domain_name": name,
+                "address": format!("{}@{}", local_part, name),
+                "destinations": ["admin@example.com"]
+            })
+        })
+        .collect();
+    serde_json::from_value(json!({ "domain": name, "aliases": aliases })).unwrap()
+}
+
+#[test]
+fn test_snapshot_is_sorted() {
+    let snapshot = Snapshot::new(vec![
+        domain("example.org", &["sales", "info"]),
+        domain("example.com", &[]),
+    ]);
+
+    assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+    let names: Vec<_> = snapshot.domains.iter().map(|d| d.domain.as_str()).collect();
+    assert_eq!(names, ["example.com", "example.org"]);
+    let aliases: Vec<_> = snapshot
+        .domain("example.org")
+        .unwrap()
+        .aliases
+        .iter()
+        .map(|a| a.local_part.as_str())
+        .collect();
+    assert_eq!(aliases, ["info", "sales"]);
+}
+
+#[test]
+fn test_snapshot_sorts_rewrites_in_evaluation_order() {
+    let mut state = domain("example.com", &[]);
+    state.rewrites = serde_json::from_value(json!([
+        { "name": "unordered", "local_part_rule": "*", "destinations": ["a@example.com"] },
+        { "name": "b", "local_part_rule": "b-*", "order_num": 2, "destinations": ["a@example.com"] },
+        { "name": "a", "local_part_rule": "a-*", "order_num": 2, "destinations": ["a@example.com"] },
+        { "name": "first", "local_part_rule": "x", "order_num": 1, "destinations": ["a@example.com"] }
+    ]))
+    .unwrap();
+    let expected: Vec<_> = evaluation_order(&state.rewrites)
+        .iter()
+        .map(|r| r.name.clone())
+        .collect();
+
+    let snapshot = Snapshot::new(vec![state]);
+    let names: Vec<_> = snapshot.domains[0]
+        .rewrites
+        .iter()
+        .map(|r| &r.name)
+        .collect();
+    assert_eq!(names, ["first", "a", "b", "unordered"]);
+    assert_eq!(names, expected.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_newer_snapshot_version_is_rejected() {
+    let snapshot: Snapshot = serde_json::from_value(json!({
+        "version": SNAPSHOT_VERSION + 1,
+        "domains": []
+    }))
+    .unwrap();
+
+    assert!(snapshot.check_version().is_err());
+    assert!(Snapshot::new(Vec::new()).check_version().is_ok());
+}
+
+#[test]
+fn test_diff_reports_list_entries() {
+    let old = Snapshot::new(vec![domain("example.com", &["info", "sales"])]);
+    let mut new = old.clone();
+    let aliases = &mut new.domains[0].aliases;
+    aliases.retain(|a| a.local_part != "sales");
+    aliases[0].destinations.push("team@example.com".to_string());
+
+    let diff = diff_snapshots(&old, &new);
+    assert_eq!(diff.count(Difference::Removed), 1);
+    let changed = diff
+        .resources
+        .iter()
+        .find(|r| r.difference == Difference::Changed)
+        .unwrap();
+    assert_eq!(changed.id, "info@example.com");
+    assert_eq!(changed.fields[0].field, "destinations");
+    assert_eq!(changed.fields[0].added, [json!("team@example.com")]);
+    assert!(changed.fields[0].removed.is_empty());
+
+    assert!(diff_snapshots(&old, &old).is_empty());
+}
+
+#[test]
+fn test_diff_matches_rewrites_by_name_regardless_of_case() {
+    let mut old = domain("example.com", &[]);
+    old.rewrites = serde_json::from_value(json!([{
+        "name": "support",
+        "local_part_rule": "support-*",
+        "destinations": ["admin@example.com"]
+    }]))
+    .unwrap();
+    let mut new = old.clone();
+    new.rewrites[0].name = "Support".to_string();
+
+    let diff = diff_snapshots(&Snapshot::new(vec![old]), &Snapshot::new(vec![new]));
+    assert_eq!(diff.count(Difference::Added), 0);
+    assert_eq!(diff.count(Difference::Removed), 0);
+}
@@ -0,0 +1,330 @@
+//! The Migadu accounts the server acts for, and which of them manages each domain.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::path::PathBuf;
+
+use clap::Args;
+use migadu_client::config::{self, Config, Profile};
+use migadu_client::credentials::{self, CredentialProvider};
+use migadu_client::MigaduClient;
+use tokio::sync::RwLock;
+
+/// Where accounts are loaded from.
+#[derive(Args, Debug, Clone)]
+pub struct AccountOptions {
+    /// Path to the config file [default: ~/.config/migadu/config.toml]
+    #[arg(long, env = "MIGADU_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Config file profile used when a tool call names neither an account nor a domain
+    #[arg(long, env = "MIGADU_PROFILE")]
+    pub profile: Option<String>,
+}
+
+/// A Migadu account and the domain it works on by default.
+#[derive(Clone)]
+pub struct Account {
+    pub client: MigaduClient,
+    pub domain: Option<String>,
+}
+
+impl Account {
+    /// Builds an account from a profile, or returns `None` if it lacks an email or API key.
+    fn from_profile(profile: &Profile, api_key: Option<String>) -> Option<Self> {
+        let (email, api_key) = (profile.email.clone()?, api_key?);
+        let client = match profile.base_url.clone() {
+            Some(base_url) => MigaduClient::with_base_url(email, api_key, base_url),
+            None => MigaduClient::new(email, api_key),
+        };
+        Some(Self {
+            client,
+            domain: profile.domain.clone(),
+        })
+    }
+}
+
+/// Every account, by name. Accounts are the profiles of the config file; the default
+/// profile also takes its settings from MIGADU_EMAIL, MIGADU_API_KEY (and friends),
+/// MIGADU_DOMAIN and MIGADU_BASE_URL, as in the CLI.
+pub struct Accounts {
+    accounts: RwLock<BTreeMap<String, Account>>,
+    default: String,
+    /// Owning account of each domain (lowercased) seen so far.
+    owners: RwLock<HashMap<String, String>>,
+    /// Domains (lowercased) that no account listed when last asked.
+    unowned: RwLock<HashSet<String>>,
+}
+
+impl Accounts {
+    pub fn load(options: &AccountOptions) -> Result<Self, String> {
+        let path = match &options.config {
+            Some(path) => Some(path.clone()),
+            None => config::default_path().ok(),
+        };
+        let config = match path {
+            Some(path) => Config::load(&path).map_err(|e| e.to_string())?,
+            None => Config::default(),
+        };
+        let default = match &options.profile {
+            Some(name) => {
+                config.profile(Some(name)).map_err(|e| e.to_string())?;
+                name.clone()
+            }
+            None => config.default_profile_name().to_string(),
+        };
+
+        let mut accounts = BTreeMap::new();
+        for (name, profile) in &config.profiles {
+            if *name == default {
+                continue;
+            }
+            match profile.credentials().and_then(|chain| chain.api_key()) {
+                Ok(api_key) => match Account::from_profile(profile, api_key) {
+                    Some(account) => {
+                        accounts.insert(name.clone(), account);
+                    }
+                    None => eprintln!("Skipping account '{}': it has no email or API key", name),
+                },
+                Err(e) => eprintln!("Skipping account '{}': {}", name, e),
+            }
+        }
+
+        let mut profile = config.profiles.get(&default).cloned().unwrap_or_default();
+        profile.email = env::var("MIGADU_EMAIL").ok().or(profile.email);
+        profile.domain = env::var("MIGADU_DOMAIN").ok().or(profile.domain);
+        profile.base_url = env::var("MIGADU_BASE_URL").ok().or(profile.base_url);
+        let api_key = credentials::from_env()
+            .and_then(|chain| Ok(chain.extend(profile.credentials()?)))
+            .and_then(|chain| chain.api_key());
+        match api_key {
+            Ok(api_key) => {
+                if let Some(account) = Account::from_profile(&profile, api_key) {
+                    accounts.insert(default.clone(), account);
+                }
+            }
+            Err(e) => eprintln!("Ignoring configured API key: {}", e),
+        }
+
+        Ok(Self::new(default, accounts))
+    }
+
+    /// `default` names the account used when a tool call names neither an account nor
+    /// a domain; it need not be one of `accounts`.
+    pub fn new(default: String, accounts: BTreeMap<String, Account>) -> Self {
+        Self {
+            accounts: RwLock::new(accounts),
+            default,
+            owners: RwLock::new(HashMap::new()),
+            unowned: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// Returns the named account, or the default one.
+    pub async fn get(&self, name: Option<&str>) -> Result<(String, Account), String> {
+        let accounts = self.accounts.read().await;
+        if accounts.is_empty() {
+            return Err("Not configured. Set MIGADU_EMAIL and MIGADU_API_KEY (or MIGADU_API_KEY_FILE, MIGADU_API_KEY_COMMAND, MIGADU_API_KEY_KEYRING) env vars, add profiles to the config file, or call 'configure' tool.".to_string());
+        }
+        let name = name.unwrap_or(&self.default);
+        match accounts.get(name) {
+            Some(account) => Ok((name.to_string(), account.clone())),
+            None => Err(format!(
+                "Unknown account '{}'. Accounts: {}",
+                name,
+                accounts.keys().cloned().collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+
+    /// Returns the account that manages `domain`, asking each account for its domains if
+    /// no account is known to.
+    ///
+    /// A domain no account listed is not asked about again until `list_domains` lists it
+    /// or `configure` adds an account, so a mistyped domain costs one round of requests.
+    pub async fn for_domain(&self, domain: &str) -> Result<(String, Account), String> {
+        let unowned = || {
+            format!(
+                "No account manages domain {}. Call 'list_domains' to see the domains of each account.",
+                domain
+            )
+        };
+        if let Some(name) = self.owner(domain).await {
+            return self.get(Some(&name)).await;
+        }
+        if self.unowned.read().await.contains(&domain.to_lowercase()) {
+            return Err(unowned());
+        }
+        let mut complete = true;
+        for name in self.names().await {
+            let (_, account) = self.get(Some(&name)).await?;
+            match account.client.list_domains().await {
+                Ok(domains) => {
+                    self.remember(&name, domains.iter().map(|d| d.name.as_str()))
+                        .await
+                }
+                // An account that cannot list its domains cannot own this one either.
+                Err(_) => complete = false,
+            }
+        }
+        match self.owner(domain).await {
+            Some(name) => self.get(Some(&name)).await,
+            None => {
+                // Only trust the answer if every account gave one.
+                if complete {
+                    self.unowned.write().await.insert(domain.to_lowercase());
+                }
+                Err(unowned())
+            }
+        }
+    }
+
+    async fn owner(&self, domain: &str) -> Option<String> {
+        let accounts = self.accounts.read().await;
+        let has_domain = |name: &String| {
+            accounts[name]
+                .domain
+                .as_deref()
+                .is_some_and(|d| d.eq_ignore_ascii_case(domain))
+        };
+        if accounts.contains_key(&self.default) && has_domain(&self.default) {
+            return Some(self.default.clone());
+        }
+        if let Some(name) = accounts.keys().find(|name| has_domain(name)) {
+            return Some(name.clone());
+        }
+        self.owners
+            .read()
+            .await
+            .get(&domain.to_lowercase())
+            .cloned()
+    }
+
+    /// Records that `account` manages `domains`.
+    pub async fn remember<'a>(&self, account: &str, domains: impl IntoIterator<Item = &'a str>) {
+        let mut owners = self.owners.write().await;
+        let mut unowned = self.unowned.write().await;
+        for domain in domains {
+            let domain = domain.to_lowercase();
+            unowned.remove(&domain);
+            owners.insert(domain, account.to_string());
+        }
+    }
+
+    /// Adds or replaces an account; `None` names the default account.
+    pub async fn insert(&self, name: Option<String>, account: Account) -> String {
+        let name = name.unwrap_or_else(|| self.default.clone());
+        self.owners.write().await.retain(|_, owner| *owner != name);
+        // The new account may manage domains no account did before.
+        self.unowned.write().await.clear();
+        self.accounts.write().await.insert(name.clone(), account);
+        name
+    }
+
+    /// Names of every account, in order.
+    pub async fn names(&self) -> Vec<String> {
+        self.accounts.read().await.keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An account working on `domain` that cannot reach the API.
+    fn account(domain: &str) -> Account {
+        Account {
+            client: MigaduClient::with_base_url(
+                "admin@example.com",
+                "api-key",
+                "http://127.0.0.1:9",
+            ),
+            domain: Some(domain.to_string()),
+        }
+    }
+
+    fn accounts(default: &str, accounts: &[(&str, &str)]) -> Accounts {
+        let accounts = accounts
+            .iter()
+            .map(|(name, domain)| (name.to_string(), account(domain)))
+            .collect();
+        Accounts::new(default.to_string(), accounts)
+    }
+
+    #[tokio::test]
+    async fn test_default_account_domain_wins() {
+        let accounts = accounts("main", &[("alpha", "example.com"), ("main", "example.com")]);
+        assert_eq!(accounts.owner("EXAMPLE.com").await.as_deref(), Some("main"));
+    }
+
+    #[tokio::test]
+    async fn test_configured_domains_beat_learned_ones() {
+        let accounts = accounts(
+            "main",
+            &[("alpha", "shop.example"), ("main", "example.com")],
+        );
+        accounts
+            .remember("main", ["shop.example", "learned.example"])
+            .await;
+        assert_eq!(
+            accounts.owner("shop.example").await.as_deref(),
+            Some("alpha")
+        );
+        assert_eq!(
+            accounts.owner("Learned.Example").await.as_deref(),
+            Some("main")
+        );
+        assert_eq!(accounts.owner("other.example").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_insert_forgets_what_the_account_managed() {
+        let accounts = accounts(
+            "main",
+            &[("alpha", "shop.example"), ("main", "example.com")],
+        );
+        accounts.remember("alpha", ["learned.example"]).await;
+        accounts.remember("main", ["kept.example"]).await;
+
+        accounts
+            .insert(Some("alpha".to_string()), account("new.example"))
+            .await;
+        assert_eq!(accounts.owner("learned.example").await, None);
+        assert_eq!(accounts.owner("shop.example").await, None);
+        assert_eq!(
+            accounts.owner("new.example").await.as_deref(),
+            Some("alpha")
+        );
+        assert_eq!(
+            accounts.owner("kept.example").await.as_deref(),
+            Some("main")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_single_account_does_not_own_every_domain() {
+        let accounts = accounts("main", &[("main", "example.com")]);
+        assert_eq!(accounts.owner("example.com").await.as_deref(), Some("main"));
+        let error = accounts.for_domain("other.example").await.err().unwrap();
+        assert!(
+            error.starts_with("No account manages domain other.example"),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unowned_domains_are_remembered_until_listed() {
+        let accounts = accounts("main", &[("main", "example.com")]);
+        accounts
+            .unowned
+            .write()
+            .await
+            .insert("typo.example".to_string());
+        assert!(accounts.for_domain("Typo.Example").await.is_err());
+
+        accounts.remember("main", ["typo.example"]).await;
+        assert!(accounts.unowned.read().await.is_empty());
+        assert_eq!(accounts.for_domain("typo.example").await.unwrap().0, "main");
+    }
+}
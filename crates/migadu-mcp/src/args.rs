@@ -0,0 +1,306 @@
+//! Typed tool arguments, from which tool input schemas are generated.
+
+use migadu_client::{
+    CreateAlias, CreateForwarding, CreateIdentity, CreateMailbox, CreateRewrite, UpdateAlias,
+    UpdateForwarding, UpdateIdentity, UpdateMailbox,
+};
+use rust_mcp_sdk::schema::ToolInputSchema;
+use schemars::generate::SchemaSettings;
+use schemars::transform::RecursiveTransform;
+use schemars::{JsonSchema, Schema};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+
+/// Builds the input schema of a tool taking `T`.
+///
+/// Tool input schemas carry only top-level properties, so subschemas are inlined, and
+/// optional arguments are described by their type alone rather than as nullable.
+pub fn input_schema<T: JsonSchema>() -> ToolInputSchema {
+    let (required, properties) = object_parts(&schema_for::<T>());
+    ToolInputSchema::new(required, properties)
+}
+
+fn schema_for<T: JsonSchema>() -> Schema {
+    SchemaSettings::draft2020_12()
+        .with(|s| s.inline_subschemas = true)
+        .with_transform(RecursiveTransform(drop_null_type))
+        .into_generator()
+        .into_root_schema_for::<T>()
+}
+
+/// Properties of an object schema.
+pub type Properties = HashMap<String, serde_json::Map<String, serde_json::Value>>;
+
+/// Splits an object schema into its required property names and its properties.
+pub fn object_parts(schema: &Schema) -> (Vec<String>, Option<Properties>) {
+    let properties = schema
+        .get("properties")
+        .and_then(|p| p.as_object())
+        .map(|p| {
+            p.iter()
+                .filter_map(|(name, property)| Some((name.clone(), property.as_object()?.clone())))
+                .collect()
+        });
+    let required = schema
+        .get("required")
+        .and_then(|r| r.as_array())
+        .map(|r| {
+            r.iter()
+                .filter_map(|n| n.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    (required, properties)
+}
+
+/// Turns `"type": ["string", "null"]` into `"type": "string"`.
+fn drop_null_type(schema: &mut Schema) {
+    let Some(types) = schema.get_mut("type").and_then(|t| t.as_array_mut()) else {
+        return;
+    };
+    types.retain(|t| t != "null");
+    if let [single] = types.as_slice() {
+        let single = single.clone();
+        schema.insert("type".to_string(), single);
+    }
+}
+
+/// Deserializes the arguments of a tool call taking `T`.
+///
+/// Arguments its schema does not name are refused: the argument structs flatten their
+/// parts, which serde cannot combine with `deny_unknown_fields`, so a misspelled argument
+/// would otherwise be dropped without a word.
+pub fn parse<T: DeserializeOwned + JsonSchema>(
+    args: serde_json::Map<String, serde_json::Value>,
+) -> Result<T, String> {
+    let (_, properties) = object_parts(&schema_for::<T>());
+    let known = |name: &String| properties.as_ref().is_some_and(|p| p.contains_key(name));
+    if let Some(unknown) = args.keys().find(|name| !known(name)) {
+        return Err(format!("Invalid arguments: unknown argument `{}`", unknown));
+    }
+    deserialize(args)
+}
+
+/// Splits the arguments of a tool on a domain into its target and the tool's own
+/// arguments, which are checked when the tool parses them.
+pub fn split_target(
+    args: serde_json::Map<String, serde_json::Value>,
+) -> Result<Targeted<serde_json::Map<String, serde_json::Value>>, String> {
+    deserialize(args)
+}
+
+fn deserialize<T: DeserializeOwned>(
+    args: serde_json::Map<String, serde_json::Value>,
+) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::Object(args))
+        .map_err(|e| format!("Invalid arguments: {}", e))
+}
+
+/// Rejects empty strings, which would address a whole collection instead of one resource.
+fn non_empty<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    if value.is_empty() {
+        return Err(serde::de::Error::custom("must not be empty"));
+    }
+    Ok(value)
+}
+
+/// Checks that `value` names one resource when it is put into an API path: an empty
+/// value would address the whole collection, and `/`, `.` or `..` a different resource,
+/// possibly in another domain.
+pub fn check_segment(value: &str) -> Result<(), &'static str> {
+    match value {
+        "" => Err("must not be empty"),
+        "." | ".." => Err("must not be . or .."),
+        _ if value.contains(['/', '\\']) => Err("must not contain / or \\"),
+        _ => Ok(()),
+    }
+}
+
+/// Deserializes a value that is put into an API path; see [`check_segment`].
+fn segment<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    check_segment(&value).map_err(serde::de::Error::custom)?;
+    Ok(value)
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct Configure {
+    /// Migadu account email
+    #[serde(deserialize_with = "non_empty")]
+    #[schemars(length(min = 1))]
+    pub email: String,
+    /// Migadu API key
+    #[serde(deserialize_with = "non_empty")]
+    #[schemars(length(min = 1))]
+    pub api_key: String,
+    /// Domain to operate on when a tool call names none
+    #[serde(deserialize_with = "segment")]
+    #[schemars(length(min = 1))]
+    pub domain: String,
+    /// Name of the account to add or replace [default: the default account]
+    pub account: Option<String>,
+}
+
+/// Which account and domain a tool acts on; every tool on a domain takes these.
+#[derive(Deserialize, JsonSchema, Default)]
+pub struct Target {
+    /// Domain to operate on [default: the account's domain]
+    pub domain: Option<String>,
+    /// Account to act as [default: the account that manages `domain`]
+    pub account: Option<String>,
+}
+
+/// Arguments of a tool on a domain.
+#[derive(Deserialize, JsonSchema)]
+pub struct Targeted<T> {
+    #[serde(flatten)]
+    pub target: Target,
+    #[serde(flatten)]
+    pub args: T,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct AccountFilter {
+    /// Only list this account's domains [default: every account]
+    pub account: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct NoArgs {}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LocalPart {
+    /// Local part of the email (before @)
+    #[serde(deserialize_with = "segment")]
+    #[schemars(length(min = 1))]
+    pub local_part: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RewriteName {
+    /// Name (slug) of the rewrite rule
+    #[serde(deserialize_with = "segment")]
+    #[schemars(length(min = 1))]
+    pub name: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct Mailbox {
+    /// Local part of the mailbox (before @)
+    #[serde(deserialize_with = "segment")]
+    #[schemars(length(min = 1))]
+    pub mailbox: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct Identity {
+    /// Local part of the mailbox (before @)
+    #[serde(deserialize_with = "segment")]
+    #[schemars(length(min = 1))]
+    pub mailbox: String,
+    /// Local part of the identity (before @)
+    #[serde(deserialize_with = "segment")]
+    #[schemars(length(min = 1))]
+    pub local_part: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct Forwarding {
+    /// Local part of the mailbox (before @)
+    #[serde(deserialize_with = "segment")]
+    #[schemars(length(min = 1))]
+    pub mailbox: String,
+    /// Forwarding destination address
+    #[serde(deserialize_with = "segment")]
+    #[schemars(length(min = 1))]
+    pub address: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct NewMailbox {
+    #[serde(flatten)]
+    pub mailbox: CreateMailbox,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct MailboxUpdate {
+    #[serde(flatten)]
+    pub key: LocalPart,
+    #[serde(flatten)]
+    pub update: UpdateMailbox,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct NewAlias {
+    #[serde(flatten)]
+    pub alias: CreateAlias,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct AliasUpdate {
+    #[serde(flatten)]
+    pub key: LocalPart,
+    #[serde(flatten)]
+    pub update: UpdateAlias,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct NewRewrite {
+    #[serde(flatten)]
+    pub rewrite: CreateRewrite,
+}
+
+/// `name` identifies the rule, so renaming it takes `new_name`.
+#[derive(Deserialize, JsonSchema)]
+pub struct RewriteUpdate {
+    #[serde(flatten)]
+    pub key: RewriteName,
+    /// New name (slug) of the rewrite rule
+    pub new_name: Option<String>,
+    /// Pattern to match (e.g., "support-*" or "sales-*")
+    pub local_part_rule: Option<String>,
+    /// Destinations as a comma-separated string
+    pub destinations: Option<String>,
+    /// Position in which rules are tried, lowest first
+    pub order_num: Option<i32>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct NewIdentity {
+    #[serde(flatten)]
+    pub key: Mailbox,
+    #[serde(flatten)]
+    pub identity: CreateIdentity,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct IdentityUpdate {
+    #[serde(flatten)]
+    pub key: Identity,
+    #[serde(flatten)]
+    pub update: UpdateIdentity,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct NewForwarding {
+    #[serde(flatten)]
+    pub key: Mailbox,
+    #[serde(flatten)]
+    pub forwarding: CreateForwarding,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ForwardingUpdate {
+    #[serde(flatten)]
+    pub key: Forwarding,
+    #[serde(flatten)]
+    pub update: UpdateForwarding,
+}
@@ -0,0 +1,204 @@
+//! Streamable HTTP (and legacy SSE) transport behind a shared bearer token.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use clap::{Args, ValueEnum};
+use rust_mcp_sdk::auth::{AuthInfo, AuthProvider, AuthenticationError, OauthEndpoint};
+use rust_mcp_sdk::mcp_http::{GenericBody, GenericBodyExt, McpAppState};
+use rust_mcp_sdk::mcp_server::error::TransportServerError;
+use rust_mcp_sdk::mcp_server::{hyper_server, HyperServerOptions, ServerHandler};
+use rust_mcp_sdk::schema::InitializeResult;
+
+/// How clients reach the server.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum Transport {
+    /// Standard input and output, for a client that starts the server itself
+    #[default]
+    Stdio,
+    /// Streamable HTTP at /mcp and SSE at /sse, for a shared server
+    Http,
+}
+
+/// Options for serving over HTTP.
+#[derive(Args, Debug, Clone)]
+pub struct HttpOptions {
+    /// Address to listen on with `--transport http`
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub listen: SocketAddr,
+
+    /// File holding the bearer token clients must send with `--transport http`
+    /// [default: the MIGADU_MCP_TOKEN environment variable]
+    #[arg(long)]
+    pub token_file: Option<PathBuf>,
+}
+
+impl HttpOptions {
+    /// Reads the bearer token from `--token-file`, or else MIGADU_MCP_TOKEN. It is never
+    /// taken as an argument, which process listings and shell history would show.
+    pub fn token(&self) -> Result<String, String> {
+        let token = match &self.token_file {
+            Some(path) => fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+                .trim()
+                .to_string(),
+            None => env::var("MIGADU_MCP_TOKEN").unwrap_or_default(),
+        };
+        if token.is_empty() {
+            return Err("--transport http requires MIGADU_MCP_TOKEN or --token-file".to_string());
+        }
+        Ok(token)
+    }
+}
+
+/// Accepts exactly one token, shared by every client.
+struct BearerToken {
+    token: String,
+}
+
+#[async_trait]
+impl AuthProvider for BearerToken {
+    async fn verify_token(&self, access_token: String) -> Result<AuthInfo, AuthenticationError> {
+        if !constant_time_eq(access_token.as_bytes(), self.token.as_bytes()) {
+            return Err(AuthenticationError::InvalidToken {
+                description: "Invalid bearer token",
+            });
+        }
+        Ok(AuthInfo {
+            token_unique_id: "shared".to_string(),
+            client_id: None,
+            user_id: None,
+            scopes: None,
+            // The token never expires, but the SDK rejects tokens without an expiry.
+            expires_at: Some(SystemTime::now() + Duration::from_secs(60)),
+            audience: None,
+            extra: None,
+        })
+    }
+
+    fn auth_endpoints(&self) -> Option<&HashMap<String, OauthEndpoint>> {
+        None
+    }
+
+    async fn handle_request(
+        &self,
+        _request: http::Request<&str>,
+        _state: Arc<McpAppState>,
+    ) -> Result<http::Response<GenericBody>, TransportServerError> {
+        Ok(GenericBody::create_404_response())
+    }
+
+    fn protected_resource_metadata_url(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Compares without returning early, so response times do not reveal how much of a
+/// guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Serves `handler` over HTTP until interrupted.
+pub async fn serve(
+    details: InitializeResult,
+    handler: impl ServerHandler,
+    listen: SocketAddr,
+    token: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let options = HyperServerOptions {
+        host: listen.ip().to_string(),
+        port: listen.port(),
+        auth: Some(Arc::new(BearerToken { token })),
+        ..Default::default()
+    };
+    let server = hyper_server::create_server(details, handler, options);
+    eprintln!(
+        "Serving MCP at http://{}{} (SSE at {})",
+        listen,
+        server.options().streamable_http_endpoint(),
+        server.options().sse_endpoint()
+    );
+    server.start().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_is_read_from_the_token_file() {
+        let path = env::temp_dir().join(format!("migadu-mcp-token-{}", std::process::id()));
+        let options = |path: &PathBuf| HttpOptions {
+            listen: "127.0.0.1:8080".parse().unwrap(),
+            token_file: Some(path.clone()),
+        };
+
+        fs::write(&path, "s3cret-token\n").unwrap();
+        assert_eq!(options(&path).token(), Ok("s3cret-token".to_string()));
+
+        fs::write(&path, "  \n").unwrap();
+        assert_eq!(
+            options(&path).token().unwrap_err(),
+            "--transport http requires MIGADU_MCP_TOKEN or --token-file"
+        );
+
+        fs::remove_file(&path).unwrap();
+        assert!(options(&path)
+            .token()
+            .unwrap_err()
+            .starts_with("Failed to read"));
+    }
+
+    fn provider() -> BearerToken {
+        BearerToken {
+            token: "s3cret-token".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_only_the_shared_token_is_accepted() {
+        for token in ["s3cret-tokem", "s3cret", "s3cret-token-and-more", ""] {
+            let result = provider().verify_token(token.to_string()).await;
+            assert!(
+                matches!(result, Err(AuthenticationError::InvalidToken { .. })),
+                "{:?} was accepted",
+                token
+            );
+        }
+        assert!(provider()
+            .verify_token("s3cret-token".to_string())
+            .await
+            .is_ok());
+    }
+
+    /// The SDK rejects a request once `expires_at` has passed, so the expiry must be set
+    /// afresh on every verification rather than once per session or token.
+    #[tokio::test]
+    async fn test_expiry_is_renewed_on_every_verification() {
+        let provider = provider();
+        let first = provider
+            .verify_token("s3cret-token".to_string())
+            .await
+            .unwrap()
+            .expires_at
+            .unwrap();
+        assert!(first > SystemTime::now() + Duration::from_secs(30));
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let second = provider
+            .verify_token("s3cret-token".to_string())
+            .await
+            .unwrap()
+            .expires_at
+            .unwrap();
+        assert!(second > first);
+    }
+}
@@ -1,133 +1,324 @@
 //! MCP server for Migadu email hosting API.
 
+mod accounts;
+mod args;
+mod http;
+mod output;
+mod policy;
+mod prompts;
+mod resources;
+
 use async_trait::async_trait;
-use migadu_client::MigaduClient;
+use clap::Parser;
+use migadu_client::{MigaduClient, UpdateRewrite};
 use rust_mcp_sdk::mcp_server::{server_runtime, ServerHandler};
 use rust_mcp_sdk::schema::{
-    CallToolRequest, CallToolResult, ContentBlock, Implementation, InitializeResult,
-    ListToolsRequest, ListToolsResult, RpcError, ServerCapabilities, ServerCapabilitiesTools,
-    TextContent, Tool, ToolInputSchema,
+    CallToolRequest, CallToolResult, ContentBlock, GetPromptRequest, GetPromptResult,
+    Implementation, InitializeResult, ListPromptsRequest, ListPromptsResult,
+    ListResourceTemplatesRequest, ListResourceTemplatesResult, ListResourcesRequest,
+    ListResourcesResult, ListToolsRequest, ListToolsResult, ReadResourceRequest,
+    ReadResourceResult, Resource, RpcError, ServerCapabilities, ServerCapabilitiesPrompts,
+    ServerCapabilitiesResources, ServerCapabilitiesTools, TextContent, TextResourceContents, Tool, ToolAnnotations,
 };
 use rust_mcp_sdk::schema::schema_utils::CallToolError;
 use rust_mcp_sdk::{McpServer, StdioTransport, TransportOptions};
-use std::collections::HashMap;
-use std::env;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::process;
 use std::sync::Arc;
-use tokio::sync::RwLock;
 
-struct MigaduMcpServer {
-    client: RwLock<Option<MigaduClient>>,
-    domain: RwLock<Option<String>>,
+use crate::accounts::{Account, AccountOptions, Accounts};
+use crate::http::{HttpOptions, Transport};
+use crate::policy::Policy;
+
+type Arguments = serde_json::Map<String, serde_json::Value>;
+
+/// MCP server for the Migadu email hosting API.
+///
+/// Accounts are the profiles of the CLI's config file. The default one can also be set
+/// with MIGADU_EMAIL, MIGADU_API_KEY (or MIGADU_API_KEY_FILE, MIGADU_API_KEY_COMMAND,
+/// MIGADU_API_KEY_KEYRING) and MIGADU_DOMAIN, or the `configure` tool.
+#[derive(Parser, Debug)]
+#[command(name = "migadu-mcp", version)]
+struct Cli {
+    /// How clients connect
+    #[arg(long, value_enum, default_value_t)]
+    transport: Transport,
+
+    #[command(flatten)]
+    http: HttpOptions,
+
+    #[command(flatten)]
+    accounts: AccountOptions,
+
+    #[command(flatten)]
+    policy: Policy,
 }
 
-impl MigaduMcpServer {
-    fn new() -> Self {
-        // Try to initialize from env vars
-        let client = match (env::var("MIGADU_EMAIL"), env::var("MIGADU_API_KEY")) {
-            (Ok(email), Ok(api_key)) => Some(MigaduClient::new(email, api_key)),
-            _ => None,
-        };
-        let domain = env::var("MIGADU_DOMAIN").ok();
+/// What a tool does to the account, which sets its annotations.
+#[derive(Clone, Copy)]
+enum Access {
+    Read,
+    /// Changes only this session's settings, not the Migadu account.
+    Session,
+    Create,
+    Update,
+    Delete,
+}
 
-        Self {
-            client: RwLock::new(client),
-            domain: RwLock::new(domain),
-        }
-    }
+struct MigaduMcpServer {
+    accounts: Accounts,
+    policy: Policy,
+}
 
-    async fn get_client(&self) -> Result<MigaduClient, String> {
-        self.client
-            .read()
-            .await
-            .clone()
-            .ok_or_else(|| "Not configured. Set MIGADU_EMAIL and MIGADU_API_KEY env vars, or call 'configure' tool.".to_string())
+impl MigaduMcpServer {
+    fn new(accounts: Accounts, policy: Policy) -> Self {
+        Self { accounts, policy }
     }
 
-    async fn get_domain(&self) -> Result<String, String> {
-        self.domain
-            .read()
-            .await
-            .clone()
-            .ok_or_else(|| "Domain not configured. Set MIGADU_DOMAIN env var, or call 'configure' tool.".to_string())
+    /// Resolves the account and domain a tool call acts on.
+    async fn get_context(&self, target: args::Target) -> Result<(MigaduClient, String), String> {
+        let domain = target.domain.filter(|d| !d.is_empty());
+        // Refuse a named domain before looking for the account that manages it.
+        if let Some(domain) = &domain {
+            args::check_segment(domain).map_err(|e| format!("Invalid domain: {}", e))?;
+            self.policy.check_domain(domain)?;
+        }
+        let (name, account) = match (target.account.as_deref(), &domain) {
+            (Some(name), _) => self.accounts.get(Some(name)).await?,
+            (None, Some(domain)) => self.accounts.for_domain(domain).await?,
+            (None, None) => self.accounts.get(None).await?,
+        };
+        let domain = domain.or(account.domain).ok_or_else(|| {
+            format!("No domain given. Pass 'domain', or set one for account '{}'.", name)
+        })?;
+        self.policy.check_domain(&domain)?;
+        Ok((account.client, domain))
     }
 
-    fn make_tool(name: &str, description: &str, properties: HashMap<String, serde_json::Map<String, serde_json::Value>>, required: Vec<String>) -> Tool {
+    fn make_tool<T: JsonSchema, O: JsonSchema>(name: &str, description: &str, access: Access) -> Tool {
+        let (read_only, destructive, idempotent) = match access {
+            Access::Read => (true, false, true),
+            Access::Session => (false, false, true),
+            Access::Create => (false, false, false),
+            Access::Update => (false, true, true),
+            Access::Delete => (false, true, true),
+        };
         Tool {
             name: name.to_string(),
             description: Some(description.to_string()),
-            input_schema: ToolInputSchema::new(required, Some(properties)),
-            annotations: None,
+            input_schema: args::input_schema::<T>(),
+            annotations: Some(ToolAnnotations {
+                destructive_hint: (!read_only).then_some(destructive),
+                idempotent_hint: (!read_only).then_some(idempotent),
+                open_world_hint: None,
+                read_only_hint: Some(read_only),
+                title: None,
+            }),
             meta: None,
-            output_schema: None,
+            output_schema: Some(output::output_schema::<O>()),
             title: None,
         }
     }
 
-    fn tools() -> Vec<Tool> {
+    /// Every tool, before the policy is applied.
+    fn all_tools() -> Vec<Tool> {
+        use args::*;
         vec![
-            Self::make_tool(
+            Self::make_tool::<Configure, output::Configured>(
                 "configure",
-                "Configure the Migadu API client credentials",
-                {
-                    let mut props = HashMap::new();
-                    props.insert("email".to_string(), {
-                        let mut m = serde_json::Map::new();
-                        m.insert("type".to_string(), serde_json::json!("string"));
-                        m.insert("description".to_string(), serde_json::json!("Migadu account email"));
-                        m
-                    });
-                    props.insert("api_key".to_string(), {
-                        let mut m = serde_json::Map::new();
-                        m.insert("type".to_string(), serde_json::json!("string"));
-                        m.insert("description".to_string(), serde_json::json!("Migadu API key"));
-                        m
-                    });
-                    props.insert("domain".to_string(), {
-                        let mut m = serde_json::Map::new();
-                        m.insert("type".to_string(), serde_json::json!("string"));
-                        m.insert("description".to_string(), serde_json::json!("Domain to operate on"));
-                        m
-                    });
-                    props
-                },
-                vec!["email".to_string(), "api_key".to_string(), "domain".to_string()],
-            ),
-            Self::make_tool(
+                "Add or replace an account and set the domain it works on",
+                Access::Session,
+            ),
+            Self::make_tool::<AccountFilter, output::Domains>(
+                "list_domains",
+                "List the domains of every account",
+                Access::Read,
+            ),
+            Self::make_tool::<Targeted<NoArgs>, output::Mailboxes>(
                 "list_mailboxes",
-                "List all mailboxes for the configured domain",
-                HashMap::new(),
-                vec![],
+                "List all mailboxes of a domain",
+                Access::Read,
             ),
-            Self::make_tool(
+            Self::make_tool::<Targeted<LocalPart>, migadu_client::Mailbox>(
                 "get_mailbox",
                 "Get details of a specific mailbox",
-                {
-                    let mut props = HashMap::new();
-                    props.insert("local_part".to_string(), {
-                        let mut m = serde_json::Map::new();
-                        m.insert("type".to_string(), serde_json::json!("string"));
-                        m.insert("description".to_string(), serde_json::json!("Local part of the email (before @)"));
-                        m
-                    });
-                    props
-                },
-                vec!["local_part".to_string()],
-            ),
-            Self::make_tool(
+                Access::Read,
+            ),
+            Self::make_tool::<Targeted<NewMailbox>, migadu_client::Mailbox>(
+                "create_mailbox",
+                "Create a mailbox",
+                Access::Create,
+            ),
+            Self::make_tool::<Targeted<MailboxUpdate>, migadu_client::Mailbox>(
+                "update_mailbox",
+                "Update a mailbox; omitted fields are left unchanged",
+                Access::Update,
+            ),
+            Self::make_tool::<Targeted<LocalPart>, migadu_client::Mailbox>(
+                "delete_mailbox",
+                "Delete a mailbox",
+                Access::Delete,
+            ),
+            Self::make_tool::<Targeted<NoArgs>, output::Aliases>(
                 "list_aliases",
-                "List all email aliases for the configured domain",
-                HashMap::new(),
-                vec![],
+                "List all email aliases of a domain",
+                Access::Read,
             ),
-            Self::make_tool(
+            Self::make_tool::<Targeted<LocalPart>, migadu_client::Alias>(
+                "get_alias",
+                "Get details of a specific alias",
+                Access::Read,
+            ),
+            Self::make_tool::<Targeted<NewAlias>, migadu_client::Alias>(
+                "create_alias",
+                "Create an alias",
+                Access::Create,
+            ),
+            Self::make_tool::<Targeted<AliasUpdate>, migadu_client::Alias>(
+                "update_alias",
+                "Update an alias; omitted fields are left unchanged",
+                Access::Update,
+            ),
+            Self::make_tool::<Targeted<LocalPart>, migadu_client::Alias>(
+                "delete_alias",
+                "Delete an alias",
+                Access::Delete,
+            ),
+            Self::make_tool::<Targeted<NoArgs>, output::Rewrites>(
                 "list_rewrites",
-                "List all rewrite rules for the configured domain",
-                HashMap::new(),
-                vec![],
+                "List all rewrite rules of a domain",
+                Access::Read,
+            ),
+            Self::make_tool::<Targeted<RewriteName>, migadu_client::Rewrite>(
+                "get_rewrite",
+                "Get details of a specific rewrite rule",
+                Access::Read,
+            ),
+            Self::make_tool::<Targeted<NewRewrite>, migadu_client::Rewrite>(
+                "create_rewrite",
+                "Create a rewrite rule",
+                Access::Create,
+            ),
+            Self::make_tool::<Targeted<RewriteUpdate>, migadu_client::Rewrite>(
+                "update_rewrite",
+                "Update a rewrite rule; omitted fields are left unchanged",
+                Access::Update,
+            ),
+            Self::make_tool::<Targeted<RewriteName>, migadu_client::Rewrite>(
+                "delete_rewrite",
+                "Delete a rewrite rule",
+                Access::Delete,
+            ),
+            Self::make_tool::<Targeted<Mailbox>, output::Identities>(
+                "list_identities",
+                "List the identities of a mailbox",
+                Access::Read,
+            ),
+            Self::make_tool::<Targeted<Identity>, migadu_client::Identity>(
+                "get_identity",
+                "Get details of a specific identity",
+                Access::Read,
+            ),
+            Self::make_tool::<Targeted<NewIdentity>, migadu_client::Identity>(
+                "create_identity",
+                "Create an identity for a mailbox",
+                Access::Create,
+            ),
+            Self::make_tool::<Targeted<IdentityUpdate>, migadu_client::Identity>(
+                "update_identity",
+                "Update an identity; omitted fields are left unchanged",
+                Access::Update,
+            ),
+            Self::make_tool::<Targeted<Identity>, migadu_client::Identity>(
+                "delete_identity",
+                "Delete an identity",
+                Access::Delete,
+            ),
+            Self::make_tool::<Targeted<Mailbox>, output::Forwardings>(
+                "list_forwardings",
+                "List the forwardings of a mailbox",
+                Access::Read,
+            ),
+            Self::make_tool::<Targeted<Forwarding>, migadu_client::Forwarding>(
+                "get_forwarding",
+                "Get details of a specific forwarding",
+                Access::Read,
+            ),
+            Self::make_tool::<Targeted<NewForwarding>, migadu_client::Forwarding>(
+                "create_forwarding",
+                "Create a forwarding for a mailbox",
+                Access::Create,
+            ),
+            Self::make_tool::<Targeted<ForwardingUpdate>, migadu_client::Forwarding>(
+                "update_forwarding",
+                "Update a forwarding; omitted fields are left unchanged",
+                Access::Update,
+            ),
+            Self::make_tool::<Targeted<Forwarding>, migadu_client::Forwarding>(
+                "delete_forwarding",
+                "Delete a forwarding",
+                Access::Delete,
             ),
         ]
     }
 
+    /// The tools the policy allows.
+    fn tools(&self) -> Vec<Tool> {
+        Self::all_tools().into_iter().filter(|t| self.policy.allows_tool(t)).collect()
+    }
+
+    /// Returns `true` if the policy allows the tool named `name`.
+    fn allows(tools: &[Tool], name: &str) -> bool {
+        tools.iter().any(|t| t.name == name)
+    }
+
+    /// The prompts whose every step uses a tool the policy allows.
+    fn workflows(&self) -> Vec<&'static prompts::Workflow> {
+        let tools = self.tools();
+        prompts::WORKFLOWS
+            .iter()
+            .filter(|w| w.tools.iter().all(|name| Self::allows(&tools, name)))
+            .collect()
+    }
+
+    /// Lists the resources of every domain each account manages that the policy allows.
+    async fn list_resources(&self) -> Result<Vec<Resource>, String> {
+        let tools = self.tools();
+        let mut resources = Vec::new();
+        for name in self.accounts.names().await {
+            let (_, account) = self.accounts.get(Some(&name)).await?;
+            let listed = account
+                .client
+                .list_domains()
+                .await
+                .map_err(|e| format!("Account '{}': {}", name, e))?;
+            self.accounts.remember(&name, listed.iter().map(|d| d.name.as_str())).await;
+            for domain in listed.iter().filter(|d| self.policy.check_domain(&d.name).is_ok()) {
+                resources.extend(
+                    resources::list(&account.client, &domain.name, |name| Self::allows(&tools, name))
+                        .await
+                        .map_err(|e| format!("Domain {}: {}", domain.name, e))?,
+                );
+            }
+        }
+        Ok(resources)
+    }
+
+    /// Reads the resource at `uri` as JSON text.
+    async fn read_resource(&self, uri: &str) -> Result<String, RpcError> {
+        let (domain, resource) = resources::parse(uri)
+            .ok_or_else(|| RpcError::invalid_params().with_message(format!("Unknown resource: {}", uri)))?;
+        if !Self::allows(&self.tools(), resource.tool()) {
+            return Err(RpcError::invalid_params().with_message(format!("Resource {} is not available: its tool {} is not offered", uri, resource.tool())));
+        }
+        let target = args::Target { domain: Some(domain), account: None };
+        let (client, domain) = self.get_context(target).await.map_err(|e| RpcError::invalid_params().with_message(e))?;
+        resources::read(&client, &domain, &resource)
+            .await
+            .map_err(|e| RpcError::internal_error().with_message(e.to_string()))
+    }
+
     fn text_result(text: String, is_error: bool) -> CallToolResult {
         CallToolResult {
             content: vec![ContentBlock::TextContent(TextContent::new(text, None, None))],
@@ -141,11 +332,216 @@ impl MigaduMcpServer {
         Self::text_result(msg, true)
     }
 
-    fn success_result(text: String) -> CallToolResult {
-        Self::text_result(text, false)
+    /// A successful result carrying `value` as structured content.
+    fn structured_result<T: Serialize>(text: String, value: &T) -> CallToolResult {
+        CallToolResult {
+            structured_content: serde_json::to_value(value).unwrap().as_object().cloned(),
+            ..Self::text_result(text, false)
+        }
+    }
+
+    /// Runs a tool.
+    async fn call_tool(&self, name: &str, args: Arguments) -> Result<CallToolResult, String> {
+        match name {
+            "configure" => {
+                let args: args::Configure = args::parse(args)?;
+                self.policy.check_domain(&args.domain)?;
+                let account = Account {
+                    client: MigaduClient::new(args.email, args.api_key),
+                    domain: Some(args.domain.clone()),
+                };
+                let account = self.accounts.insert(args.account, account).await;
+                let text = format!("Configured account '{}' for domain: {}", account, args.domain);
+                Ok(Self::structured_result(text, &output::Configured { account, domain: args.domain }))
+            }
+            "list_domains" => {
+                let args: args::AccountFilter = args::parse(args)?;
+                let names = match args.account {
+                    Some(name) => vec![self.accounts.get(Some(&name)).await?.0],
+                    None => self.accounts.names().await,
+                };
+                let mut domains = Vec::new();
+                for name in names {
+                    let (_, account) = self.accounts.get(Some(&name)).await?;
+                    let listed = account
+                        .client
+                        .list_domains()
+                        .await
+                        .map_err(|e| format!("Account '{}': {}", name, e))?;
+                    self.accounts.remember(&name, listed.iter().map(|d| d.name.as_str())).await;
+                    domains.extend(
+                        listed
+                            .into_iter()
+                            .filter(|d| self.policy.check_domain(&d.name).is_ok())
+                            .map(|d| output::DomainSummary {
+                                account: name.clone(),
+                                name: d.name,
+                                description: d.description,
+                                state: d.state,
+                            }),
+                    );
+                }
+                to_result(Ok(output::Domains { domains }))
+            }
+            _ => {
+                let args = args::split_target(args)?;
+                let (client, domain) = self.get_context(args.target).await?;
+                self.call_domain_tool(name, &client, &domain, args.args).await
+            }
+        }
+    }
+
+    /// Runs a tool that acts on `domain`.
+    async fn call_domain_tool(
+        &self,
+        name: &str,
+        client: &MigaduClient,
+        domain: &str,
+        args: Arguments,
+    ) -> Result<CallToolResult, String> {
+        match name {
+            "list_mailboxes" => {
+                let _: args::NoArgs = args::parse(args)?;
+                let mailboxes = client.list_mailboxes(domain).await.map_err(|e| e.to_string())?;
+                let mailboxes = mailboxes
+                    .into_iter()
+                    .map(|m| output::MailboxSummary { address: m.address, name: m.name })
+                    .collect();
+                to_result(Ok(output::Mailboxes { mailboxes }))
+            }
+            "get_mailbox" => {
+                let args: args::LocalPart = args::parse(args)?;
+                to_result(client.get_mailbox(domain, &args.local_part).await)
+            }
+            "create_mailbox" => {
+                let args: args::NewMailbox = args::parse(args)?;
+                to_result(client.create_mailbox(domain, &args.mailbox).await)
+            }
+            "update_mailbox" => {
+                let args: args::MailboxUpdate = args::parse(args)?;
+                to_result(client.update_mailbox(domain, &args.key.local_part, &args.update).await)
+            }
+            "delete_mailbox" => {
+                let args: args::LocalPart = args::parse(args)?;
+                to_result(client.delete_mailbox(domain, &args.local_part).await)
+            }
+
+            "list_aliases" => {
+                let _: args::NoArgs = args::parse(args)?;
+                let aliases = client.list_aliases(domain).await.map_err(|e| e.to_string())?;
+                let aliases = aliases
+                    .into_iter()
+                    .map(|a| output::AliasSummary { address: a.address, destinations: a.destinations })
+                    .collect();
+                to_result(Ok(output::Aliases { aliases }))
+            }
+            "get_alias" => {
+                let args: args::LocalPart = args::parse(args)?;
+                to_result(client.get_alias(domain, &args.local_part).await)
+            }
+            "create_alias" => {
+                let args: args::NewAlias = args::parse(args)?;
+                to_result(client.create_alias(domain, &args.alias).await)
+            }
+            "update_alias" => {
+                let args: args::AliasUpdate = args::parse(args)?;
+                to_result(client.update_alias(domain, &args.key.local_part, &args.update).await)
+            }
+            "delete_alias" => {
+                let args: args::LocalPart = args::parse(args)?;
+                to_result(client.delete_alias(domain, &args.local_part).await)
+            }
+
+            "list_rewrites" => {
+                let _: args::NoArgs = args::parse(args)?;
+                let rewrites = client.list_rewrites(domain).await.map_err(|e| e.to_string())?;
+                let rewrites = rewrites
+                    .into_iter()
+                    .map(|r| output::RewriteSummary {
+                        name: r.name,
+                        local_part_rule: r.local_part_rule,
+                        destinations: r.destinations,
+                    })
+                    .collect();
+                to_result(Ok(output::Rewrites { rewrites }))
+            }
+            "get_rewrite" => {
+                let args: args::RewriteName = args::parse(args)?;
+                to_result(client.get_rewrite(domain, &args.name).await)
+            }
+            "create_rewrite" => {
+                let args: args::NewRewrite = args::parse(args)?;
+                to_result(client.create_rewrite(domain, &args.rewrite).await)
+            }
+            "update_rewrite" => {
+                let args: args::RewriteUpdate = args::parse(args)?;
+                let update = UpdateRewrite {
+                    name: args.new_name,
+                    local_part_rule: args.local_part_rule,
+                    destinations: args.destinations,
+                    order_num: args.order_num,
+                };
+                to_result(client.update_rewrite(domain, &args.key.name, &update).await)
+            }
+            "delete_rewrite" => {
+                let args: args::RewriteName = args::parse(args)?;
+                to_result(client.delete_rewrite(domain, &args.name).await)
+            }
+
+            "list_identities" => {
+                let args: args::Mailbox = args::parse(args)?;
+                to_result(client.list_identities(domain, &args.mailbox).await.map(|identities| output::Identities { identities }))
+            }
+            "get_identity" => {
+                let args: args::Identity = args::parse(args)?;
+                to_result(client.get_identity(domain, &args.mailbox, &args.local_part).await)
+            }
+            "create_identity" => {
+                let args: args::NewIdentity = args::parse(args)?;
+                to_result(client.create_identity(domain, &args.key.mailbox, &args.identity).await)
+            }
+            "update_identity" => {
+                let args: args::IdentityUpdate = args::parse(args)?;
+                to_result(client.update_identity(domain, &args.key.mailbox, &args.key.local_part, &args.update).await)
+            }
+            "delete_identity" => {
+                let args: args::Identity = args::parse(args)?;
+                to_result(client.delete_identity(domain, &args.mailbox, &args.local_part).await)
+            }
+
+            "list_forwardings" => {
+                let args: args::Mailbox = args::parse(args)?;
+                to_result(client.list_forwardings(domain, &args.mailbox).await.map(|forwardings| output::Forwardings { forwardings }))
+            }
+            "get_forwarding" => {
+                let args: args::Forwarding = args::parse(args)?;
+                to_result(client.get_forwarding(domain, &args.mailbox, &args.address).await)
+            }
+            "create_forwarding" => {
+                let args: args::NewForwarding = args::parse(args)?;
+                to_result(client.create_forwarding(domain, &args.key.mailbox, &args.forwarding).await)
+            }
+            "update_forwarding" => {
+                let args: args::ForwardingUpdate = args::parse(args)?;
+                to_result(client.update_forwarding(domain, &args.key.mailbox, &args.key.address, &args.update).await)
+            }
+            "delete_forwarding" => {
+                let args: args::Forwarding = args::parse(args)?;
+                to_result(client.delete_forwarding(domain, &args.mailbox, &args.address).await)
+            }
+
+            _ => Err(format!("Unknown tool: {}", name)),
+        }
     }
 }
 
+/// Returns the value both as structured content and as JSON text, for clients that only
+/// read text.
+fn to_result<T: Serialize>(result: migadu_client::Result<T>) -> Result<CallToolResult, String> {
+    let value = result.map_err(|e| e.to_string())?;
+    Ok(MigaduMcpServer::structured_result(serde_json::to_string_pretty(&value).unwrap(), &value))
+}
+
 #[async_trait]
 impl ServerHandler for MigaduMcpServer {
     async fn handle_list_tools_request(
@@ -154,7 +550,7 @@ impl ServerHandler for MigaduMcpServer {
         _runtime: Arc<dyn McpServer>,
     ) -> Result<ListToolsResult, RpcError> {
         Ok(ListToolsResult {
-            tools: Self::tools(),
+            tools: self.tools(),
             next_cursor: None,
             meta: None,
         })
@@ -168,115 +564,86 @@ impl ServerHandler for MigaduMcpServer {
         let args = request.params.arguments.unwrap_or_default();
         let tool_name = &request.params.name;
 
-        match tool_name.as_str() {
-            "configure" => {
-                let email = args.get("email").and_then(|v| v.as_str()).unwrap_or("");
-                let api_key = args.get("api_key").and_then(|v| v.as_str()).unwrap_or("");
-                let domain = args.get("domain").and_then(|v| v.as_str()).unwrap_or("");
-
-                if email.is_empty() || api_key.is_empty() || domain.is_empty() {
-                    return Ok(Self::error_result("Missing required parameters: email, api_key, domain".to_string()));
-                }
-
-                *self.client.write().await = Some(MigaduClient::new(email, api_key));
-                *self.domain.write().await = Some(domain.to_string());
-
-                Ok(Self::success_result(format!("Configured Migadu client for domain: {}", domain)))
-            }
-
-            "list_mailboxes" => {
-                let client = match self.get_client().await {
-                    Ok(c) => c,
-                    Err(e) => return Ok(Self::error_result(e)),
-                };
-                let domain = match self.get_domain().await {
-                    Ok(d) => d,
-                    Err(e) => return Ok(Self::error_result(e)),
-                };
-
-                match client.list_mailboxes(&domain).await {
-                    Ok(mailboxes) => {
-                        let result: Vec<_> = mailboxes
-                            .iter()
-                            .map(|m| serde_json::json!({"address": m.address, "name": m.name}))
-                            .collect();
-                        Ok(Self::success_result(serde_json::to_string_pretty(&result).unwrap()))
-                    }
-                    Err(e) => Ok(Self::error_result(e.to_string())),
-                }
-            }
-
-            "get_mailbox" => {
-                let local_part = args.get("local_part").and_then(|v| v.as_str()).unwrap_or("");
-                if local_part.is_empty() {
-                    return Ok(Self::error_result("Missing required parameter: local_part".to_string()));
-                }
-
-                let client = match self.get_client().await {
-                    Ok(c) => c,
-                    Err(e) => return Ok(Self::error_result(e)),
-                };
-                let domain = match self.get_domain().await {
-                    Ok(d) => d,
-                    Err(e) => return Ok(Self::error_result(e)),
-                };
+        if !self.tools().iter().any(|t| &t.name == tool_name) {
+            return Ok(CallToolError::unknown_tool(format!("Unknown tool: {}", tool_name)).into());
+        }
+        match self.call_tool(tool_name, args).await {
+            Ok(result) => Ok(result),
+            Err(e) => Ok(Self::error_result(e)),
+        }
+    }
 
-                match client.get_mailbox(&domain, local_part).await {
-                    Ok(mailbox) => Ok(Self::success_result(serde_json::to_string_pretty(&mailbox).unwrap())),
-                    Err(e) => Ok(Self::error_result(e.to_string())),
-                }
-            }
+    async fn handle_list_resources_request(
+        &self,
+        _request: ListResourcesRequest,
+        _runtime: Arc<dyn McpServer>,
+    ) -> Result<ListResourcesResult, RpcError> {
+        let resources = self.list_resources().await.map_err(|e| RpcError::internal_error().with_message(e))?;
+        Ok(ListResourcesResult {
+            resources,
+            next_cursor: None,
+            meta: None,
+        })
+    }
 
-            "list_aliases" => {
-                let client = match self.get_client().await {
-                    Ok(c) => c,
-                    Err(e) => return Ok(Self::error_result(e)),
-                };
-                let domain = match self.get_domain().await {
-                    Ok(d) => d,
-                    Err(e) => return Ok(Self::error_result(e)),
-                };
+    async fn handle_list_resource_templates_request(
+        &self,
+        _request: ListResourceTemplatesRequest,
+        _runtime: Arc<dyn McpServer>,
+    ) -> Result<ListResourceTemplatesResult, RpcError> {
+        let tools = self.tools();
+        Ok(ListResourceTemplatesResult {
+            resource_templates: resources::templates(|name| Self::allows(&tools, name)),
+            next_cursor: None,
+            meta: None,
+        })
+    }
 
-                match client.list_aliases(&domain).await {
-                    Ok(aliases) => {
-                        let result: Vec<_> = aliases
-                            .iter()
-                            .map(|a| serde_json::json!({"address": a.address, "destinations": a.destinations}))
-                            .collect();
-                        Ok(Self::success_result(serde_json::to_string_pretty(&result).unwrap()))
-                    }
-                    Err(e) => Ok(Self::error_result(e.to_string())),
-                }
+    async fn handle_read_resource_request(
+        &self,
+        request: ReadResourceRequest,
+        _runtime: Arc<dyn McpServer>,
+    ) -> Result<ReadResourceResult, RpcError> {
+        let uri = request.params.uri;
+        let text = self.read_resource(&uri).await?;
+        Ok(ReadResourceResult {
+            contents: vec![TextResourceContents {
+                meta: None,
+                mime_type: Some(resources::MIME_TYPE.to_string()),
+                text,
+                uri,
             }
+            .into()],
+            meta: None,
+        })
+    }
 
-            "list_rewrites" => {
-                let client = match self.get_client().await {
-                    Ok(c) => c,
-                    Err(e) => return Ok(Self::error_result(e)),
-                };
-                let domain = match self.get_domain().await {
-                    Ok(d) => d,
-                    Err(e) => return Ok(Self::error_result(e)),
-                };
-
-                match client.list_rewrites(&domain).await {
-                    Ok(rewrites) => {
-                        let result: Vec<_> = rewrites
-                            .iter()
-                            .map(|r| serde_json::json!({
-                                "name": r.name,
-                                "local_part_rule": r.local_part_rule,
-                                "destinations": r.destinations
-                            }))
-                            .collect();
-                        Ok(Self::success_result(serde_json::to_string_pretty(&result).unwrap()))
-                    }
-                    Err(e) => Ok(Self::error_result(e.to_string())),
-                }
-            }
+    async fn handle_list_prompts_request(
+        &self,
+        _request: ListPromptsRequest,
+        _runtime: Arc<dyn McpServer>,
+    ) -> Result<ListPromptsResult, RpcError> {
+        Ok(ListPromptsResult {
+            prompts: self.workflows().iter().map(|w| w.prompt()).collect(),
+            next_cursor: None,
+            meta: None,
+        })
+    }
 
-            _ => Ok(CallToolError::unknown_tool(format!("Unknown tool: {}", tool_name)).into()),
-        }
+    async fn handle_get_prompt_request(
+        &self,
+        request: GetPromptRequest,
+        _runtime: Arc<dyn McpServer>,
+    ) -> Result<GetPromptResult, RpcError> {
+        let name = &request.params.name;
+        let workflow = self
+            .workflows()
+            .into_iter()
+            .find(|w| w.name == name)
+            .ok_or_else(|| RpcError::invalid_params().with_message(format!("Unknown prompt: {}", name)))?;
+        workflow
+            .render(request.params.arguments.unwrap_or_default())
+            .map_err(|e| RpcError::invalid_params().with_message(e))
     }
 }
 
@@ -286,9 +653,16 @@ fn server_info() -> InitializeResult {
             tools: Some(ServerCapabilitiesTools {
                 list_changed: Some(false),
             }),
+            resources: Some(ServerCapabilitiesResources {
+                list_changed: Some(false),
+                subscribe: Some(false),
+            }),
+            prompts: Some(ServerCapabilitiesPrompts {
+                list_changed: Some(false),
+            }),
             ..Default::default()
         },
-        instructions: Some("Migadu email hosting API server. Configure with MIGADU_EMAIL, MIGADU_API_KEY, and MIGADU_DOMAIN environment variables, or use the 'configure' tool.".into()),
+        instructions: Some("Migadu email hosting API server. Tools act on the default account's domain unless given 'domain' (and optionally 'account'); call 'list_domains' to see every domain the server can manage.".into()),
         meta: None,
         protocol_version: rust_mcp_sdk::schema::LATEST_PROTOCOL_VERSION.to_string(),
         server_info: Implementation {
@@ -301,11 +675,534 @@ fn server_info() -> InitializeResult {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let handler = MigaduMcpServer::new();
-    let transport = StdioTransport::new(TransportOptions::default())?;
+    let mut cli = Cli::parse();
+    if let Err(e) = cli.policy.validate(&MigaduMcpServer::all_tools()) {
+        eprintln!("Error: {}", e);
+        process::exit(2);
+    }
+    let accounts = match Accounts::load(&cli.accounts) {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(2);
+        }
+    };
 
-    let server = server_runtime::create_server(server_info(), transport, handler);
-    server.start().await?;
+    match cli.transport {
+        Transport::Stdio => {
+            let handler = MigaduMcpServer::new(accounts, cli.policy);
+            let transport = StdioTransport::new(TransportOptions::default())?;
+            let server = server_runtime::create_server(server_info(), transport, handler);
+            server.start().await?;
+        }
+        Transport::Http => {
+            let token = match cli.http.token() {
+                Ok(token) => token,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(2);
+                }
+            };
+            // Every client shares the server's settings, so one client's `configure`
+            // would change them for all the others.
+            cli.policy.deny_tools.push("configure".to_string());
+            let handler = MigaduMcpServer::new(accounts, cli.policy);
+            http::serve(server_info(), handler, cli.http.listen, token).await?;
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// A server limited to example.com, whose only account works on `domain` and cannot
+    /// reach the API.
+    fn server(domain: &str) -> MigaduMcpServer {
+        let policy = Policy { allow_domains: vec!["Example.com".to_string()], ..Default::default() };
+        server_with(domain, policy)
+    }
+
+    fn server_with(domain: &str, policy: Policy) -> MigaduMcpServer {
+        server_using(MigaduClient::with_base_url("admin@example.com", "api-key", "http://127.0.0.1:9"), domain, policy)
+    }
+
+    fn server_using(client: MigaduClient, domain: &str, policy: Policy) -> MigaduMcpServer {
+        let account = Account { client, domain: Some(domain.to_string()) };
+        let accounts = Accounts::new("default".to_string(), BTreeMap::from([("default".to_string(), account)]));
+        MigaduMcpServer::new(accounts, policy)
+    }
+
+    fn arguments(value: serde_json::Value) -> Arguments {
+        value.as_object().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_are_refused_outside_allowed_domains() {
+        let named = arguments(serde_json::json!({"domain": "other.org"}));
+        let error = server("example.com").call_tool("list_mailboxes", named).await.unwrap_err();
+        assert!(error.starts_with("Domain other.org is not allowed"), "{}", error);
+
+        let error = server("other.org").call_tool("list_mailboxes", Arguments::new()).await.unwrap_err();
+        assert!(error.starts_with("Domain other.org is not allowed"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_path_traversal_is_refused() {
+        let server = server("example.com");
+        for (tool, args) in [
+            ("get_mailbox", serde_json::json!({"local_part": "../../other.org/mailboxes/bob"})),
+            ("delete_mailbox", serde_json::json!({"local_part": ".."})),
+            ("update_alias", serde_json::json!({"local_part": "info/..", "destinations": "a@example.com"})),
+            ("delete_rewrite", serde_json::json!({"name": "."})),
+            ("list_identities", serde_json::json!({"mailbox": "..\\..\\other.org"})),
+            ("delete_forwarding", serde_json::json!({"mailbox": "alice", "address": "../x"})),
+            ("list_mailboxes", serde_json::json!({"domain": "example.com/../other.org"})),
+        ] {
+            let error = server.call_tool(tool, arguments(args)).await.unwrap_err();
+            assert!(error.starts_with("Invalid"), "{}: {}", tool, error);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_arguments_are_refused() {
+        let server = server("example.com");
+        let misspelled = arguments(serde_json::json!({"domain": "example.com", "local_part": "alice", "may_sendd": false}));
+        let error = server.call_tool("update_mailbox", misspelled).await.unwrap_err();
+        assert_eq!(error, "Invalid arguments: unknown argument `may_sendd`");
+
+        let error = server.call_tool("list_domains", arguments(serde_json::json!({"acount": "work"}))).await.unwrap_err();
+        assert_eq!(error, "Invalid arguments: unknown argument `acount`");
+
+        let known = arguments(serde_json::json!({"domain": "example.com", "local_part": "alice", "may_send": false}));
+        let error = server.call_tool("update_mailbox", known).await.unwrap_err();
+        assert!(error.starts_with("HTTP request failed"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_allowed_domains_ignore_case() {
+        let named = arguments(serde_json::json!({"domain": "EXAMPLE.com"}));
+        let error = server("example.com").call_tool("list_mailboxes", named).await.unwrap_err();
+        // The call gets past the policy and fails to reach the API.
+        assert!(error.starts_with("HTTP request failed"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_resource_reads_are_refused_outside_allowed_domains() {
+        let server = server("example.com");
+        let error = server.read_resource("migadu://other.org/mailboxes").await.unwrap_err();
+        assert!(error.message.starts_with("Domain other.org is not allowed"), "{}", error.message);
+
+        let error = server.read_resource("migadu://EXAMPLE.COM/mailboxes").await.unwrap_err();
+        assert!(error.message.starts_with("HTTP request failed"), "{}", error.message);
+    }
+
+    #[tokio::test]
+    async fn test_resources_follow_the_tool_lists() {
+        let policy = Policy { deny_tools: vec!["get_mailbox".to_string()], ..Default::default() };
+        let server = server_with("example.com", policy);
+        let error = server.read_resource("migadu://example.com/mailboxes/alice").await.unwrap_err();
+        assert_eq!(error.message, "Resource migadu://example.com/mailboxes/alice is not available: its tool get_mailbox is not offered");
+
+        let error = server.read_resource("migadu://example.com/mailboxes/alice/identities").await.unwrap_err();
+        assert!(error.message.starts_with("HTTP request failed"), "{}", error.message);
+    }
+
+    /// A client for an API that answers a GET of each path in `routes` with its body,
+    /// and anything else with 404.
+    async fn routed_client(routes: Vec<(&'static str, String)>) -> MigaduClient {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut data = Vec::new();
+                let mut buf = [0; 4096];
+                while !data.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    assert!(n > 0, "connection closed mid-request");
+                    data.extend_from_slice(&buf[..n]);
+                }
+                let head = String::from_utf8_lossy(&data).to_string();
+                let path = head.split(' ').nth(1).unwrap().to_string();
+                let (status, body) = match routes.iter().find(|(p, _)| *p == path) {
+                    Some((_, body)) => ("200 OK", body.clone()),
+                    None => ("404 Not Found", r#"{"error":"not found"}"#.to_string()),
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+        MigaduClient::with_base_url("admin@example.com", "api-key", base_url)
+    }
+
+    #[tokio::test]
+    async fn test_resources_are_listed_for_every_allowed_domain_of_every_account() {
+        let domains = |names: &[&str]| {
+            let domains: Vec<_> = names.iter().map(|n| serde_json::json!({"name": n})).collect();
+            serde_json::json!({ "domains": domains }).to_string()
+        };
+        let mailboxes = |domain: &str| {
+            serde_json::json!({"mailboxes": [{"local_part": "alice", "domain_name": domain, "address": format!("alice@{}", domain), "name": "Alice"}]}).to_string()
+        };
+        let main = routed_client(vec![
+            ("/domains", domains(&["example.com", "hidden.example"])),
+            ("/domains/example.com/mailboxes", mailboxes("example.com")),
+        ])
+        .await;
+        let work = routed_client(vec![
+            ("/domains", domains(&["shop.example"])),
+            ("/domains/shop.example/mailboxes", mailboxes("shop.example")),
+        ])
+        .await;
+        let accounts = Accounts::new(
+            "default".to_string(),
+            BTreeMap::from([
+                ("default".to_string(), Account { client: main, domain: Some("example.com".to_string()) }),
+                ("work".to_string(), Account { client: work, domain: None }),
+            ]),
+        );
+        let policy = Policy {
+            allow_domains: vec!["example.com".to_string(), "shop.example".to_string()],
+            deny_tools: vec!["list_rewrites".to_string()],
+            ..Default::default()
+        };
+        let server = MigaduMcpServer::new(accounts, policy);
+
+        let uris: Vec<_> = server.list_resources().await.unwrap().into_iter().map(|r| r.uri).collect();
+        assert_eq!(
+            uris,
+            [
+                "migadu://example.com/mailboxes",
+                "migadu://example.com/aliases",
+                "migadu://example.com/mailboxes/alice",
+                "migadu://shop.example/mailboxes",
+                "migadu://shop.example/aliases",
+                "migadu://shop.example/mailboxes/alice",
+            ]
+        );
+        // Listing taught the server which account manages each domain.
+        let text = server.read_resource("migadu://shop.example/mailboxes").await.unwrap();
+        assert!(text.contains("alice@shop.example"), "{}", text);
+    }
+
+    /// A request received by the API of [`recording_server`]: method, path and body.
+    type Request = (String, String, Option<serde_json::Value>);
+
+    /// A server on example.com whose API records every request and answers it with an
+    /// object that reads as any resource.
+    async fn recording_server() -> (MigaduMcpServer, Arc<Mutex<Vec<Request>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            let resource = r#"{"local_part":"x","domain_name":"example.com","address":"x@example.com","name":"X","local_part_rule":"x-*","destinations":[]}"#;
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut data = Vec::new();
+                let mut buf = [0; 4096];
+                let (head, body) = loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    assert!(n > 0, "connection closed mid-request");
+                    data.extend_from_slice(&buf[..n]);
+                    let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") else {
+                        continue;
+                    };
+                    let head = String::from_utf8_lossy(&data[..end]).to_string();
+                    let length = head
+                        .lines()
+                        .find_map(|l| {
+                            l.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|n| n.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if data.len() >= end + 4 + length {
+                        break (head, data[end + 4..end + 4 + length].to_vec());
+                    }
+                };
+                let mut request_line = head.lines().next().unwrap().split(' ');
+                let method = request_line.next().unwrap().to_string();
+                let path = request_line.next().unwrap().to_string();
+                let body = (!body.is_empty()).then(|| serde_json::from_slice(&body).unwrap());
+                recorded.lock().unwrap().push((method, path, body));
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    resource.len(),
+                    resource
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+        let client = MigaduClient::with_base_url("admin@example.com", "api-key", base_url);
+        (
+            server_using(client, "example.com", Policy::default()),
+            requests,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_each_tool_sends_its_request() {
+        use serde_json::json;
+
+        let (server, requests) = recording_server().await;
+        let cases = [
+            (
+                "get_mailbox",
+                json!({"local_part": "alice"}),
+                "GET",
+                "/domains/example.com/mailboxes/alice",
+                None,
+            ),
+            (
+                "create_mailbox",
+                json!({"local_part": "bob", "name": "Bob", "password": "s3cret"}),
+                "POST",
+                "/domains/example.com/mailboxes",
+                Some(json!({"local_part": "bob", "name": "Bob", "password": "s3cret"})),
+            ),
+            (
+                "update_mailbox",
+                json!({"local_part": "alice", "may_send": false}),
+                "PUT",
+                "/domains/example.com/mailboxes/alice",
+                Some(json!({"may_send": false})),
+            ),
+            (
+                "delete_mailbox",
+                json!({"local_part": "alice"}),
+                "DELETE",
+                "/domains/example.com/mailboxes/alice",
+                None,
+            ),
+            (
+                "get_alias",
+                json!({"local_part": "info"}),
+                "GET",
+                "/domains/example.com/aliases/info",
+                None,
+            ),
+            (
+                "create_alias",
+                json!({"local_part": "info", "destinations": "a@example.com,b@example.com"}),
+                "POST",
+                "/domains/example.com/aliases",
+                Some(json!({"local_part": "info", "destinations": "a@example.com,b@example.com"})),
+            ),
+            (
+                "update_alias",
+                json!({"local_part": "info", "is_internal": true}),
+                "PUT",
+                "/domains/example.com/aliases/info",
+                Some(json!({"is_internal": true})),
+            ),
+            (
+                "delete_alias",
+                json!({"local_part": "info"}),
+                "DELETE",
+                "/domains/example.com/aliases/info",
+                None,
+            ),
+            (
+                "get_rewrite",
+                json!({"name": "support"}),
+                "GET",
+                "/domains/example.com/rewrites/support",
+                None,
+            ),
+            (
+                "create_rewrite",
+                json!({"name": "support", "local_part_rule": "support-*", "destinations": "a@example.com"}),
+                "POST",
+                "/domains/example.com/rewrites",
+                Some(
+                    json!({"name": "support", "local_part_rule": "support-*", "destinations": "a@example.com"}),
+                ),
+            ),
+            (
+                "update_rewrite",
+                json!({"name": "support", "new_name": "help", "order_num": 2}),
+                "PUT",
+                "/domains/example.com/rewrites/support",
+                Some(json!({"name": "help", "order_num": 2})),
+            ),
+            (
+                "delete_rewrite",
+                json!({"name": "support"}),
+                "DELETE",
+                "/domains/example.com/rewrites/support",
+                None,
+            ),
+            (
+                "get_identity",
+                json!({"mailbox": "alice", "local_part": "sales"}),
+                "GET",
+                "/domains/example.com/mailboxes/alice/identities/sales",
+                None,
+            ),
+            (
+                "create_identity",
+                json!({"mailbox": "alice", "local_part": "sales", "name": "Sales"}),
+                "POST",
+                "/domains/example.com/mailboxes/alice/identities",
+                Some(json!({"local_part": "sales", "name": "Sales"})),
+            ),
+            (
+                "update_identity",
+                json!({"mailbox": "alice", "local_part": "sales", "name": "Sales team"}),
+                "PUT",
+                "/domains/example.com/mailboxes/alice/identities/sales",
+                Some(json!({"name": "Sales team"})),
+            ),
+            (
+                "delete_identity",
+                json!({"mailbox": "alice", "local_part": "sales"}),
+                "DELETE",
+                "/domains/example.com/mailboxes/alice/identities/sales",
+                None,
+            ),
+            (
+                "get_forwarding",
+                json!({"mailbox": "alice", "address": "a@home.example"}),
+                "GET",
+                "/domains/example.com/mailboxes/alice/forwardings/a@home.example",
+                None,
+            ),
+            (
+                "create_forwarding",
+                json!({"mailbox": "alice", "address": "a@home.example"}),
+                "POST",
+                "/domains/example.com/mailboxes/alice/forwardings",
+                Some(json!({"address": "a@home.example"})),
+            ),
+            (
+                "update_forwarding",
+                json!({"mailbox": "alice", "address": "a@home.example", "is_active": false}),
+                "PUT",
+                "/domains/example.com/mailboxes/alice/forwardings/a@home.example",
+                Some(json!({"is_active": false})),
+            ),
+            (
+                "delete_forwarding",
+                json!({"mailbox": "alice", "address": "a@home.example"}),
+                "DELETE",
+                "/domains/example.com/mailboxes/alice/forwardings/a@home.example",
+                None,
+            ),
+        ];
+        for (tool, args, method, path, body) in cases {
+            let result = server.call_tool(tool, arguments(args)).await;
+            assert!(
+                result.is_ok_and(|r| r.is_error == Some(false)),
+                "{} failed",
+                tool
+            );
+            let request = requests.lock().unwrap().pop().unwrap();
+            assert_eq!(
+                request,
+                (method.to_string(), path.to_string(), body),
+                "{}",
+                tool
+            );
+        }
+    }
+
+    /// Checks `value` against the parts of JSON Schema that output schemas use: types,
+    /// properties, required properties, array items and `anyOf`.
+    fn check_schema(value: &serde_json::Value, schema: &serde_json::Value, at: &str) -> Result<(), String> {
+        if let Some(any_of) = schema.get("anyOf").and_then(|a| a.as_array()) {
+            if !any_of.iter().any(|s| check_schema(value, s, at).is_ok()) {
+                return Err(format!("{}: {} matches no alternative", at, value));
+            }
+        }
+        if let Some(types) = schema.get("type") {
+            let types: Vec<&str> = match types {
+                serde_json::Value::Array(types) => types.iter().filter_map(|t| t.as_str()).collect(),
+                other => vec![other.as_str().unwrap()],
+            };
+            let matches = |t: &&str| match *t {
+                "null" => value.is_null(),
+                "boolean" => value.is_boolean(),
+                "integer" => value.is_i64() || value.is_u64(),
+                "number" => value.is_number(),
+                "string" => value.is_string(),
+                "array" => value.is_array(),
+                "object" => value.is_object(),
+                other => panic!("Unexpected type {}", other),
+            };
+            if !types.iter().any(matches) {
+                return Err(format!("{}: {} is not {:?}", at, value, types));
+            }
+        }
+        if let Some(object) = value.as_object() {
+            for name in schema.get("required").and_then(|r| r.as_array()).into_iter().flatten() {
+                let name = name.as_str().unwrap();
+                if !object.contains_key(name) {
+                    return Err(format!("{}: missing required {}", at, name));
+                }
+            }
+            let properties = schema.get("properties").and_then(|p| p.as_object());
+            for (name, property) in properties.into_iter().flatten() {
+                if let Some(field) = object.get(name) {
+                    check_schema(field, property, &format!("{}.{}", at, name))?;
+                }
+            }
+        }
+        if let (Some(items), Some(schema)) = (value.as_array(), schema.get("items")) {
+            for (index, item) in items.iter().enumerate() {
+                check_schema(item, schema, &format!("{}[{}]", at, index))?;
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_structured_content_matches_the_output_schema() {
+        use serde_json::json;
+
+        let (server, _) = recording_server().await;
+        let tools = MigaduMcpServer::all_tools();
+        let calls = [
+            ("configure", json!({"email": "admin@example.com", "api_key": "key", "domain": "example.com", "account": "work"})),
+            ("get_mailbox", json!({"account": "default", "local_part": "x"})),
+            ("get_alias", json!({"account": "default", "local_part": "x"})),
+            ("get_rewrite", json!({"account": "default", "name": "x"})),
+            ("get_identity", json!({"account": "default", "mailbox": "x", "local_part": "x"})),
+        ];
+        for (tool, args) in calls {
+            let result = server.call_tool(tool, arguments(args)).await.unwrap();
+            let content = serde_json::Value::Object(result.structured_content.expect(tool));
+            let schema = tools.iter().find(|t| t.name == tool).unwrap().output_schema.as_ref().unwrap();
+            check_schema(&content, &serde_json::to_value(schema).unwrap(), tool).unwrap();
+
+            let ContentBlock::TextContent(text) = &result.content[0] else { panic!("{} returned no text", tool) };
+            assert_eq!(serde_json::from_str::<serde_json::Value>(&text.text).ok(), (tool != "configure").then_some(content));
+        }
+    }
+
+    #[test]
+    fn test_schema_check_rejects_mismatches() {
+        let schema = serde_json::to_value(output::output_schema::<output::Aliases>()).unwrap();
+        let valid = serde_json::json!({"aliases": [{"address": "info@example.com", "destinations": ["a@example.com"]}]});
+        assert_eq!(check_schema(&valid, &schema, "aliases"), Ok(()));
+        let invalid = serde_json::json!({"aliases": [{"address": "info@example.com", "destinations": "a@example.com"}]});
+        assert!(check_schema(&invalid, &schema, "aliases").is_err());
+        assert!(check_schema(&serde_json::json!({}), &schema, "aliases").is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! Typed tool results, from which tool output schemas are generated.
+//!
+//! Structured content must be a JSON object, so lists are wrapped in one.
+
+use migadu_client::{Forwarding, Identity};
+use rust_mcp_sdk::schema::ToolOutputSchema;
+use schemars::generate::SchemaSettings;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::args;
+
+/// Builds the output schema of a tool returning `T`.
+pub fn output_schema<T: JsonSchema>() -> ToolOutputSchema {
+    let schema = SchemaSettings::draft2020_12()
+        .for_serialize()
+        .with(|s| s.inline_subschemas = true)
+        .into_generator()
+        .into_root_schema_for::<T>();
+    let (required, properties) = args::object_parts(&schema);
+    ToolOutputSchema::new(required, properties)
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct Configured {
+    /// Account that was added or replaced
+    pub account: String,
+    /// Domain tools operate on when a call names none
+    pub domain: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DomainSummary {
+    /// Account that manages the domain
+    pub account: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub state: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct Domains {
+    pub domains: Vec<DomainSummary>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct MailboxSummary {
+    pub address: String,
+    pub name: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct Mailboxes {
+    pub mailboxes: Vec<MailboxSummary>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct AliasSummary {
+    pub address: String,
+    pub destinations: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct Aliases {
+    pub aliases: Vec<AliasSummary>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct RewriteSummary {
+    pub name: String,
+    pub local_part_rule: String,
+    pub destinations: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct Rewrites {
+    pub rewrites: Vec<RewriteSummary>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct Identities {
+    pub identities: Vec<Identity>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct Forwardings {
+    pub forwardings: Vec<Forwarding>,
+}
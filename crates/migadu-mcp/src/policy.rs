@@ -0,0 +1,172 @@
+//! Limits on which tools the server offers and which domains they may touch.
+
+use clap::Args;
+use rust_mcp_sdk::schema::Tool;
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct Policy {
+    /// Hide every tool that changes the account, and `configure`
+    #[arg(long, env = "MIGADU_MCP_READ_ONLY")]
+    pub read_only: bool,
+
+    /// Only offer these tools (comma-separated)
+    #[arg(long, env = "MIGADU_MCP_ALLOW_TOOLS", value_delimiter = ',')]
+    pub allow_tools: Vec<String>,
+
+    /// Never offer these tools (comma-separated)
+    #[arg(long, env = "MIGADU_MCP_DENY_TOOLS", value_delimiter = ',')]
+    pub deny_tools: Vec<String>,
+
+    /// Only operate on these domains (comma-separated)
+    #[arg(long, env = "MIGADU_MCP_ALLOW_DOMAINS", value_delimiter = ',')]
+    pub allow_domains: Vec<String>,
+}
+
+impl Policy {
+    /// Checks that the allow and deny lists only name tools that exist, so a typo
+    /// cannot silently leave a tool enabled.
+    pub fn validate(&self, tools: &[Tool]) -> Result<(), String> {
+        for (flag, names) in [
+            ("--allow-tools", &self.allow_tools),
+            ("--deny-tools", &self.deny_tools),
+        ] {
+            if let Some(unknown) = names.iter().find(|n| !tools.iter().any(|t| &t.name == *n)) {
+                return Err(format!("Unknown tool in {}: {}", flag, unknown));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the server may offer `tool`.
+    pub fn allows_tool(&self, tool: &Tool) -> bool {
+        let read_only = tool
+            .annotations
+            .as_ref()
+            .and_then(|a| a.read_only_hint)
+            .unwrap_or(false);
+        if self.read_only && !read_only {
+            return false;
+        }
+        if !self.allow_tools.is_empty() && !self.allow_tools.contains(&tool.name) {
+            return false;
+        }
+        !self.deny_tools.contains(&tool.name)
+    }
+
+    /// Fails unless tools may operate on `domain`.
+    pub fn check_domain(&self, domain: &str) -> Result<(), String> {
+        if self.allow_domains.is_empty()
+            || self
+                .allow_domains
+                .iter()
+                .any(|d| d.eq_ignore_ascii_case(domain))
+        {
+            return Ok(());
+        }
+        Err(format!(
+            "Domain {} is not allowed. This server may only operate on: {}",
+            domain,
+            self.allow_domains.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MigaduMcpServer;
+
+    fn allowed(policy: &Policy) -> Vec<String> {
+        MigaduMcpServer::all_tools()
+            .into_iter()
+            .filter(|t| policy.allows_tool(t))
+            .map(|t| t.name)
+            .collect()
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_read_only_hides_every_tool_that_changes_the_account() {
+        let policy = Policy {
+            read_only: true,
+            ..Default::default()
+        };
+        let allowed = allowed(&policy);
+        assert!(!allowed.contains(&"configure".to_string()));
+        for tool in MigaduMcpServer::all_tools() {
+            let changes = ["create_", "update_", "delete_"]
+                .iter()
+                .any(|p| tool.name.starts_with(p));
+            let reads = ["list_", "get_"].iter().any(|p| tool.name.starts_with(p));
+            if changes {
+                assert!(
+                    !allowed.contains(&tool.name),
+                    "{} is offered in read-only mode",
+                    tool.name
+                );
+            } else if reads {
+                assert!(
+                    allowed.contains(&tool.name),
+                    "{} is hidden in read-only mode",
+                    tool.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_deny_beats_allow() {
+        let policy = Policy {
+            allow_tools: names(&["get_mailbox", "delete_mailbox"]),
+            deny_tools: names(&["delete_mailbox"]),
+            ..Default::default()
+        };
+        assert_eq!(allowed(&policy), ["get_mailbox"]);
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_tools() {
+        let tools = MigaduMcpServer::all_tools();
+        let policy = Policy {
+            allow_tools: names(&["get_mailbox", "get_mailbx"]),
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.validate(&tools).unwrap_err(),
+            "Unknown tool in --allow-tools: get_mailbx"
+        );
+
+        let policy = Policy {
+            deny_tools: names(&["delete_mailboxes"]),
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.validate(&tools).unwrap_err(),
+            "Unknown tool in --deny-tools: delete_mailboxes"
+        );
+
+        let policy = Policy {
+            allow_tools: names(&["get_mailbox"]),
+            deny_tools: names(&["delete_alias"]),
+            ..Default::default()
+        };
+        assert_eq!(policy.validate(&tools), Ok(()));
+    }
+
+    #[test]
+    fn test_domains_are_compared_case_insensitively() {
+        let policy = Policy {
+            allow_domains: names(&["Example.com"]),
+            ..Default::default()
+        };
+        assert_eq!(policy.check_domain("example.COM"), Ok(()));
+        assert_eq!(
+            policy.check_domain("other.org").unwrap_err(),
+            "Domain other.org is not allowed. This server may only operate on: Example.com"
+        );
+        assert_eq!(Policy::default().check_domain("other.org"), Ok(()));
+    }
+}
@@ -0,0 +1,415 @@
+//! Prompt templates that walk the model through common admin workflows.
+
+use std::collections::HashMap;
+
+use rust_mcp_sdk::schema::{
+    ContentBlock, GetPromptResult, Prompt, PromptArgument, PromptMessage, Role, TextContent,
+};
+
+/// A prompt, the tools its steps call and how to render it.
+pub struct Workflow {
+    pub name: &'static str,
+    title: &'static str,
+    description: &'static str,
+    /// Name, description and whether it is required.
+    arguments: &'static [(&'static str, &'static str, bool)],
+    /// Tools the steps call; the prompt is only offered if all of them are.
+    pub tools: &'static [&'static str],
+    render: fn(&Arguments) -> String,
+}
+
+type Arguments = HashMap<String, String>;
+
+/// Every prompt takes the domain to work on.
+const DOMAIN: (&str, &str, bool) = (
+    "domain",
+    "Domain to work on [default: the account's domain]",
+    false,
+);
+
+pub const WORKFLOWS: &[Workflow] = &[
+    Workflow {
+        name: "onboard_employee",
+        title: "Onboard employee",
+        description: "Create a mailbox for a new employee and add them to team aliases",
+        arguments: &[
+            (
+                "local_part",
+                "Local part of the new address (before @)",
+                true,
+            ),
+            ("name", "Full name of the employee", true),
+            (
+                "recovery_email",
+                "Personal address the employee sets their password from",
+                false,
+            ),
+            (
+                "aliases",
+                "Comma-separated local parts of aliases to join",
+                false,
+            ),
+        ],
+        tools: &["get_mailbox", "create_mailbox", "get_alias", "update_alias"],
+        render: onboard,
+    },
+    Workflow {
+        name: "offboard_employee",
+        title: "Offboard employee",
+        description:
+            "Lock a departing employee's mailbox, hand over their mail and remove them from aliases",
+        arguments: &[
+            (
+                "local_part",
+                "Local part of the employee's address (before @)",
+                true,
+            ),
+            (
+                "successor",
+                "Address that should receive their mail from now on",
+                false,
+            ),
+        ],
+        tools: &[
+            "get_mailbox",
+            "update_mailbox",
+            "list_aliases",
+            "update_alias",
+            "list_identities",
+            "list_forwardings",
+            "create_forwarding",
+            "delete_forwarding",
+        ],
+        render: offboard,
+    },
+    Workflow {
+        name: "audit_domain",
+        title: "Audit domain",
+        description: "Review a domain for likely mistakes",
+        arguments: &[],
+        tools: &[
+            "list_mailboxes",
+            "get_mailbox",
+            "list_aliases",
+            "list_rewrites",
+            "list_identities",
+            "list_forwardings",
+        ],
+        render: audit,
+    },
+    Workflow {
+        name: "troubleshoot_delivery",
+        title: "Troubleshoot delivery",
+        description: "Work out where mail to an address goes, and why it might not arrive",
+        arguments: &[(
+            "address",
+            "Address or local part that is not receiving mail",
+            true,
+        )],
+        tools: &[
+            "get_mailbox",
+            "list_mailboxes",
+            "list_identities",
+            "list_forwardings",
+            "get_alias",
+            "list_rewrites",
+        ],
+        render: troubleshoot,
+    },
+];
+
+impl Workflow {
+    pub fn prompt(&self) -> Prompt {
+        Prompt {
+            arguments: self
+                .arguments
+                .iter()
+                .chain([&DOMAIN])
+                .map(|(name, description, required)| PromptArgument {
+                    description: Some(description.to_string()),
+                    name: name.to_string(),
+                    required: Some(*required),
+                    title: None,
+                })
+                .collect(),
+            description: Some(self.description.to_string()),
+            meta: None,
+            name: self.name.to_string(),
+            title: Some(self.title.to_string()),
+        }
+    }
+
+    /// Renders the prompt, failing if a required argument is missing.
+    pub fn render(&self, arguments: Arguments) -> Result<GetPromptResult, String> {
+        for (name, _, required) in self.arguments {
+            if *required && arguments.get(*name).is_none_or(|v| v.is_empty()) {
+                return Err(format!("Missing required argument: {}", name));
+            }
+        }
+        let text = (self.render)(&arguments);
+        Ok(GetPromptResult {
+            description: Some(self.description.to_string()),
+            messages: vec![PromptMessage {
+                content: ContentBlock::TextContent(TextContent::new(text, None, None)),
+                role: Role::User,
+            }],
+            meta: None,
+        })
+    }
+}
+
+fn arg<'a>(arguments: &'a Arguments, name: &str) -> Option<&'a str> {
+    arguments
+        .get(name)
+        .map(String::as_str)
+        .filter(|v| !v.is_empty())
+}
+
+/// Names the domain a prompt works on, and how to pass it to tools.
+fn scope(domain: Option<&str>) -> (String, String) {
+    match domain {
+        Some(domain) => (
+            domain.to_string(),
+            format!("Pass domain \"{domain}\" to every tool call.\n\n"),
+        ),
+        None => ("the default domain".to_string(), String::new()),
+    }
+}
+
+fn onboard(arguments: &Arguments) -> String {
+    let local_part = arg(arguments, "local_part").unwrap_or_default();
+    let name = arg(arguments, "name").unwrap_or_default();
+    let (domain, note) = scope(arg(arguments, "domain"));
+    let mut text = format!(
+        "Onboard {name} with the mailbox {local_part} on {domain}.\n\n{note}\
+         1. Call `get_mailbox` with local_part \"{local_part}\" to make sure it does not exist yet. \
+         If it does, stop and tell me.\n\
+         2. Generate a strong random password of at least 20 characters and call `create_mailbox` \
+         with local_part \"{local_part}\", name \"{name}\" and that password. Never repeat the \
+         password in your replies: nobody should learn it from this conversation"
+    );
+    match arg(arguments, "recovery_email") {
+        Some(email) => text.push_str(&format!(
+            ". Also pass password_recovery_email \"{email}\", so {name} can choose their own \
+             password through password recovery.\n"
+        )),
+        None => text.push_str(
+            ". Ask me for a personal address of the employee first, and pass it as \
+             password_recovery_email so they can choose their own password through password \
+             recovery. If there is none, tell me to set a password in the Migadu admin panel \
+             and hand it to them myself.\n",
+        ),
+    }
+    match arg(arguments, "aliases") {
+        Some(aliases) => text.push_str(&format!(
+            "3. For each of these aliases: {aliases}, call `get_alias`, then `update_alias` with \
+             its destinations plus the new address. Keep every existing destination. If an alias \
+             does not exist, tell me instead of creating it.\n"
+        )),
+        None => text.push_str("3. Ask me whether the new address should join any aliases, and add it with `update_alias` if so.\n"),
+    }
+    text.push_str(
+        "\nFinish with a summary: the new address, the aliases it joined, and how the employee \
+         will set their password. Do not include the password.",
+    );
+    text
+}
+
+fn offboard(arguments: &Arguments) -> String {
+    let local_part = arg(arguments, "local_part").unwrap_or_default();
+    let (domain, note) = scope(arg(arguments, "domain"));
+    let mut text = format!(
+        "Offboard the owner of the mailbox {local_part} on {domain}. Do not delete anything \
+         without asking me first.\n\n{note}\
+         1. Call `get_mailbox` with local_part \"{local_part}\" and show me its settings.\n\
+         2. Call `update_mailbox` to set may_send, may_access_imap, may_access_pop3 and \
+         may_access_managesieve to false. The password cannot be changed from here, so tell me \
+         to reset it in the Migadu admin panel to end existing sessions.\n\
+         3. Call `list_aliases` and find every alias whose destinations include this mailbox's \
+         address. For each, call `update_alias` with the remaining destinations. If the mailbox \
+         is an alias's only destination, ask me what should replace it.\n\
+         4. Call `list_identities` and `list_forwardings` for mailbox \"{local_part}\" and list \
+         them for me. Offer to remove forwardings with `delete_forwarding`.\n"
+    );
+    match arg(arguments, "successor") {
+        Some(successor) => text.push_str(&format!(
+            "5. Mail should now reach {successor}: call `update_mailbox` with autorespond_active \
+             true and an autorespond_subject and autorespond_body telling senders to write to \
+             {successor}. Ask me whether to also forward new mail there with `create_forwarding`.\n"
+        )),
+        None => text.push_str("5. Ask me who should receive this mailbox's mail from now on.\n"),
+    }
+    text.push_str(
+        "\nFinish with a summary of every change made, and what is left for me to decide.",
+    );
+    text
+}
+
+fn audit(arguments: &Arguments) -> String {
+    let (domain, note) = scope(arg(arguments, "domain"));
+    format!(
+        "Audit {domain} and report likely mistakes. Only read; do not change anything.\n\n{note}\
+         1. Call `list_mailboxes`, `list_aliases` and `list_rewrites`.\n\
+         2. Call `get_mailbox` for each mailbox, and `list_identities` and `list_forwardings` for it.\n\
+         3. Check for:\n\
+         - alias or rewrite destinations on this domain that no mailbox, identity or alias receives\n\
+         - aliases whose destinations lead back to themselves\n\
+         - rewrite rules that an earlier rule (lower order_num) always matches first\n\
+         - mailboxes without a password_recovery_email\n\
+         - autoresponders still active after autorespond_expires_on\n\
+         - forwardings that are blocked or not active\n\
+         - identities that may neither send nor receive\n\n\
+         Report each finding with the resource it concerns and a suggested fix, most serious first."
+    )
+}
+
+fn troubleshoot(arguments: &Arguments) -> String {
+    let address = arg(arguments, "address").unwrap_or_default();
+    let (local_part, address_domain) = match address.split_once('@') {
+        Some((local_part, domain)) => (local_part, Some(domain)),
+        None => (address, None),
+    };
+    let (_, note) = scope(arg(arguments, "domain").or(address_domain));
+    format!(
+        "Work out where mail to {address} goes and why it might not arrive. Only read; do not \
+         change anything. {note}Mail to a local part is handled by the first of these that \
+         matches:\n\n\
+         1. A mailbox: call `get_mailbox` with local_part \"{local_part}\". If it exists, check \
+         may_receive and its spam settings and denylists, and call `list_forwardings` to see \
+         where copies go and whether those forwardings are active.\n\
+         2. An identity: call `list_mailboxes`, then `list_identities` for each until one has \
+         local_part \"{local_part}\". Its mail goes to the owning mailbox if the identity may_receive.\n\
+         3. An alias: call `get_alias` with local_part \"{local_part}\" and follow each destination \
+         the same way, watching for loops.\n\
+         4. A rewrite rule: call `list_rewrites` and try the rules in order_num order; `*` matches \
+         any run of characters. The first match delivers to its destinations.\n\n\
+         If nothing matches, the mail is rejected. Explain the path the mail takes, step by step, \
+         and point out anything that would stop it arriving."
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workflow(name: &str) -> &'static Workflow {
+        WORKFLOWS.iter().find(|w| w.name == name).unwrap()
+    }
+
+    fn render(name: &str, arguments: &[(&str, &str)]) -> Result<String, String> {
+        let arguments = arguments
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let result = workflow(name).render(arguments)?;
+        match &result.messages[0].content {
+            ContentBlock::TextContent(text) => Ok(text.text.clone()),
+            other => panic!("Unexpected content {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_required_arguments_are_refused() {
+        assert_eq!(
+            render("onboard_employee", &[("local_part", "alice")]),
+            Err("Missing required argument: name".to_string())
+        );
+        assert_eq!(
+            render("onboard_employee", &[("local_part", ""), ("name", "Alice")]),
+            Err("Missing required argument: local_part".to_string())
+        );
+        assert_eq!(
+            render("troubleshoot_delivery", &[]),
+            Err("Missing required argument: address".to_string())
+        );
+        assert!(render("audit_domain", &[]).is_ok());
+    }
+
+    #[test]
+    fn test_onboarding_keeps_the_password_out_of_the_conversation() {
+        let text = render(
+            "onboard_employee",
+            &[
+                ("local_part", "alice"),
+                ("name", "Alice"),
+                ("recovery_email", "alice@home.example"),
+            ],
+        )
+        .unwrap();
+        assert!(
+            text.contains("password_recovery_email \"alice@home.example\""),
+            "{}",
+            text
+        );
+        assert!(text.contains("Never repeat the password"), "{}", text);
+        assert!(text.ends_with("Do not include the password."), "{}", text);
+        assert!(!text.contains("its password"), "{}", text);
+
+        let text = render(
+            "onboard_employee",
+            &[("local_part", "alice"), ("name", "Alice")],
+        )
+        .unwrap();
+        assert!(text.contains("Ask me for a personal address"), "{}", text);
+    }
+
+    #[test]
+    fn test_rendering_fills_in_arguments() {
+        let text = render(
+            "onboard_employee",
+            &[
+                ("local_part", "alice"),
+                ("name", "Alice"),
+                ("aliases", "team,sales"),
+                ("domain", "example.com"),
+            ],
+        )
+        .unwrap();
+        assert!(
+            text.starts_with("Onboard Alice with the mailbox alice on example.com."),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("Pass domain \"example.com\" to every tool call."),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("For each of these aliases: team,sales"),
+            "{}",
+            text
+        );
+
+        let text = render("troubleshoot_delivery", &[("address", "info@example.org")]).unwrap();
+        assert!(text.contains("local_part \"info\""), "{}", text);
+        assert!(text.contains("Pass domain \"example.org\""), "{}", text);
+
+        let text = render("offboard_employee", &[("local_part", "bob")]).unwrap();
+        assert!(
+            text.contains("Ask me who should receive this mailbox's mail"),
+            "{}",
+            text
+        );
+        // `update_mailbox` takes no password, so the user resets it.
+        assert!(
+            text.contains("reset it in the Migadu admin panel"),
+            "{}",
+            text
+        );
+        assert!(!text.contains("random password"), "{}", text);
+    }
+
+    #[test]
+    fn test_every_prompt_takes_a_domain() {
+        for workflow in WORKFLOWS {
+            let prompt = workflow.prompt();
+            let domain = prompt
+                .arguments
+                .iter()
+                .find(|a| a.name == "domain")
+                .unwrap();
+            assert_eq!(domain.required, Some(false), "{}", workflow.name);
+        }
+    }
+}
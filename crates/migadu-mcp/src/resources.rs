@@ -0,0 +1,272 @@
+//! Migadu entities as MCP resources, addressed as `migadu://{domain}/...`.
+
+use migadu_client::{MigaduClient, Result};
+use rust_mcp_sdk::schema::{Resource, ResourceTemplate};
+use serde::Serialize;
+
+use crate::args;
+
+const SCHEME: &str = "migadu://";
+pub const MIME_TYPE: &str = "application/json";
+
+/// What a resource URI points at, within its domain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    Mailboxes,
+    Mailbox(String),
+    Identities(String),
+    Forwardings(String),
+    Aliases,
+    Alias(String),
+    Rewrites,
+    Rewrite(String),
+}
+
+impl Target {
+    /// The tool that reads the same thing; the resource is only offered if it is.
+    pub fn tool(&self) -> &'static str {
+        match self {
+            Target::Mailboxes => "list_mailboxes",
+            Target::Mailbox(_) => "get_mailbox",
+            Target::Identities(_) => "list_identities",
+            Target::Forwardings(_) => "list_forwardings",
+            Target::Aliases => "list_aliases",
+            Target::Alias(_) => "get_alias",
+            Target::Rewrites => "list_rewrites",
+            Target::Rewrite(_) => "get_rewrite",
+        }
+    }
+}
+
+/// Splits a resource URI into its domain and target.
+pub fn parse(uri: &str) -> Option<(String, Target)> {
+    let rest = uri.strip_prefix(SCHEME)?;
+    let segments: Vec<&str> = rest.split('/').collect();
+    if segments.iter().any(|s| args::check_segment(s).is_err()) {
+        return None;
+    }
+    let target = match segments[1..] {
+        ["mailboxes"] => Target::Mailboxes,
+        ["mailboxes", local_part] => Target::Mailbox(local_part.to_string()),
+        ["mailboxes", local_part, "identities"] => Target::Identities(local_part.to_string()),
+        ["mailboxes", local_part, "forwardings"] => Target::Forwardings(local_part.to_string()),
+        ["aliases"] => Target::Aliases,
+        ["aliases", local_part] => Target::Alias(local_part.to_string()),
+        ["rewrites"] => Target::Rewrites,
+        ["rewrites", name] => Target::Rewrite(name.to_string()),
+        _ => return None,
+    };
+    Some((segments[0].to_string(), target))
+}
+
+/// Builds the URI of `path` within `domain`.
+pub fn uri(domain: &str, path: &str) -> String {
+    format!("{}{}/{}", SCHEME, domain, path)
+}
+
+/// Fetches a resource and returns it as pretty JSON.
+pub async fn read(client: &MigaduClient, domain: &str, target: &Target) -> Result<String> {
+    match target {
+        Target::Mailboxes => to_json(client.list_mailboxes(domain).await),
+        Target::Mailbox(local_part) => to_json(client.get_mailbox(domain, local_part).await),
+        Target::Identities(local_part) => to_json(client.list_identities(domain, local_part).await),
+        Target::Forwardings(local_part) => {
+            to_json(client.list_forwardings(domain, local_part).await)
+        }
+        Target::Aliases => to_json(client.list_aliases(domain).await),
+        Target::Alias(local_part) => to_json(client.get_alias(domain, local_part).await),
+        Target::Rewrites => to_json(client.list_rewrites(domain).await),
+        Target::Rewrite(name) => to_json(client.get_rewrite(domain, name).await),
+    }
+}
+
+fn to_json<T: Serialize>(result: Result<T>) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&result?).unwrap())
+}
+
+/// Lists the resources of `domain` whose tool is `allowed`: its collections and each of
+/// its mailboxes.
+pub async fn list(
+    client: &MigaduClient,
+    domain: &str,
+    allowed: impl Fn(&str) -> bool,
+) -> Result<Vec<Resource>> {
+    let mut resources: Vec<Resource> = [
+        (Target::Mailboxes, "mailboxes", "Mailboxes"),
+        (Target::Aliases, "aliases", "Aliases"),
+        (Target::Rewrites, "rewrites", "Rewrite rules"),
+    ]
+    .into_iter()
+    .filter(|(target, _, _)| allowed(target.tool()))
+    .map(|(_, path, name)| resource(uri(domain, path), format!("{} of {}", name, domain)))
+    .collect();
+    if allowed(Target::Mailboxes.tool()) && allowed(Target::Mailbox(String::new()).tool()) {
+        for mailbox in client.list_mailboxes(domain).await? {
+            let path = format!("mailboxes/{}", mailbox.local_part);
+            resources.push(resource(uri(domain, &path), mailbox.address));
+        }
+    }
+    Ok(resources)
+}
+
+fn resource(uri: String, name: String) -> Resource {
+    Resource {
+        annotations: None,
+        description: None,
+        meta: None,
+        mime_type: Some(MIME_TYPE.to_string()),
+        name,
+        size: None,
+        title: None,
+        uri,
+    }
+}
+
+/// Templates for every kind of resource whose tool is `allowed`.
+pub fn templates(allowed: impl Fn(&str) -> bool) -> Vec<ResourceTemplate> {
+    [
+        ("mailboxes", "Mailboxes", "Every mailbox of a domain"),
+        (
+            "mailboxes/{local_part}",
+            "Mailbox",
+            "Configuration of a mailbox",
+        ),
+        (
+            "mailboxes/{local_part}/identities",
+            "Identities",
+            "Identities of a mailbox",
+        ),
+        (
+            "mailboxes/{local_part}/forwardings",
+            "Forwardings",
+            "Forwardings of a mailbox",
+        ),
+        ("aliases", "Aliases", "Every alias of a domain"),
+        (
+            "aliases/{local_part}",
+            "Alias",
+            "An alias and its destinations",
+        ),
+        (
+            "rewrites",
+            "Rewrite rules",
+            "Every rewrite rule of a domain",
+        ),
+        (
+            "rewrites/{name}",
+            "Rewrite rule",
+            "A rewrite rule and its destinations",
+        ),
+    ]
+    .into_iter()
+    .filter(|(path, _, _)| {
+        parse(&uri("domain", path)).is_some_and(|(_, target)| allowed(target.tool()))
+    })
+    .map(|(path, name, description)| ResourceTemplate {
+        annotations: None,
+        description: Some(description.to_string()),
+        meta: None,
+        mime_type: Some(MIME_TYPE.to_string()),
+        name: name.to_string(),
+        title: None,
+        uri_template: uri("{domain}", path),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_collections_and_entities() {
+        assert_eq!(
+            parse("migadu://example.com/mailboxes"),
+            Some(("example.com".to_string(), Target::Mailboxes))
+        );
+        assert_eq!(
+            parse("migadu://example.com/aliases/info"),
+            Some(("example.com".to_string(), Target::Alias("info".to_string())))
+        );
+        assert_eq!(
+            parse("migadu://example.com/rewrites/support"),
+            Some((
+                "example.com".to_string(),
+                Target::Rewrite("support".to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_mailbox_suffixes() {
+        let parse_target = |uri: &str| parse(uri).map(|(_, target)| target);
+        assert_eq!(
+            parse_target("migadu://example.com/mailboxes/alice"),
+            Some(Target::Mailbox("alice".to_string()))
+        );
+        assert_eq!(
+            parse_target("migadu://example.com/mailboxes/alice/identities"),
+            Some(Target::Identities("alice".to_string()))
+        );
+        assert_eq!(
+            parse_target("migadu://example.com/mailboxes/alice/forwardings"),
+            Some(Target::Forwardings("alice".to_string()))
+        );
+        assert_eq!(
+            parse_target("migadu://example.com/mailboxes/alice/aliases"),
+            None
+        );
+        assert_eq!(
+            parse_target("migadu://example.com/aliases/info/identities"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_empty_and_dot_segments() {
+        for uri in [
+            "migadu://example.com/mailboxes/..",
+            "migadu://example.com/mailboxes/./identities",
+            "migadu://../mailboxes",
+            "migadu:///mailboxes",
+            "migadu://example.com//mailboxes",
+            "migadu://example.com/mailboxes/",
+            "migadu://example.com/mailboxes//identities",
+        ] {
+            assert_eq!(parse(uri), None, "{}", uri);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_uris() {
+        for uri in [
+            "migadu://example.com",
+            "migadu://example.com/domains",
+            "migadu://example.com/rewrites/support/destinations",
+            "https://example.com/mailboxes",
+            "mailboxes",
+        ] {
+            assert_eq!(parse(uri), None, "{}", uri);
+        }
+    }
+
+    #[test]
+    fn test_templates_follow_allowed_tools() {
+        let names = |templates: Vec<ResourceTemplate>| {
+            templates
+                .into_iter()
+                .map(|t| t.uri_template)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(templates(|_| true).len(), 8);
+        assert_eq!(
+            names(templates(
+                |tool| tool == "get_mailbox" || tool == "list_aliases"
+            )),
+            [
+                "migadu://{domain}/mailboxes/{local_part}",
+                "migadu://{domain}/aliases"
+            ]
+        );
+    }
+}